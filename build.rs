@@ -0,0 +1,28 @@
+use std::process::Command;
+
+fn main() {
+    // Migrations are embedded in the binary, so changes to them must trigger
+    // a rebuild.
+    println!("cargo:rerun-if-changed=migrations");
+
+    // Builds outside a git checkout, such as in a container, can pass the
+    // commit in through the environment instead.
+    println!("cargo:rerun-if-env-changed=TAPSTER_GIT_COMMIT");
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+
+    let commit = std::env::var("TAPSTER_GIT_COMMIT").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|commit| commit.trim().to_string())
+    });
+
+    println!(
+        "cargo:rustc-env=TAPSTER_GIT_COMMIT={}",
+        commit.unwrap_or("unknown".to_string())
+    );
+}
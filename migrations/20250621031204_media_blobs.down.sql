@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+ALTER TABLE media
+DROP COLUMN IF EXISTS blob_id;
+
+DROP TABLE IF EXISTS blobs;
@@ -0,0 +1,27 @@
+-- Add up migration script here
+
+CREATE TABLE IF NOT EXISTS blobs (
+  blob_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  hash CHAR(64) UNIQUE,
+  object_key VARCHAR(255) NOT NULL UNIQUE,
+  size BIGINT NOT NULL DEFAULT 0,
+  reference_count INTEGER NOT NULL DEFAULT 0 CHECK(reference_count >= 0),
+  created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+ALTER TABLE media
+ADD COLUMN blob_id UUID REFERENCES blobs(blob_id) ON DELETE RESTRICT;
+
+-- Media uploaded before deduplication keep their object keyed by media id and
+-- have no known hash.
+INSERT INTO blobs (object_key, size, reference_count)
+SELECT media_id::text, size, 1
+FROM media;
+
+UPDATE media m
+SET blob_id = b.blob_id
+FROM blobs b
+WHERE b.object_key = m.media_id::text;
+
+ALTER TABLE media
+ALTER COLUMN blob_id SET NOT NULL;
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+DROP INDEX IF EXISTS media_user_id_created_at_idx;
+
+ALTER TABLE users
+DROP COLUMN IF EXISTS storage_quota;
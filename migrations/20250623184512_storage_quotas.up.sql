@@ -0,0 +1,7 @@
+-- Add up migration script here
+
+-- A NULL quota falls back to the server's default.
+ALTER TABLE users
+ADD COLUMN storage_quota BIGINT CHECK(storage_quota >= 0);
+
+CREATE INDEX IF NOT EXISTS media_user_id_created_at_idx ON media (user_id, created_at);
@@ -0,0 +1,7 @@
+-- Add down migration script here
+
+ALTER TABLE media
+DROP COLUMN IF EXISTS dominant_color,
+DROP COLUMN IF EXISTS blurhash,
+DROP COLUMN IF EXISTS height,
+DROP COLUMN IF EXISTS width;
@@ -0,0 +1,7 @@
+-- Add up migration script here
+
+ALTER TABLE media
+ADD COLUMN width INTEGER CHECK(width > 0),
+ADD COLUMN height INTEGER CHECK(height > 0),
+ADD COLUMN blurhash VARCHAR(128),
+ADD COLUMN dominant_color CHAR(7);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE units
+DROP COLUMN IF EXISTS base_quantity,
+DROP COLUMN IF EXISTS dimension;
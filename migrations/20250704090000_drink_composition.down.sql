@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+ALTER TABLE recipes DROP COLUMN IF EXISTS method;
+
+ALTER TABLE ingredients
+DROP COLUMN IF EXISTS acid,
+DROP COLUMN IF EXISTS sugar,
+DROP COLUMN IF EXISTS abv;
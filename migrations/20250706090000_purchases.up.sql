@@ -0,0 +1,61 @@
+-- Add up migration script here
+
+-- The baseline let a bar stock an ingredient more than once. Each bar's
+-- duplicates are merged into the first of them, whose quantity takes on theirs
+-- converted to its unit. A quantity measured in a unit of the other dimension
+-- cannot be converted, and goes with its row.
+UPDATE bar_ingredients
+SET quantity = quantity + (
+  SELECT SUM(d.quantity * du.base_quantity / ku.base_quantity)
+  FROM bar_ingredients d
+  JOIN units du ON du.unit_id = d.unit_id
+  JOIN units ku ON ku.unit_id = bar_ingredients.unit_id
+  WHERE d.bar_id = bar_ingredients.bar_id
+    AND d.ingredient_id = bar_ingredients.ingredient_id
+    AND d.bar_ingredient_id > bar_ingredients.bar_ingredient_id
+    AND du.dimension = ku.dimension
+)
+WHERE NOT EXISTS (
+  SELECT 1
+  FROM bar_ingredients d
+  WHERE d.bar_id = bar_ingredients.bar_id
+    AND d.ingredient_id = bar_ingredients.ingredient_id
+    AND d.bar_ingredient_id < bar_ingredients.bar_ingredient_id
+)
+AND EXISTS (
+  SELECT 1
+  FROM bar_ingredients d
+  JOIN units du ON du.unit_id = d.unit_id
+  JOIN units ku ON ku.unit_id = bar_ingredients.unit_id
+  WHERE d.bar_id = bar_ingredients.bar_id
+    AND d.ingredient_id = bar_ingredients.ingredient_id
+    AND d.bar_ingredient_id > bar_ingredients.bar_ingredient_id
+    AND du.dimension = ku.dimension
+);
+
+DELETE FROM bar_ingredients
+WHERE EXISTS (
+  SELECT 1
+  FROM bar_ingredients d
+  WHERE d.bar_id = bar_ingredients.bar_id
+    AND d.ingredient_id = bar_ingredients.ingredient_id
+    AND d.bar_ingredient_id < bar_ingredients.bar_ingredient_id
+);
+
+-- A bar stocks each ingredient once.
+CREATE UNIQUE INDEX IF NOT EXISTS bar_ingredients_bar_id_ingredient_id
+ON bar_ingredients (bar_id, ingredient_id);
+
+-- What was paid for a quantity of a bar ingredient, in whichever unit it was
+-- bought in.
+CREATE TABLE IF NOT EXISTS purchases (
+  purchase_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  price DOUBLE PRECISION NOT NULL CHECK(price >= 0),
+  quantity REAL NOT NULL CHECK(quantity > 0),
+  store VARCHAR(64) CHECK(length(store) >= 1),
+  purchased_at DATE NOT NULL DEFAULT CURRENT_DATE,
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+
+  bar_ingredient_id UUID NOT NULL REFERENCES bar_ingredients(bar_ingredient_id) ON DELETE CASCADE,
+  unit_id UUID NOT NULL REFERENCES units(unit_id) ON DELETE CASCADE
+);
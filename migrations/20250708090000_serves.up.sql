@@ -0,0 +1,24 @@
+-- Add up migration script here
+
+-- A number of servings of a recipe made at a bar. Undoing one puts what it
+-- took back into stock and keeps it in the history.
+CREATE TABLE IF NOT EXISTS serves (
+  serve_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  servings REAL NOT NULL CHECK(servings > 0),
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+  undone_at TIMESTAMP,
+
+  bar_id UUID NOT NULL REFERENCES bars(bar_id) ON DELETE CASCADE,
+  recipe_id UUID NOT NULL REFERENCES recipes(recipe_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS serves_bar_id_created_at ON serves (bar_id, created_at);
+
+-- What a serve took from each bar ingredient, in the unit it is stocked in.
+CREATE TABLE IF NOT EXISTS serve_deductions (
+  serve_deduction_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  quantity REAL NOT NULL CHECK(quantity > 0),
+
+  serve_id UUID NOT NULL REFERENCES serves(serve_id) ON DELETE CASCADE,
+  bar_ingredient_id UUID NOT NULL REFERENCES bar_ingredients(bar_ingredient_id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+ALTER TABLE bar_ingredients
+DROP COLUMN IF EXISTS bottle_size,
+DROP COLUMN IF EXISTS minimum,
+DROP COLUMN IF EXISTS par;
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS bottles;
+ALTER TABLE ingredients DROP COLUMN IF EXISTS shelf_life_id;
+DROP TABLE IF EXISTS shelf_lives;
@@ -0,0 +1,29 @@
+-- Add up migration script here
+
+-- How many days a type of ingredient keeps once opened, such as vermouth for
+-- 30 days or fresh juice for a day.
+CREATE TABLE IF NOT EXISTS shelf_lives (
+  shelf_life_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  name VARCHAR(64) NOT NULL CHECK(length(name) >= 2),
+  days INTEGER NOT NULL CHECK(days > 0),
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+
+  user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+  UNIQUE (user_id, name)
+);
+
+ALTER TABLE ingredients
+ADD COLUMN shelf_life_id UUID REFERENCES shelf_lives(shelf_life_id) ON DELETE SET NULL;
+
+-- A bottle of a bar ingredient, in the unit it is stocked in, with how full it
+-- is and where it is kept. Bottles without an opened_at are still sealed.
+CREATE TABLE IF NOT EXISTS bottles (
+  bottle_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  size REAL NOT NULL CHECK(size > 0),
+  fill REAL NOT NULL DEFAULT 1.0 CHECK(fill >= 0 AND fill <= 1),
+  location VARCHAR(64),
+  opened_at TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+
+  bar_ingredient_id UUID NOT NULL REFERENCES bar_ingredients(bar_ingredient_id) ON DELETE CASCADE
+);
@@ -0,0 +1,16 @@
+-- Add up migration script here
+
+-- A bottled product by its barcode, and the ingredient, size and strength of
+-- what is in it. Barcodes are kept as GTIN-14s, with UPCs and EANs padded with
+-- leading zeros. The catalogue is shared by every user and imported from a
+-- dataset.
+CREATE TABLE IF NOT EXISTS products (
+  barcode VARCHAR(14) PRIMARY KEY NOT NULL CHECK(length(barcode) = 14),
+  name VARCHAR(255) NOT NULL CHECK(length(name) >= 2),
+  ingredient VARCHAR(64) NOT NULL CHECK(length(ingredient) >= 2),
+  size REAL NOT NULL CHECK(size > 0),
+  abv REAL CHECK(abv BETWEEN 0 AND 100),
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+
+  unit_id UUID NOT NULL REFERENCES units(unit_id) ON DELETE CASCADE
+);
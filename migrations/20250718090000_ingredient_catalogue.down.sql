@@ -0,0 +1,8 @@
+-- Add down migration script here
+
+DELETE FROM ingredients WHERE user_id IS NULL;
+
+DROP INDEX IF EXISTS ingredients_user_id;
+
+ALTER TABLE ingredients
+ALTER COLUMN user_id SET NOT NULL;
@@ -0,0 +1,47 @@
+-- Add up migration script here
+
+-- Ingredients without an owner make up a catalogue shared by every user, who
+-- can stock them and use them in recipes but not change them.
+ALTER TABLE ingredients
+ALTER COLUMN user_id DROP NOT NULL;
+
+CREATE INDEX IF NOT EXISTS ingredients_user_id ON ingredients (user_id);
+
+-- The catalogue, with what each ingredient is made of per 100ml.
+INSERT INTO ingredients (ingredient_id, name, description, abv, sugar, acid)
+VALUES
+  ('00000000-0000-0000-0000-000000000201', 'gin', 'A juniper-led spirit.', 40, 0, 0),
+  ('00000000-0000-0000-0000-000000000202', 'vodka', 'A neutral spirit.', 40, 0, 0),
+  ('00000000-0000-0000-0000-000000000203', 'white rum', 'An unaged cane spirit.', 40, 0, 0),
+  ('00000000-0000-0000-0000-000000000204', 'aged rum', 'A cane spirit aged in oak.', 40, 0, 0),
+  ('00000000-0000-0000-0000-000000000205', 'tequila blanco', 'An unaged agave spirit.', 40, 0, 0),
+  ('00000000-0000-0000-0000-000000000206', 'mezcal', 'A smoky agave spirit.', 40, 0, 0),
+  ('00000000-0000-0000-0000-000000000207', 'bourbon', 'An American whiskey of mostly corn.', 45, 0, 0),
+  ('00000000-0000-0000-0000-000000000208', 'rye whiskey', 'A spicy whiskey of mostly rye.', 45, 0, 0),
+  ('00000000-0000-0000-0000-000000000209', 'scotch whisky', 'A whisky from Scotland.', 40, 0, 0),
+  ('00000000-0000-0000-0000-00000000020a', 'cognac', 'A grape brandy from Cognac.', 40, 0, 0),
+  ('00000000-0000-0000-0000-00000000020b', 'campari', 'A bitter red aperitivo.', 24, 24, 0),
+  ('00000000-0000-0000-0000-00000000020c', 'aperol', 'A bitter orange aperitivo.', 11, 19, 0),
+  ('00000000-0000-0000-0000-00000000020d', 'sweet vermouth', 'A sweet aromatised wine.', 16.5, 16, 0.6),
+  ('00000000-0000-0000-0000-00000000020e', 'dry vermouth', 'A dry aromatised wine.', 17.5, 3, 0.6),
+  ('00000000-0000-0000-0000-00000000020f', 'triple sec', 'An orange liqueur.', 40, 25, 0),
+  ('00000000-0000-0000-0000-000000000210', 'maraschino liqueur', 'A cherry liqueur.', 32, 35, 0),
+  ('00000000-0000-0000-0000-000000000211', 'green chartreuse', 'A herbal liqueur.', 55, 25, 0),
+  ('00000000-0000-0000-0000-000000000212', 'angostura bitters', 'Aromatic bitters.', 44.7, 4.2, 0),
+  ('00000000-0000-0000-0000-000000000213', 'orange bitters', 'Bitters of bitter orange peel.', 28, NULL, NULL),
+  ('00000000-0000-0000-0000-000000000214', 'lime juice', 'Freshly squeezed lime juice.', 0, 1.6, 6),
+  ('00000000-0000-0000-0000-000000000215', 'lemon juice', 'Freshly squeezed lemon juice.', 0, 1.6, 6),
+  ('00000000-0000-0000-0000-000000000216', 'orange juice', 'Freshly squeezed orange juice.', 0, 12.4, 0.8),
+  ('00000000-0000-0000-0000-000000000217', 'grapefruit juice', 'Freshly squeezed grapefruit juice.', 0, 10.4, 2.4),
+  ('00000000-0000-0000-0000-000000000218', 'simple syrup', 'Sugar and water, one to one by weight.', 0, 61.5, 0),
+  ('00000000-0000-0000-0000-000000000219', 'rich simple syrup', 'Sugar and water, two to one by weight.', 0, 87.5, 0),
+  ('00000000-0000-0000-0000-00000000021a', 'honey syrup', 'Honey and water, one to one by weight.', 0, 61.5, 0),
+  ('00000000-0000-0000-0000-00000000021b', 'demerara syrup', 'Demerara sugar and water, one to one by weight.', 0, 61.5, 0),
+  ('00000000-0000-0000-0000-00000000021c', 'grenadine', 'A pomegranate syrup.', 0, 61.5, 1),
+  ('00000000-0000-0000-0000-00000000021d', 'soda water', 'Carbonated water.', 0, 0, 0),
+  ('00000000-0000-0000-0000-00000000021e', 'tonic water', 'Carbonated water with quinine.', 0, 8.7, 0),
+  ('00000000-0000-0000-0000-00000000021f', 'ginger beer', 'A spicy carbonated ginger soda.', 0, 12, 0),
+  ('00000000-0000-0000-0000-000000000220', 'cola', 'A carbonated cola soda.', 0, 10.6, 0.1),
+  ('00000000-0000-0000-0000-000000000221', 'prosecco', 'A dry Italian sparkling wine.', 11, 1.5, 0.6),
+  ('00000000-0000-0000-0000-000000000222', 'egg white', 'The white of a fresh egg.', 0, 0, 0),
+  ('00000000-0000-0000-0000-000000000223', 'heavy cream', 'Cream of at least 36% fat.', 0, 3, 0);
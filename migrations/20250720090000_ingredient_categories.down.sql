@@ -0,0 +1,10 @@
+-- Add down migration script here
+
+ALTER TABLE recipe_ingredients DROP COLUMN IF EXISTS category_id;
+ALTER TABLE ingredients
+DROP COLUMN IF EXISTS flavours,
+DROP COLUMN IF EXISTS tags,
+DROP COLUMN IF EXISTS producer,
+DROP COLUMN IF EXISTS origin,
+DROP COLUMN IF EXISTS category_id;
+DROP TABLE IF EXISTS categories;
@@ -0,0 +1,140 @@
+-- Add up migration script here
+
+-- A taxonomy of ingredients, such as spirit, whiskey and rye whiskey. Categories
+-- without an owner are shared by every user, who can add their own beneath them.
+CREATE TABLE IF NOT EXISTS categories (
+  category_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  name VARCHAR(64) NOT NULL CHECK(length(name) >= 2),
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+
+  parent_id UUID REFERENCES categories(category_id) ON DELETE CASCADE,
+  user_id UUID REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS categories_user_id ON categories (user_id);
+
+-- Tags and flavour notes are free-form, such as `house` or `smoke`.
+ALTER TABLE ingredients
+ADD COLUMN category_id UUID REFERENCES categories(category_id) ON DELETE SET NULL,
+ADD COLUMN origin VARCHAR(64),
+ADD COLUMN producer VARCHAR(64),
+ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}',
+ADD COLUMN flavours TEXT[] NOT NULL DEFAULT '{}';
+
+-- A recipe line with a category takes any ingredient of it, such as any rye
+-- whiskey, with its ingredient standing in for the rest.
+ALTER TABLE recipe_ingredients
+ADD COLUMN category_id UUID REFERENCES categories(category_id) ON DELETE SET NULL;
+
+-- The shared taxonomy, parents first.
+INSERT INTO categories (category_id, name, parent_id)
+VALUES
+  ('00000000-0000-0000-0000-000000000301', 'spirit', NULL),
+  ('00000000-0000-0000-0000-000000000302', 'whiskey', '00000000-0000-0000-0000-000000000301'),
+  ('00000000-0000-0000-0000-000000000303', 'rye whiskey', '00000000-0000-0000-0000-000000000302'),
+  ('00000000-0000-0000-0000-000000000304', 'bourbon', '00000000-0000-0000-0000-000000000302'),
+  ('00000000-0000-0000-0000-000000000305', 'scotch whisky', '00000000-0000-0000-0000-000000000302'),
+  ('00000000-0000-0000-0000-000000000306', 'gin', '00000000-0000-0000-0000-000000000301'),
+  ('00000000-0000-0000-0000-000000000307', 'vodka', '00000000-0000-0000-0000-000000000301'),
+  ('00000000-0000-0000-0000-000000000308', 'rum', '00000000-0000-0000-0000-000000000301'),
+  ('00000000-0000-0000-0000-000000000309', 'agave spirit', '00000000-0000-0000-0000-000000000301'),
+  ('00000000-0000-0000-0000-00000000030a', 'tequila', '00000000-0000-0000-0000-000000000309'),
+  ('00000000-0000-0000-0000-00000000030b', 'mezcal', '00000000-0000-0000-0000-000000000309'),
+  ('00000000-0000-0000-0000-00000000030c', 'brandy', '00000000-0000-0000-0000-000000000301'),
+  ('00000000-0000-0000-0000-00000000030d', 'cognac', '00000000-0000-0000-0000-00000000030c'),
+  ('00000000-0000-0000-0000-00000000030e', 'liqueur', NULL),
+  ('00000000-0000-0000-0000-00000000030f', 'amaro', '00000000-0000-0000-0000-00000000030e'),
+  ('00000000-0000-0000-0000-000000000310', 'orange liqueur', '00000000-0000-0000-0000-00000000030e'),
+  ('00000000-0000-0000-0000-000000000311', 'herbal liqueur', '00000000-0000-0000-0000-00000000030e'),
+  ('00000000-0000-0000-0000-000000000312', 'cherry liqueur', '00000000-0000-0000-0000-00000000030e'),
+  ('00000000-0000-0000-0000-000000000313', 'wine', NULL),
+  ('00000000-0000-0000-0000-000000000314', 'vermouth', '00000000-0000-0000-0000-000000000313'),
+  ('00000000-0000-0000-0000-000000000315', 'sparkling wine', '00000000-0000-0000-0000-000000000313'),
+  ('00000000-0000-0000-0000-000000000316', 'bitters', NULL),
+  ('00000000-0000-0000-0000-000000000317', 'juice', NULL),
+  ('00000000-0000-0000-0000-000000000318', 'citrus juice', '00000000-0000-0000-0000-000000000317'),
+  ('00000000-0000-0000-0000-000000000319', 'syrup', NULL),
+  ('00000000-0000-0000-0000-00000000031a', 'mixer', NULL),
+  ('00000000-0000-0000-0000-00000000031b', 'soda', '00000000-0000-0000-0000-00000000031a'),
+  ('00000000-0000-0000-0000-00000000031c', 'dairy', NULL),
+  ('00000000-0000-0000-0000-00000000031d', 'egg', NULL);
+
+-- Files the ingredient catalogue under the taxonomy.
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000303'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000208');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000304'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000207');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000305'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000209');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000306'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000201');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000307'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000202');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000308'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000203', '00000000-0000-0000-0000-000000000204');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-00000000030a'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000205');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-00000000030b'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000206');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-00000000030d'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-00000000020a');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-00000000030f'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-00000000020b', '00000000-0000-0000-0000-00000000020c');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000310'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-00000000020f');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000311'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000211');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000312'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000210');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000314'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-00000000020d', '00000000-0000-0000-0000-00000000020e');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000315'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000221');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000316'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000212', '00000000-0000-0000-0000-000000000213');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000318'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000214', '00000000-0000-0000-0000-000000000215', '00000000-0000-0000-0000-000000000216', '00000000-0000-0000-0000-000000000217');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-000000000319'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000218', '00000000-0000-0000-0000-000000000219', '00000000-0000-0000-0000-00000000021a', '00000000-0000-0000-0000-00000000021b', '00000000-0000-0000-0000-00000000021c');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-00000000031b'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-00000000021d', '00000000-0000-0000-0000-00000000021e', '00000000-0000-0000-0000-00000000021f', '00000000-0000-0000-0000-000000000220');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-00000000031c'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000223');
+UPDATE ingredients SET category_id = '00000000-0000-0000-0000-00000000031d'
+WHERE ingredient_id IN ('00000000-0000-0000-0000-000000000222');
+
+-- Where the catalogue comes from and what it tastes of.
+UPDATE ingredients SET origin = 'england', flavours = ARRAY['citrus', 'floral', 'juniper']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000201';
+UPDATE ingredients SET origin = 'jamaica', flavours = ARRAY['banana', 'molasses', 'vanilla']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000204';
+UPDATE ingredients SET origin = 'mexico', flavours = ARRAY['agave', 'citrus', 'pepper']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000205';
+UPDATE ingredients SET origin = 'mexico', flavours = ARRAY['agave', 'earth', 'smoke']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000206';
+UPDATE ingredients SET origin = 'united states', flavours = ARRAY['caramel', 'oak', 'vanilla']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000207';
+UPDATE ingredients SET origin = 'united states', flavours = ARRAY['oak', 'pepper', 'spice']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000208';
+UPDATE ingredients SET origin = 'scotland', flavours = ARRAY['honey', 'malt', 'smoke']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000209';
+UPDATE ingredients SET origin = 'france', flavours = ARRAY['dried fruit', 'oak', 'vanilla']
+WHERE ingredient_id = '00000000-0000-0000-0000-00000000020a';
+UPDATE ingredients SET origin = 'italy', flavours = ARRAY['bitter', 'herbal', 'orange']
+WHERE ingredient_id = '00000000-0000-0000-0000-00000000020b';
+UPDATE ingredients SET origin = 'italy', flavours = ARRAY['bitter', 'orange', 'rhubarb']
+WHERE ingredient_id = '00000000-0000-0000-0000-00000000020c';
+UPDATE ingredients SET origin = 'italy', flavours = ARRAY['dried fruit', 'herbal', 'vanilla']
+WHERE ingredient_id = '00000000-0000-0000-0000-00000000020d';
+UPDATE ingredients SET origin = 'france', flavours = ARRAY['citrus', 'floral', 'herbal']
+WHERE ingredient_id = '00000000-0000-0000-0000-00000000020e';
+UPDATE ingredients SET origin = 'france', flavours = ARRAY['orange']
+WHERE ingredient_id = '00000000-0000-0000-0000-00000000020f';
+UPDATE ingredients SET origin = 'croatia', flavours = ARRAY['almond', 'cherry', 'floral']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000210';
+UPDATE ingredients SET origin = 'france', flavours = ARRAY['herbal', 'mint', 'spice']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000211';
+UPDATE ingredients SET origin = 'trinidad and tobago', flavours = ARRAY['bitter', 'cinnamon', 'clove']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000212';
+UPDATE ingredients SET origin = 'italy', flavours = ARRAY['apple', 'floral', 'pear']
+WHERE ingredient_id = '00000000-0000-0000-0000-000000000221';
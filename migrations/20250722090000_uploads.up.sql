@@ -0,0 +1,18 @@
+-- Add up migration script here
+
+-- Every upload a user makes, kept after its media is deleted so that deleting
+-- media does not hand back uploads within the hourly limit.
+CREATE TABLE IF NOT EXISTS uploads (
+  upload_id UUID PRIMARY KEY NOT NULL DEFAULT uuid_generate_v4(),
+  size BIGINT NOT NULL CHECK(size >= 0),
+  created_at TIMESTAMP NOT NULL DEFAULT now(),
+
+  user_id UUID NOT NULL REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS uploads_user_id_created_at_idx ON uploads (user_id, created_at);
+
+INSERT INTO uploads (size, created_at, user_id)
+SELECT size, created_at, user_id
+FROM media
+WHERE created_at > now() - INTERVAL '1 hour';
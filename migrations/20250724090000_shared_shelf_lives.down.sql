@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+DELETE FROM shelf_lives WHERE user_id IS NULL;
+
+ALTER TABLE shelf_lives
+ALTER COLUMN user_id SET NOT NULL;
@@ -0,0 +1,39 @@
+-- Add up migration script here
+
+-- Shelf lives without an owner are shared by every user, who can give them to
+-- their ingredients but not change them.
+ALTER TABLE shelf_lives
+ALTER COLUMN user_id DROP NOT NULL;
+
+INSERT INTO shelf_lives (shelf_life_id, name, days)
+VALUES
+  ('00000000-0000-0000-0000-000000000401', 'vermouth', 30),
+  ('00000000-0000-0000-0000-000000000402', 'fresh juice', 1),
+  ('00000000-0000-0000-0000-000000000403', 'syrup', 14);
+
+-- The catalogue's vermouths, fresh juices and syrups keep as long as these.
+UPDATE ingredients
+SET shelf_life_id = '00000000-0000-0000-0000-000000000401'
+WHERE ingredient_id IN (
+  '00000000-0000-0000-0000-00000000020d',
+  '00000000-0000-0000-0000-00000000020e'
+);
+
+UPDATE ingredients
+SET shelf_life_id = '00000000-0000-0000-0000-000000000402'
+WHERE ingredient_id IN (
+  '00000000-0000-0000-0000-000000000214',
+  '00000000-0000-0000-0000-000000000215',
+  '00000000-0000-0000-0000-000000000216',
+  '00000000-0000-0000-0000-000000000217'
+);
+
+UPDATE ingredients
+SET shelf_life_id = '00000000-0000-0000-0000-000000000403'
+WHERE ingredient_id IN (
+  '00000000-0000-0000-0000-000000000218',
+  '00000000-0000-0000-0000-000000000219',
+  '00000000-0000-0000-0000-00000000021a',
+  '00000000-0000-0000-0000-00000000021b',
+  '00000000-0000-0000-0000-00000000021c'
+);
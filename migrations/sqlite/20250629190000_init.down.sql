@@ -0,0 +1,15 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS bar_recipes;
+DROP TABLE IF EXISTS recipe_ingredients;
+DROP TABLE IF EXISTS recipes;
+DROP TABLE IF EXISTS bar_ingredients;
+DROP TABLE IF EXISTS ingredient_ingredients;
+DROP TABLE IF EXISTS ingredients;
+DROP TABLE IF EXISTS units;
+DROP TABLE IF EXISTS unit_systems;
+DROP TABLE IF EXISTS bars;
+DROP TABLE IF EXISTS profiles;
+DROP TABLE IF EXISTS media;
+DROP TABLE IF EXISTS blobs;
+DROP TABLE IF EXISTS users;
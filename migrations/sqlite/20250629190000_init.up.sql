@@ -0,0 +1,141 @@
+-- Add up migration script here
+
+-- The SQLite schema mirrors the Postgres one as of its latest migration. Ids
+-- are generated by the server and stored as 16 byte blobs, and timestamps are
+-- stored as ISO 8601 text so they sort and compare correctly.
+
+CREATE TABLE IF NOT EXISTS users (
+  user_id BLOB PRIMARY KEY NOT NULL,
+  storage_quota INTEGER CHECK(storage_quota >= 0),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS blobs (
+  blob_id BLOB PRIMARY KEY NOT NULL,
+  hash TEXT UNIQUE,
+  object_key TEXT NOT NULL UNIQUE,
+  size INTEGER NOT NULL DEFAULT 0,
+  reference_count INTEGER NOT NULL DEFAULT 0 CHECK(reference_count >= 0),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+);
+
+CREATE TABLE IF NOT EXISTS media (
+  media_id BLOB PRIMARY KEY NOT NULL,
+  size INTEGER NOT NULL DEFAULT 0,
+  mime_type TEXT NOT NULL DEFAULT 'application/octet-stream',
+  width INTEGER CHECK(width > 0),
+  height INTEGER CHECK(height > 0),
+  blurhash TEXT,
+  dominant_color TEXT,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  blob_id BLOB NOT NULL REFERENCES blobs(blob_id) ON DELETE RESTRICT,
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS media_user_id_created_at_idx ON media (user_id, created_at);
+
+CREATE TABLE IF NOT EXISTS profiles (
+  profile_id BLOB PRIMARY KEY NOT NULL,
+  first_name TEXT NOT NULL CHECK(length(first_name) BETWEEN 2 AND 64),
+  last_name TEXT NOT NULL CHECK(length(last_name) BETWEEN 2 AND 64),
+  email TEXT NOT NULL UNIQUE CHECK(length(email) BETWEEN 5 AND 255),
+
+  media_id BLOB REFERENCES media(media_id) ON DELETE SET NULL,
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS bars (
+  bar_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  media_id BLOB REFERENCES media(media_id) ON DELETE SET NULL,
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS unit_systems (
+  unit_system_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64)
+);
+
+CREATE TABLE IF NOT EXISTS units (
+  unit_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64),
+  abbreviation TEXT NOT NULL CHECK(length(abbreviation) BETWEEN 1 AND 64),
+
+  unit_system_id BLOB REFERENCES unit_systems(unit_system_id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS ingredients (
+  ingredient_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64),
+  description TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  media_id BLOB REFERENCES media(media_id) ON DELETE SET NULL,
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS ingredient_ingredients (
+  ingredient_ingredient_id BLOB PRIMARY KEY NOT NULL,
+  parts INTEGER NOT NULL CHECK(parts > 0),
+
+  ingredient_id BLOB NOT NULL REFERENCES ingredients(ingredient_id) ON DELETE CASCADE,
+  compound_ingredient_id BLOB NOT NULL REFERENCES ingredients(ingredient_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS bar_ingredients (
+  bar_ingredient_id BLOB PRIMARY KEY NOT NULL,
+  quantity REAL NOT NULL DEFAULT 1.0,
+
+  bar_id BLOB NOT NULL REFERENCES bars(bar_id) ON DELETE CASCADE,
+  ingredient_id BLOB NOT NULL REFERENCES ingredients(ingredient_id) ON DELETE CASCADE,
+  unit_id BLOB NOT NULL REFERENCES units(unit_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS recipes (
+  recipe_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64),
+  description TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+  media_id BLOB REFERENCES media(media_id) ON DELETE SET NULL
+);
+
+CREATE TABLE IF NOT EXISTS recipe_ingredients (
+  recipe_ingredient_id BLOB PRIMARY KEY NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+  quantity REAL NOT NULL DEFAULT 1.0,
+
+  recipe_id BLOB NOT NULL REFERENCES recipes(recipe_id) ON DELETE CASCADE,
+  ingredient_id BLOB NOT NULL REFERENCES ingredients(ingredient_id) ON DELETE CASCADE,
+  unit_id BLOB NOT NULL REFERENCES units(unit_id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS bar_recipes (
+  bar_recipe_id BLOB PRIMARY KEY NOT NULL,
+  description TEXT NOT NULL CHECK(length(description) BETWEEN 2 AND 255),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  bar_id BLOB NOT NULL REFERENCES bars(bar_id) ON DELETE CASCADE,
+  recipe_id BLOB NOT NULL REFERENCES recipes(recipe_id) ON DELETE CASCADE
+);
+
+-- Unit ids are fixed so they match across installs.
+INSERT INTO unit_systems (unit_system_id, name)
+VALUES
+  (X'00000000000000000000000000000001', 'metric'),
+  (X'00000000000000000000000000000002', 'imperial');
+
+INSERT INTO units (unit_id, name, abbreviation, unit_system_id)
+VALUES
+  (X'00000000000000000000000000000101', 'fluid ounce', 'oz', X'00000000000000000000000000000002'),
+  (X'00000000000000000000000000000102', 'milliliter', 'ml', X'00000000000000000000000000000001'),
+  (X'00000000000000000000000000000103', 'barspoon', 'barspoon', NULL),
+  (X'00000000000000000000000000000104', 'dash', 'dash', NULL),
+  (X'00000000000000000000000000000105', 'liter', 'l', X'00000000000000000000000000000001'),
+  (X'00000000000000000000000000000106', 'cup', 'cup', X'00000000000000000000000000000002'),
+  (X'00000000000000000000000000000107', 'milligram', 'mg', X'00000000000000000000000000000001'),
+  (X'00000000000000000000000000000108', 'gram', 'g', X'00000000000000000000000000000001');
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+ALTER TABLE units DROP COLUMN base_quantity;
+ALTER TABLE units DROP COLUMN dimension;
@@ -0,0 +1,15 @@
+-- Add up migration script here
+
+-- Every unit measures either a volume or a mass, and converts to the base
+-- unit of its dimension (milliliters or grams) by `base_quantity`.
+ALTER TABLE units ADD COLUMN dimension TEXT NOT NULL DEFAULT 'volume' CHECK(dimension IN ('volume', 'mass'));
+ALTER TABLE units ADD COLUMN base_quantity REAL NOT NULL DEFAULT 1.0 CHECK(base_quantity > 0);
+
+UPDATE units SET dimension = 'volume', base_quantity = 29.5735 WHERE abbreviation = 'oz';
+UPDATE units SET dimension = 'volume', base_quantity = 1.0 WHERE abbreviation = 'ml';
+UPDATE units SET dimension = 'volume', base_quantity = 5.0 WHERE abbreviation = 'barspoon';
+UPDATE units SET dimension = 'volume', base_quantity = 0.92 WHERE abbreviation = 'dash';
+UPDATE units SET dimension = 'volume', base_quantity = 1000.0 WHERE abbreviation = 'l';
+UPDATE units SET dimension = 'volume', base_quantity = 236.588 WHERE abbreviation = 'cup';
+UPDATE units SET dimension = 'mass', base_quantity = 0.001 WHERE abbreviation = 'mg';
+UPDATE units SET dimension = 'mass', base_quantity = 1.0 WHERE abbreviation = 'g';
@@ -0,0 +1,6 @@
+-- Add down migration script here
+
+ALTER TABLE recipes DROP COLUMN method;
+ALTER TABLE ingredients DROP COLUMN acid;
+ALTER TABLE ingredients DROP COLUMN sugar;
+ALTER TABLE ingredients DROP COLUMN abv;
@@ -0,0 +1,11 @@
+-- Add up migration script here
+
+-- What an ingredient is made of, per 100ml: alcohol by volume, and grams of
+-- sugar and of acid. Unknown when null, and resolved through the parts of a
+-- compound ingredient.
+ALTER TABLE ingredients ADD COLUMN abv REAL CHECK(abv BETWEEN 0 AND 100);
+ALTER TABLE ingredients ADD COLUMN sugar REAL CHECK(sugar >= 0);
+ALTER TABLE ingredients ADD COLUMN acid REAL CHECK(acid >= 0);
+
+-- How a recipe is mixed, which decides how much it is diluted by ice.
+ALTER TABLE recipes ADD COLUMN method TEXT CHECK(method IN ('shaken', 'stirred', 'built', 'blended'));
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS purchases;
+
+DROP INDEX IF EXISTS bar_ingredients_bar_id_ingredient_id;
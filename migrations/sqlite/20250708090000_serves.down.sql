@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS serve_deductions;
+DROP TABLE IF EXISTS serves;
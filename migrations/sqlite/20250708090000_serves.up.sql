@@ -0,0 +1,24 @@
+-- Add up migration script here
+
+-- A number of servings of a recipe made at a bar. Undoing one puts what it
+-- took back into stock and keeps it in the history.
+CREATE TABLE IF NOT EXISTS serves (
+  serve_id BLOB PRIMARY KEY NOT NULL,
+  servings REAL NOT NULL CHECK(servings > 0),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+  undone_at TEXT,
+
+  bar_id BLOB NOT NULL REFERENCES bars(bar_id) ON DELETE CASCADE,
+  recipe_id BLOB NOT NULL REFERENCES recipes(recipe_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS serves_bar_id_created_at ON serves (bar_id, created_at);
+
+-- What a serve took from each bar ingredient, in the unit it is stocked in.
+CREATE TABLE IF NOT EXISTS serve_deductions (
+  serve_deduction_id BLOB PRIMARY KEY NOT NULL,
+  quantity REAL NOT NULL CHECK(quantity > 0),
+
+  serve_id BLOB NOT NULL REFERENCES serves(serve_id) ON DELETE CASCADE,
+  bar_ingredient_id BLOB NOT NULL REFERENCES bar_ingredients(bar_ingredient_id) ON DELETE CASCADE
+);
@@ -0,0 +1,5 @@
+-- Add down migration script here
+
+ALTER TABLE bar_ingredients DROP COLUMN bottle_size;
+ALTER TABLE bar_ingredients DROP COLUMN minimum;
+ALTER TABLE bar_ingredients DROP COLUMN par;
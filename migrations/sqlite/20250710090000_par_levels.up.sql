@@ -0,0 +1,8 @@
+-- Add up migration script here
+
+-- Stock levels of a bar ingredient, in the unit it is stocked in: the par to
+-- restock up to, the minimum to restock at, and the size of the bottles it
+-- comes in. Without a minimum, anything below par is restocked.
+ALTER TABLE bar_ingredients ADD COLUMN par REAL CHECK(par >= 0);
+ALTER TABLE bar_ingredients ADD COLUMN minimum REAL CHECK(minimum >= 0);
+ALTER TABLE bar_ingredients ADD COLUMN bottle_size REAL CHECK(bottle_size > 0);
@@ -0,0 +1,4 @@
+-- Add down migration script here
+
+DROP TABLE IF EXISTS stock_counts;
+DROP TABLE IF EXISTS stock_takes;
@@ -0,0 +1,30 @@
+-- Add up migration script here
+
+-- A count of a bar's stock. Counts are recorded while it is open, closing it
+-- snapshots what the bar expected to have, and applying it corrects the stock
+-- by the difference.
+CREATE TABLE IF NOT EXISTS stock_takes (
+  stock_take_id BLOB PRIMARY KEY NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+  closed_at TEXT,
+  applied_at TEXT,
+
+  bar_id BLOB NOT NULL REFERENCES bars(bar_id) ON DELETE CASCADE
+);
+
+-- A bar counts one stock take at a time.
+CREATE UNIQUE INDEX IF NOT EXISTS stock_takes_open_bar_id
+ON stock_takes (bar_id) WHERE closed_at IS NULL;
+
+-- What was counted of a bar ingredient, in the unit it is stocked in, and what
+-- the bar expected to have once the stock take was closed.
+CREATE TABLE IF NOT EXISTS stock_counts (
+  stock_count_id BLOB PRIMARY KEY NOT NULL,
+  quantity REAL NOT NULL CHECK(quantity >= 0),
+  expected REAL,
+  counted_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  stock_take_id BLOB NOT NULL REFERENCES stock_takes(stock_take_id) ON DELETE CASCADE,
+  bar_ingredient_id BLOB NOT NULL REFERENCES bar_ingredients(bar_ingredient_id) ON DELETE CASCADE,
+  UNIQUE (stock_take_id, bar_ingredient_id)
+);
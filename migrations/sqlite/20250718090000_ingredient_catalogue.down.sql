@@ -0,0 +1,60 @@
+-- Add down migration script here
+
+-- Foreign keys are not enforced while migrating, so whatever refers to the
+-- catalogue is deleted along with it by hand.
+DELETE FROM purchases
+WHERE bar_ingredient_id IN (
+  SELECT bar_ingredient_id FROM bar_ingredients
+  WHERE ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL)
+);
+DELETE FROM serve_deductions
+WHERE bar_ingredient_id IN (
+  SELECT bar_ingredient_id FROM bar_ingredients
+  WHERE ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL)
+);
+DELETE FROM stock_counts
+WHERE bar_ingredient_id IN (
+  SELECT bar_ingredient_id FROM bar_ingredients
+  WHERE ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL)
+);
+DELETE FROM bottles
+WHERE bar_ingredient_id IN (
+  SELECT bar_ingredient_id FROM bar_ingredients
+  WHERE ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL)
+);
+DELETE FROM bar_ingredients
+WHERE ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL);
+DELETE FROM recipe_ingredients
+WHERE ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL);
+DELETE FROM ingredient_ingredients
+WHERE ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL)
+  OR compound_ingredient_id IN (SELECT ingredient_id FROM ingredients WHERE user_id IS NULL);
+DELETE FROM ingredients WHERE user_id IS NULL;
+
+-- Rebuilds the table with the NOT NULL constraint the up migration took out.
+CREATE TABLE ingredients_old (
+  ingredient_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64),
+  description TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  media_id BLOB REFERENCES media(media_id) ON DELETE SET NULL,
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+
+  abv REAL CHECK(abv BETWEEN 0 AND 100),
+  sugar REAL CHECK(sugar >= 0),
+  acid REAL CHECK(acid >= 0),
+  shelf_life_id BLOB REFERENCES shelf_lives(shelf_life_id) ON DELETE SET NULL
+);
+
+INSERT INTO ingredients_old (
+  ingredient_id, name, description, created_at, media_id, user_id,
+  abv, sugar, acid, shelf_life_id
+)
+SELECT
+  ingredient_id, name, description, created_at, media_id, user_id,
+  abv, sugar, acid, shelf_life_id
+FROM ingredients;
+
+DROP TABLE ingredients;
+ALTER TABLE ingredients_old RENAME TO ingredients;
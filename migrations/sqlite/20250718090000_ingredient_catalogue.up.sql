@@ -0,0 +1,76 @@
+-- Add up migration script here
+
+-- Ingredients without an owner make up a catalogue shared by every user, who
+-- can stock them and use them in recipes but not change them.
+--
+-- SQLite cannot drop a NOT NULL constraint, so the table is rebuilt without it.
+-- Foreign keys must not be enforced while it is, or dropping the old table
+-- would cascade to every row referring to it; the migrations are run with
+-- them off, as the sqlite3 shell does by default.
+CREATE TABLE ingredients_new (
+  ingredient_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64),
+  description TEXT NOT NULL,
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  media_id BLOB REFERENCES media(media_id) ON DELETE SET NULL,
+  user_id BLOB REFERENCES users(user_id) ON DELETE CASCADE,
+
+  abv REAL CHECK(abv BETWEEN 0 AND 100),
+  sugar REAL CHECK(sugar >= 0),
+  acid REAL CHECK(acid >= 0),
+  shelf_life_id BLOB REFERENCES shelf_lives(shelf_life_id) ON DELETE SET NULL
+);
+
+INSERT INTO ingredients_new (
+  ingredient_id, name, description, created_at, media_id, user_id,
+  abv, sugar, acid, shelf_life_id
+)
+SELECT
+  ingredient_id, name, description, created_at, media_id, user_id,
+  abv, sugar, acid, shelf_life_id
+FROM ingredients;
+
+DROP TABLE ingredients;
+ALTER TABLE ingredients_new RENAME TO ingredients;
+
+CREATE INDEX IF NOT EXISTS ingredients_user_id ON ingredients (user_id);
+
+-- The catalogue, with what each ingredient is made of per 100ml.
+INSERT INTO ingredients (ingredient_id, name, description, abv, sugar, acid)
+VALUES
+  (X'00000000000000000000000000000201', 'gin', 'A juniper-led spirit.', 40, 0, 0),
+  (X'00000000000000000000000000000202', 'vodka', 'A neutral spirit.', 40, 0, 0),
+  (X'00000000000000000000000000000203', 'white rum', 'An unaged cane spirit.', 40, 0, 0),
+  (X'00000000000000000000000000000204', 'aged rum', 'A cane spirit aged in oak.', 40, 0, 0),
+  (X'00000000000000000000000000000205', 'tequila blanco', 'An unaged agave spirit.', 40, 0, 0),
+  (X'00000000000000000000000000000206', 'mezcal', 'A smoky agave spirit.', 40, 0, 0),
+  (X'00000000000000000000000000000207', 'bourbon', 'An American whiskey of mostly corn.', 45, 0, 0),
+  (X'00000000000000000000000000000208', 'rye whiskey', 'A spicy whiskey of mostly rye.', 45, 0, 0),
+  (X'00000000000000000000000000000209', 'scotch whisky', 'A whisky from Scotland.', 40, 0, 0),
+  (X'0000000000000000000000000000020A', 'cognac', 'A grape brandy from Cognac.', 40, 0, 0),
+  (X'0000000000000000000000000000020B', 'campari', 'A bitter red aperitivo.', 24, 24, 0),
+  (X'0000000000000000000000000000020C', 'aperol', 'A bitter orange aperitivo.', 11, 19, 0),
+  (X'0000000000000000000000000000020D', 'sweet vermouth', 'A sweet aromatised wine.', 16.5, 16, 0.6),
+  (X'0000000000000000000000000000020E', 'dry vermouth', 'A dry aromatised wine.', 17.5, 3, 0.6),
+  (X'0000000000000000000000000000020F', 'triple sec', 'An orange liqueur.', 40, 25, 0),
+  (X'00000000000000000000000000000210', 'maraschino liqueur', 'A cherry liqueur.', 32, 35, 0),
+  (X'00000000000000000000000000000211', 'green chartreuse', 'A herbal liqueur.', 55, 25, 0),
+  (X'00000000000000000000000000000212', 'angostura bitters', 'Aromatic bitters.', 44.7, 4.2, 0),
+  (X'00000000000000000000000000000213', 'orange bitters', 'Bitters of bitter orange peel.', 28, NULL, NULL),
+  (X'00000000000000000000000000000214', 'lime juice', 'Freshly squeezed lime juice.', 0, 1.6, 6),
+  (X'00000000000000000000000000000215', 'lemon juice', 'Freshly squeezed lemon juice.', 0, 1.6, 6),
+  (X'00000000000000000000000000000216', 'orange juice', 'Freshly squeezed orange juice.', 0, 12.4, 0.8),
+  (X'00000000000000000000000000000217', 'grapefruit juice', 'Freshly squeezed grapefruit juice.', 0, 10.4, 2.4),
+  (X'00000000000000000000000000000218', 'simple syrup', 'Sugar and water, one to one by weight.', 0, 61.5, 0),
+  (X'00000000000000000000000000000219', 'rich simple syrup', 'Sugar and water, two to one by weight.', 0, 87.5, 0),
+  (X'0000000000000000000000000000021A', 'honey syrup', 'Honey and water, one to one by weight.', 0, 61.5, 0),
+  (X'0000000000000000000000000000021B', 'demerara syrup', 'Demerara sugar and water, one to one by weight.', 0, 61.5, 0),
+  (X'0000000000000000000000000000021C', 'grenadine', 'A pomegranate syrup.', 0, 61.5, 1),
+  (X'0000000000000000000000000000021D', 'soda water', 'Carbonated water.', 0, 0, 0),
+  (X'0000000000000000000000000000021E', 'tonic water', 'Carbonated water with quinine.', 0, 8.7, 0),
+  (X'0000000000000000000000000000021F', 'ginger beer', 'A spicy carbonated ginger soda.', 0, 12, 0),
+  (X'00000000000000000000000000000220', 'cola', 'A carbonated cola soda.', 0, 10.6, 0.1),
+  (X'00000000000000000000000000000221', 'prosecco', 'A dry Italian sparkling wine.', 11, 1.5, 0.6),
+  (X'00000000000000000000000000000222', 'egg white', 'The white of a fresh egg.', 0, 0, 0),
+  (X'00000000000000000000000000000223', 'heavy cream', 'Cream of at least 36% fat.', 0, 3, 0);
@@ -0,0 +1,9 @@
+-- Add down migration script here
+
+ALTER TABLE recipe_ingredients DROP COLUMN category_id;
+ALTER TABLE ingredients DROP COLUMN flavours;
+ALTER TABLE ingredients DROP COLUMN tags;
+ALTER TABLE ingredients DROP COLUMN producer;
+ALTER TABLE ingredients DROP COLUMN origin;
+ALTER TABLE ingredients DROP COLUMN category_id;
+DROP TABLE IF EXISTS categories;
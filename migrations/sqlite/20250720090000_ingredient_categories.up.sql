@@ -0,0 +1,141 @@
+-- Add up migration script here
+
+-- A taxonomy of ingredients, such as spirit, whiskey and rye whiskey. Categories
+-- without an owner are shared by every user, who can add their own beneath them.
+CREATE TABLE IF NOT EXISTS categories (
+  category_id BLOB PRIMARY KEY NOT NULL,
+  name TEXT NOT NULL CHECK(length(name) BETWEEN 2 AND 64),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  parent_id BLOB REFERENCES categories(category_id) ON DELETE CASCADE,
+  user_id BLOB REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS categories_user_id ON categories (user_id);
+
+-- Tags and flavour notes are free-form, such as `house` or `smoke`, and kept
+-- as JSON arrays.
+ALTER TABLE ingredients
+ADD COLUMN category_id BLOB REFERENCES categories(category_id) ON DELETE SET NULL;
+ALTER TABLE ingredients ADD COLUMN origin TEXT CHECK(length(origin) <= 64);
+ALTER TABLE ingredients ADD COLUMN producer TEXT CHECK(length(producer) <= 64);
+ALTER TABLE ingredients ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE ingredients ADD COLUMN flavours TEXT NOT NULL DEFAULT '[]';
+
+-- A recipe line with a category takes any ingredient of it, such as any rye
+-- whiskey, with its ingredient standing in for the rest.
+ALTER TABLE recipe_ingredients
+ADD COLUMN category_id BLOB REFERENCES categories(category_id) ON DELETE SET NULL;
+
+-- The shared taxonomy, parents first.
+INSERT INTO categories (category_id, name, parent_id)
+VALUES
+  (X'00000000000000000000000000000301', 'spirit', NULL),
+  (X'00000000000000000000000000000302', 'whiskey', X'00000000000000000000000000000301'),
+  (X'00000000000000000000000000000303', 'rye whiskey', X'00000000000000000000000000000302'),
+  (X'00000000000000000000000000000304', 'bourbon', X'00000000000000000000000000000302'),
+  (X'00000000000000000000000000000305', 'scotch whisky', X'00000000000000000000000000000302'),
+  (X'00000000000000000000000000000306', 'gin', X'00000000000000000000000000000301'),
+  (X'00000000000000000000000000000307', 'vodka', X'00000000000000000000000000000301'),
+  (X'00000000000000000000000000000308', 'rum', X'00000000000000000000000000000301'),
+  (X'00000000000000000000000000000309', 'agave spirit', X'00000000000000000000000000000301'),
+  (X'0000000000000000000000000000030A', 'tequila', X'00000000000000000000000000000309'),
+  (X'0000000000000000000000000000030B', 'mezcal', X'00000000000000000000000000000309'),
+  (X'0000000000000000000000000000030C', 'brandy', X'00000000000000000000000000000301'),
+  (X'0000000000000000000000000000030D', 'cognac', X'0000000000000000000000000000030C'),
+  (X'0000000000000000000000000000030E', 'liqueur', NULL),
+  (X'0000000000000000000000000000030F', 'amaro', X'0000000000000000000000000000030E'),
+  (X'00000000000000000000000000000310', 'orange liqueur', X'0000000000000000000000000000030E'),
+  (X'00000000000000000000000000000311', 'herbal liqueur', X'0000000000000000000000000000030E'),
+  (X'00000000000000000000000000000312', 'cherry liqueur', X'0000000000000000000000000000030E'),
+  (X'00000000000000000000000000000313', 'wine', NULL),
+  (X'00000000000000000000000000000314', 'vermouth', X'00000000000000000000000000000313'),
+  (X'00000000000000000000000000000315', 'sparkling wine', X'00000000000000000000000000000313'),
+  (X'00000000000000000000000000000316', 'bitters', NULL),
+  (X'00000000000000000000000000000317', 'juice', NULL),
+  (X'00000000000000000000000000000318', 'citrus juice', X'00000000000000000000000000000317'),
+  (X'00000000000000000000000000000319', 'syrup', NULL),
+  (X'0000000000000000000000000000031A', 'mixer', NULL),
+  (X'0000000000000000000000000000031B', 'soda', X'0000000000000000000000000000031A'),
+  (X'0000000000000000000000000000031C', 'dairy', NULL),
+  (X'0000000000000000000000000000031D', 'egg', NULL);
+
+-- Files the ingredient catalogue under the taxonomy.
+UPDATE ingredients SET category_id = X'00000000000000000000000000000303'
+WHERE ingredient_id IN (X'00000000000000000000000000000208');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000304'
+WHERE ingredient_id IN (X'00000000000000000000000000000207');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000305'
+WHERE ingredient_id IN (X'00000000000000000000000000000209');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000306'
+WHERE ingredient_id IN (X'00000000000000000000000000000201');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000307'
+WHERE ingredient_id IN (X'00000000000000000000000000000202');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000308'
+WHERE ingredient_id IN (X'00000000000000000000000000000203', X'00000000000000000000000000000204');
+UPDATE ingredients SET category_id = X'0000000000000000000000000000030A'
+WHERE ingredient_id IN (X'00000000000000000000000000000205');
+UPDATE ingredients SET category_id = X'0000000000000000000000000000030B'
+WHERE ingredient_id IN (X'00000000000000000000000000000206');
+UPDATE ingredients SET category_id = X'0000000000000000000000000000030D'
+WHERE ingredient_id IN (X'0000000000000000000000000000020A');
+UPDATE ingredients SET category_id = X'0000000000000000000000000000030F'
+WHERE ingredient_id IN (X'0000000000000000000000000000020B', X'0000000000000000000000000000020C');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000310'
+WHERE ingredient_id IN (X'0000000000000000000000000000020F');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000311'
+WHERE ingredient_id IN (X'00000000000000000000000000000211');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000312'
+WHERE ingredient_id IN (X'00000000000000000000000000000210');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000314'
+WHERE ingredient_id IN (X'0000000000000000000000000000020D', X'0000000000000000000000000000020E');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000315'
+WHERE ingredient_id IN (X'00000000000000000000000000000221');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000316'
+WHERE ingredient_id IN (X'00000000000000000000000000000212', X'00000000000000000000000000000213');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000318'
+WHERE ingredient_id IN (X'00000000000000000000000000000214', X'00000000000000000000000000000215', X'00000000000000000000000000000216', X'00000000000000000000000000000217');
+UPDATE ingredients SET category_id = X'00000000000000000000000000000319'
+WHERE ingredient_id IN (X'00000000000000000000000000000218', X'00000000000000000000000000000219', X'0000000000000000000000000000021A', X'0000000000000000000000000000021B', X'0000000000000000000000000000021C');
+UPDATE ingredients SET category_id = X'0000000000000000000000000000031B'
+WHERE ingredient_id IN (X'0000000000000000000000000000021D', X'0000000000000000000000000000021E', X'0000000000000000000000000000021F', X'00000000000000000000000000000220');
+UPDATE ingredients SET category_id = X'0000000000000000000000000000031C'
+WHERE ingredient_id IN (X'00000000000000000000000000000223');
+UPDATE ingredients SET category_id = X'0000000000000000000000000000031D'
+WHERE ingredient_id IN (X'00000000000000000000000000000222');
+
+-- Where the catalogue comes from and what it tastes of.
+UPDATE ingredients SET origin = 'england', flavours = '["citrus","floral","juniper"]'
+WHERE ingredient_id = X'00000000000000000000000000000201';
+UPDATE ingredients SET origin = 'jamaica', flavours = '["banana","molasses","vanilla"]'
+WHERE ingredient_id = X'00000000000000000000000000000204';
+UPDATE ingredients SET origin = 'mexico', flavours = '["agave","citrus","pepper"]'
+WHERE ingredient_id = X'00000000000000000000000000000205';
+UPDATE ingredients SET origin = 'mexico', flavours = '["agave","earth","smoke"]'
+WHERE ingredient_id = X'00000000000000000000000000000206';
+UPDATE ingredients SET origin = 'united states', flavours = '["caramel","oak","vanilla"]'
+WHERE ingredient_id = X'00000000000000000000000000000207';
+UPDATE ingredients SET origin = 'united states', flavours = '["oak","pepper","spice"]'
+WHERE ingredient_id = X'00000000000000000000000000000208';
+UPDATE ingredients SET origin = 'scotland', flavours = '["honey","malt","smoke"]'
+WHERE ingredient_id = X'00000000000000000000000000000209';
+UPDATE ingredients SET origin = 'france', flavours = '["dried fruit","oak","vanilla"]'
+WHERE ingredient_id = X'0000000000000000000000000000020A';
+UPDATE ingredients SET origin = 'italy', flavours = '["bitter","herbal","orange"]'
+WHERE ingredient_id = X'0000000000000000000000000000020B';
+UPDATE ingredients SET origin = 'italy', flavours = '["bitter","orange","rhubarb"]'
+WHERE ingredient_id = X'0000000000000000000000000000020C';
+UPDATE ingredients SET origin = 'italy', flavours = '["dried fruit","herbal","vanilla"]'
+WHERE ingredient_id = X'0000000000000000000000000000020D';
+UPDATE ingredients SET origin = 'france', flavours = '["citrus","floral","herbal"]'
+WHERE ingredient_id = X'0000000000000000000000000000020E';
+UPDATE ingredients SET origin = 'france', flavours = '["orange"]'
+WHERE ingredient_id = X'0000000000000000000000000000020F';
+UPDATE ingredients SET origin = 'croatia', flavours = '["almond","cherry","floral"]'
+WHERE ingredient_id = X'00000000000000000000000000000210';
+UPDATE ingredients SET origin = 'france', flavours = '["herbal","mint","spice"]'
+WHERE ingredient_id = X'00000000000000000000000000000211';
+UPDATE ingredients SET origin = 'trinidad and tobago', flavours = '["bitter","cinnamon","clove"]'
+WHERE ingredient_id = X'00000000000000000000000000000212';
+UPDATE ingredients SET origin = 'italy', flavours = '["apple","floral","pear"]'
+WHERE ingredient_id = X'00000000000000000000000000000221';
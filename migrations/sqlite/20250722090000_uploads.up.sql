@@ -0,0 +1,19 @@
+-- Add up migration script here
+
+-- Every upload a user makes, kept after its media is deleted so that deleting
+-- media does not hand back uploads within the hourly limit.
+CREATE TABLE IF NOT EXISTS uploads (
+  upload_id BLOB PRIMARY KEY NOT NULL,
+  size INTEGER NOT NULL CHECK(size >= 0),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS uploads_user_id_created_at_idx ON uploads (user_id, created_at);
+
+-- The ids only need to be unique, so the media's own are reused.
+INSERT INTO uploads (upload_id, size, created_at, user_id)
+SELECT media_id, size, created_at, user_id
+FROM media
+WHERE created_at > strftime('%Y-%m-%dT%H:%M:%f', 'now', '-1 hour');
@@ -0,0 +1,26 @@
+-- Add down migration script here
+
+-- Foreign keys are not enforced while migrating, so ingredients given a shared
+-- shelf life lose it by hand.
+UPDATE ingredients
+SET shelf_life_id = NULL
+WHERE shelf_life_id IN (SELECT shelf_life_id FROM shelf_lives WHERE user_id IS NULL);
+DELETE FROM shelf_lives WHERE user_id IS NULL;
+
+-- Rebuilds the table with the NOT NULL constraint the up migration took out.
+CREATE TABLE shelf_lives_old (
+  shelf_life_id BLOB PRIMARY KEY NOT NULL,
+  name VARCHAR(64) NOT NULL CHECK(length(name) >= 2),
+  days INTEGER NOT NULL CHECK(days > 0),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  user_id BLOB NOT NULL REFERENCES users(user_id) ON DELETE CASCADE,
+  UNIQUE (user_id, name)
+);
+
+INSERT INTO shelf_lives_old (shelf_life_id, name, days, created_at, user_id)
+SELECT shelf_life_id, name, days, created_at, user_id
+FROM shelf_lives;
+
+DROP TABLE shelf_lives;
+ALTER TABLE shelf_lives_old RENAME TO shelf_lives;
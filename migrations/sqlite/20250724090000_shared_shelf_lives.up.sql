@@ -0,0 +1,56 @@
+-- Add up migration script here
+
+-- Shelf lives without an owner are shared by every user, who can give them to
+-- their ingredients but not change them.
+--
+-- SQLite cannot drop a NOT NULL constraint, so the table is rebuilt without it,
+-- with foreign keys off as for the ingredient catalogue.
+CREATE TABLE shelf_lives_new (
+  shelf_life_id BLOB PRIMARY KEY NOT NULL,
+  name VARCHAR(64) NOT NULL CHECK(length(name) >= 2),
+  days INTEGER NOT NULL CHECK(days > 0),
+  created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%f', 'now')),
+
+  user_id BLOB REFERENCES users(user_id) ON DELETE CASCADE,
+  UNIQUE (user_id, name)
+);
+
+INSERT INTO shelf_lives_new (shelf_life_id, name, days, created_at, user_id)
+SELECT shelf_life_id, name, days, created_at, user_id
+FROM shelf_lives;
+
+DROP TABLE shelf_lives;
+ALTER TABLE shelf_lives_new RENAME TO shelf_lives;
+
+INSERT INTO shelf_lives (shelf_life_id, name, days)
+VALUES
+  (X'00000000000000000000000000000401', 'vermouth', 30),
+  (X'00000000000000000000000000000402', 'fresh juice', 1),
+  (X'00000000000000000000000000000403', 'syrup', 14);
+
+-- The catalogue's vermouths, fresh juices and syrups keep as long as these.
+UPDATE ingredients
+SET shelf_life_id = X'00000000000000000000000000000401'
+WHERE ingredient_id IN (
+  X'0000000000000000000000000000020D',
+  X'0000000000000000000000000000020E'
+);
+
+UPDATE ingredients
+SET shelf_life_id = X'00000000000000000000000000000402'
+WHERE ingredient_id IN (
+  X'00000000000000000000000000000214',
+  X'00000000000000000000000000000215',
+  X'00000000000000000000000000000216',
+  X'00000000000000000000000000000217'
+);
+
+UPDATE ingredients
+SET shelf_life_id = X'00000000000000000000000000000403'
+WHERE ingredient_id IN (
+  X'00000000000000000000000000000218',
+  X'00000000000000000000000000000219',
+  X'0000000000000000000000000000021A',
+  X'0000000000000000000000000000021B',
+  X'0000000000000000000000000000021C'
+);
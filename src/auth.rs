@@ -21,8 +21,8 @@ pub struct Auth {
 }
 
 impl Auth {
-    pub fn create(signing_key: &str, user: UserModel) -> crate::Result<Self> {
-        let expiration = Utc::now() + Duration::weeks(2);
+    pub fn create(signing_key: &str, ttl: Duration, user: UserModel) -> crate::Result<Self> {
+        let expiration = Utc::now() + ttl;
         let claims = Claims {
             exp: expiration.timestamp(),
             sub: user.id,
@@ -45,7 +45,8 @@ impl Auth {
             &token.into(),
             &DecodingKey::from_secret(signing_key.as_ref()),
             &Validation::default(),
-        )?;
+        )
+        .map_err(|_| Error::new(StatusCode::UNAUTHORIZED, "invalid auth token"))?;
 
         Ok(decoded.claims.sub)
     }
@@ -0,0 +1,1024 @@
+use std::collections::{HashMap, HashSet};
+
+use axum::http::StatusCode;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        BarIngredientModel, BarModel, CategoryModel, CreateDeductionSchema, CreateStockCountSchema,
+        IngredientModel, IngredientPartModel, PurchaseModel, RecipeIngredientModel, RecipeModel,
+        StockTakeModel, StockUsageModel, UnitModel,
+    },
+    Error,
+};
+
+pub(crate) const VOLUME: &str = "volume";
+const DEFAULT_SYSTEM: &str = "metric";
+
+const ETHANOL_DENSITY: f64 = 0.789;
+
+/// A rule of thumb: most crushed ice in a blended drink stays frozen.
+const BLENDED_DILUTION: f64 = 0.5;
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub(crate) struct QuantityModel {
+    pub quantity: f64,
+    pub unit: UnitModel,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ScaledIngredientModel {
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub quantity: f64,
+    pub unit: UnitModel,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ScaledRecipeModel {
+    pub recipe_id: Uuid,
+    pub servings: f64,
+    pub ingredients: Vec<ScaledIngredientModel>,
+    pub water: Option<QuantityModel>,
+    pub total_volume: QuantityModel,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RecipeAnalysisModel {
+    pub recipe_id: Uuid,
+    pub method: Option<String>,
+    pub initial_volume: QuantityModel,
+    /// Water melted from ice, as a percentage of the initial volume.
+    pub dilution: f64,
+    pub final_volume: QuantityModel,
+    /// Alcohol by volume of the drink, as a percentage.
+    pub abv: f64,
+    /// Grams of sugar per 100ml of the drink.
+    pub sugar: f64,
+    /// Grams of acid per 100ml of the drink.
+    pub acid: f64,
+    pub standard_drinks: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct IngredientCostModel {
+    pub bar_ingredient_id: Uuid,
+    pub purchases: usize,
+    pub spent: f64,
+    pub purchased: QuantityModel,
+    pub unit_cost: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CostedIngredientModel {
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub quantity: f32,
+    pub unit: UnitModel,
+    pub cost: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RecipeCostModel {
+    pub recipe_id: Uuid,
+    pub bar_id: Uuid,
+    pub ingredients: Vec<CostedIngredientModel>,
+    pub cost: f64,
+    pub complete: bool,
+    pub pour_cost: f64,
+    pub suggested_price: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ShoppingItemModel {
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub bar_ingredient_id: Option<Uuid>,
+    pub in_stock: f64,
+    pub for_recipes: f64,
+    pub needed: f64,
+    pub bottles: Option<u32>,
+    pub bottle_size: Option<f64>,
+    pub quantity: f64,
+    pub unit: UnitModel,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ShoppingListModel {
+    pub bar_id: Uuid,
+    pub items: Vec<ShoppingItemModel>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct VarianceModel {
+    pub bar_ingredient_id: Uuid,
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub expected: f64,
+    pub counted: f64,
+    pub variance: f64,
+    pub usage: f64,
+    pub variance_of_usage: Option<f64>,
+    pub unit: UnitModel,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct VarianceReportModel {
+    pub stock_take_id: Uuid,
+    pub bar_id: Uuid,
+    pub since: Option<NaiveDateTime>,
+    pub until: Option<NaiveDateTime>,
+    pub ingredients: Vec<VarianceModel>,
+    pub uncounted: Vec<Uuid>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct Composition {
+    pub abv: f64,
+    pub sugar: f64,
+    pub acid: f64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum ScaleTarget {
+    Servings(f64),
+    Volume(f64),
+}
+
+pub(crate) fn round(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+fn round_cost(amount: f64) -> f64 {
+    (amount * 10_000.0).round() / 10_000.0
+}
+
+fn base_volume_unit(units: &[UnitModel]) -> crate::Result<&UnitModel> {
+    units
+        .iter()
+        .find(|u| u.dimension == VOLUME && u.base_quantity == 1.0)
+        .ok_or(Error::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "missing base volume unit",
+        ))
+}
+
+fn volume_of(
+    milliliters: f64,
+    base_unit: &UnitModel,
+    system: &str,
+    units: &[UnitModel],
+) -> QuantityModel {
+    let unit = practical_unit(milliliters, base_unit, system, units);
+    QuantityModel {
+        quantity: round(milliliters / unit.base_quantity),
+        unit: unit.clone(),
+    }
+}
+
+fn practical_unit<'a>(
+    base_amount: f64,
+    original: &'a UnitModel,
+    system: &str,
+    units: &'a [UnitModel],
+) -> &'a UnitModel {
+    let mut candidates: Vec<&UnitModel> = units
+        .iter()
+        .filter(|u| u.dimension == original.dimension && u.system.as_deref() == Some(system))
+        .collect();
+    if original.system.is_none() {
+        candidates.push(original);
+    }
+    candidates.sort_by(|a, b| a.base_quantity.total_cmp(&b.base_quantity));
+
+    candidates
+        .iter()
+        .rev()
+        .find(|u| base_amount / u.base_quantity >= 1.0)
+        .or(candidates.first())
+        .copied()
+        .unwrap_or(original)
+}
+
+fn recipe_system(lines: &[RecipeIngredientModel]) -> &str {
+    let count = |system: &str| {
+        lines
+            .iter()
+            .filter(|line| line.unit.system.as_deref() == Some(system))
+            .count()
+    };
+
+    if count("imperial") > count(DEFAULT_SYSTEM) {
+        "imperial"
+    } else {
+        DEFAULT_SYSTEM
+    }
+}
+
+pub(crate) fn scale(
+    recipe: &RecipeModel,
+    lines: &[RecipeIngredientModel],
+    units: &[UnitModel],
+    target: ScaleTarget,
+    dilution: f64,
+    system: Option<&str>,
+) -> crate::Result<ScaledRecipeModel> {
+    let system = system.unwrap_or_else(|| recipe_system(lines));
+    let milliliters = base_volume_unit(units)?;
+    let serving_volume: f64 = lines.iter().filter_map(line_volume).sum();
+
+    let servings = match target {
+        ScaleTarget::Servings(servings) => servings,
+        ScaleTarget::Volume(_) if serving_volume <= 0.0 => {
+            return Err(Error::new(
+                StatusCode::BAD_REQUEST,
+                "recipe has no volume to scale by",
+            ));
+        }
+        ScaleTarget::Volume(volume) => volume / (serving_volume * (1.0 + dilution)),
+    };
+
+    let ingredients = lines
+        .iter()
+        .map(|line| {
+            let base_amount = line.quantity as f64 * line.unit.base_quantity * servings;
+            let unit = practical_unit(base_amount, &line.unit, system, units);
+
+            ScaledIngredientModel {
+                ingredient_id: line.ingredient.id,
+                name: line.ingredient.name.clone(),
+                quantity: round(base_amount / unit.base_quantity),
+                unit: unit.clone(),
+            }
+        })
+        .collect();
+
+    let batch_volume = serving_volume * servings;
+    let water =
+        (dilution > 0.0).then(|| volume_of(batch_volume * dilution, milliliters, system, units));
+
+    Ok(ScaledRecipeModel {
+        recipe_id: recipe.id,
+        servings: round(servings),
+        ingredients,
+        water,
+        total_volume: volume_of(batch_volume * (1.0 + dilution), milliliters, system, units),
+    })
+}
+
+fn line_volume(line: &RecipeIngredientModel) -> Option<f64> {
+    (line.unit.dimension == VOLUME).then_some(line.quantity as f64 * line.unit.base_quantity)
+}
+
+pub(crate) fn compositions(
+    ingredients: &[IngredientModel],
+    parts: &[IngredientPartModel],
+) -> HashMap<Uuid, Composition> {
+    let ingredients: HashMap<Uuid, &IngredientModel> =
+        ingredients.iter().map(|i| (i.id, i)).collect();
+    let mut parts_of: HashMap<Uuid, Vec<&IngredientPartModel>> = HashMap::new();
+    for part in parts {
+        parts_of.entry(part.compound_id).or_default().push(part);
+    }
+
+    let mut resolved = HashMap::new();
+    for id in ingredients.keys() {
+        resolve(
+            *id,
+            &ingredients,
+            &parts_of,
+            &mut resolved,
+            &mut HashSet::new(),
+        );
+    }
+
+    resolved
+}
+
+pub(crate) fn contains(parts: &[IngredientPartModel], part: Uuid, compound: Uuid) -> bool {
+    let mut parts_of: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for p in parts {
+        parts_of
+            .entry(p.compound_id)
+            .or_default()
+            .push(p.ingredient_id);
+    }
+
+    let mut seen = HashSet::new();
+    let mut pending = vec![part];
+    while let Some(id) = pending.pop() {
+        if id == compound {
+            return true;
+        }
+        if seen.insert(id) {
+            pending.extend(parts_of.get(&id).into_iter().flatten());
+        }
+    }
+
+    false
+}
+
+fn resolve(
+    id: Uuid,
+    ingredients: &HashMap<Uuid, &IngredientModel>,
+    parts_of: &HashMap<Uuid, Vec<&IngredientPartModel>>,
+    resolved: &mut HashMap<Uuid, Composition>,
+    visiting: &mut HashSet<Uuid>,
+) -> Composition {
+    if let Some(composition) = resolved.get(&id) {
+        return *composition;
+    }
+    // An ingredient that is, through its parts, a part of itself adds nothing
+    // the second time round.
+    if !visiting.insert(id) {
+        return Composition::default();
+    }
+
+    let mut from_parts = Composition::default();
+    if let Some(parts) = parts_of.get(&id) {
+        let total: f64 = parts.iter().map(|p| p.parts as f64).sum();
+        for part in parts {
+            let share = part.parts as f64 / total;
+            let c = resolve(
+                part.ingredient_id,
+                ingredients,
+                parts_of,
+                resolved,
+                visiting,
+            );
+            from_parts.abv += c.abv * share;
+            from_parts.sugar += c.sugar * share;
+            from_parts.acid += c.acid * share;
+        }
+    }
+
+    let own = ingredients.get(&id);
+    let value = |field: fn(&IngredientModel) -> Option<f32>, fallback: f64| {
+        own.and_then(|i| field(i)).map_or(fallback, |v| v as f64)
+    };
+    let composition = Composition {
+        abv: value(|i| i.abv, from_parts.abv),
+        sugar: value(|i| i.sugar, from_parts.sugar),
+        acid: value(|i| i.acid, from_parts.acid),
+    };
+
+    visiting.remove(&id);
+    resolved.insert(id, composition);
+    composition
+}
+
+/// Shaken and stirred dilution follow the curves Dave Arnold fitted in Liquid
+/// Intelligence, with `abv` as a fraction. Built drinks use the stirred curve.
+pub(crate) fn dilution(method: Option<&str>, abv: f64) -> f64 {
+    match method {
+        Some("shaken") => 1.567 * abv * abv + 1.742 * abv + 0.203,
+        Some("stirred" | "built") => -1.21 * abv * abv + 1.246 * abv + 0.145,
+        Some("blended") => BLENDED_DILUTION,
+        _ => 0.0,
+    }
+}
+
+pub(crate) fn analyse(
+    recipe: &RecipeModel,
+    lines: &[RecipeIngredientModel],
+    units: &[UnitModel],
+    compositions: &HashMap<Uuid, Composition>,
+    standard_drink: f64,
+) -> crate::Result<RecipeAnalysisModel> {
+    let system = recipe_system(lines);
+    let milliliters = base_volume_unit(units)?;
+
+    let mut initial_volume = 0.0;
+    let mut totals = Composition::default();
+    for line in lines {
+        let Some(volume) = line_volume(line) else {
+            continue;
+        };
+        let c = compositions
+            .get(&line.ingredient.id)
+            .copied()
+            .unwrap_or_default();
+
+        initial_volume += volume;
+        totals.abv += volume * c.abv;
+        totals.sugar += volume * c.sugar;
+        totals.acid += volume * c.acid;
+    }
+
+    let initial_abv = if initial_volume > 0.0 {
+        totals.abv / initial_volume / 100.0
+    } else {
+        0.0
+    };
+    let dilution = dilution(recipe.method.as_deref(), initial_abv);
+    let final_volume = initial_volume * (1.0 + dilution);
+    let per_100ml = |total: f64| {
+        if final_volume > 0.0 {
+            round(total / final_volume)
+        } else {
+            0.0
+        }
+    };
+    let ethanol = totals.abv / 100.0 * ETHANOL_DENSITY;
+
+    Ok(RecipeAnalysisModel {
+        recipe_id: recipe.id,
+        method: recipe.method.clone(),
+        initial_volume: volume_of(initial_volume, milliliters, system, units),
+        dilution: round(dilution * 100.0),
+        final_volume: volume_of(final_volume, milliliters, system, units),
+        abv: per_100ml(totals.abv),
+        sugar: per_100ml(totals.sugar),
+        acid: per_100ml(totals.acid),
+        standard_drinks: round(ethanol / standard_drink),
+    })
+}
+
+fn base_unit_cost<'a>(purchases: impl IntoIterator<Item = &'a PurchaseModel>) -> Option<f64> {
+    let (price, quantity) = purchases
+        .into_iter()
+        .fold((0.0, 0.0), |(price, quantity), p| {
+            (
+                price + p.price,
+                quantity + p.quantity as f64 * p.unit.base_quantity,
+            )
+        });
+
+    (quantity > 0.0).then(|| price / quantity)
+}
+
+pub(crate) fn ingredient_cost(
+    stock: &BarIngredientModel,
+    purchases: &[PurchaseModel],
+) -> IngredientCostModel {
+    let base_quantity: f64 = purchases
+        .iter()
+        .map(|p| p.quantity as f64 * p.unit.base_quantity)
+        .sum();
+
+    IngredientCostModel {
+        bar_ingredient_id: stock.id,
+        purchases: purchases.len(),
+        spent: round(purchases.iter().map(|p| p.price).sum()),
+        purchased: QuantityModel {
+            quantity: round(base_quantity / stock.unit.base_quantity),
+            unit: stock.unit.clone(),
+        },
+        unit_cost: base_unit_cost(purchases)
+            .map(|cost| round_cost(cost * stock.unit.base_quantity)),
+    }
+}
+
+pub(crate) fn recipe_cost(
+    recipe: &RecipeModel,
+    bar: &BarModel,
+    lines: &[RecipeIngredientModel],
+    stock: &[BarIngredientModel],
+    purchases: &[PurchaseModel],
+    pour_cost: f64,
+) -> RecipeCostModel {
+    let ingredients: Vec<CostedIngredientModel> = lines
+        .iter()
+        .map(|line| {
+            let cost = stock
+                .iter()
+                .find(|s| s.ingredient.id == line.ingredient.id)
+                .filter(|s| s.unit.dimension == line.unit.dimension)
+                .and_then(|s| {
+                    base_unit_cost(purchases.iter().filter(|p| p.bar_ingredient_id == s.id))
+                })
+                .map(|cost| cost * line.quantity as f64 * line.unit.base_quantity);
+
+            CostedIngredientModel {
+                ingredient_id: line.ingredient.id,
+                name: line.ingredient.name.clone(),
+                quantity: line.quantity,
+                unit: line.unit.clone(),
+                cost: cost.map(round_cost),
+            }
+        })
+        .collect();
+
+    let cost: f64 = ingredients.iter().filter_map(|i| i.cost).sum();
+
+    RecipeCostModel {
+        recipe_id: recipe.id,
+        bar_id: bar.id,
+        complete: ingredients.iter().all(|i| i.cost.is_some()),
+        ingredients,
+        cost: round(cost),
+        pour_cost: round(pour_cost * 100.0),
+        suggested_price: round(cost / pour_cost),
+    }
+}
+
+pub(crate) fn substitute(
+    lines: Vec<RecipeIngredientModel>,
+    stock: &[BarIngredientModel],
+    categories: &[CategoryModel],
+) -> Vec<RecipeIngredientModel> {
+    lines
+        .into_iter()
+        .map(|mut line| {
+            let Some(category_id) = line.category_id else {
+                return line;
+            };
+            if stock.iter().any(|s| s.ingredient.id == line.ingredient.id) {
+                return line;
+            }
+
+            let within = CategoryModel::within(categories, category_id);
+            if let Some(s) = stock.iter().find(|s| {
+                s.unit.dimension == line.unit.dimension
+                    && s.ingredient
+                        .category_id
+                        .is_some_and(|c| within.contains(&c))
+            }) {
+                line.ingredient = s.ingredient.clone();
+            }
+            line
+        })
+        .collect()
+}
+
+struct Allotment<'a> {
+    line: &'a RecipeIngredientModel,
+    ingredient_id: Uuid,
+    stock: Option<&'a BarIngredientModel>,
+    base_amount: f64,
+}
+
+fn allotments<'a>(
+    lines: &'a [RecipeIngredientModel],
+    servings: f64,
+    stock: &'a [BarIngredientModel],
+    parts: &[IngredientPartModel],
+) -> Vec<Allotment<'a>> {
+    let stock: HashMap<Uuid, &BarIngredientModel> =
+        stock.iter().map(|s| (s.ingredient.id, s)).collect();
+    let mut parts_of: HashMap<Uuid, Vec<&IngredientPartModel>> = HashMap::new();
+    for part in parts {
+        parts_of.entry(part.compound_id).or_default().push(part);
+    }
+
+    let mut allotments = Vec::new();
+    for line in lines {
+        let base_amount = line.quantity as f64 * line.unit.base_quantity * servings;
+        allot(
+            line,
+            line.ingredient.id,
+            base_amount,
+            &stock,
+            &parts_of,
+            &mut allotments,
+            &mut HashSet::new(),
+        );
+    }
+
+    allotments
+}
+
+fn allot<'a>(
+    line: &'a RecipeIngredientModel,
+    ingredient_id: Uuid,
+    base_amount: f64,
+    stock: &HashMap<Uuid, &'a BarIngredientModel>,
+    parts_of: &HashMap<Uuid, Vec<&IngredientPartModel>>,
+    allotments: &mut Vec<Allotment<'a>>,
+    visiting: &mut HashSet<Uuid>,
+) {
+    if let Some(stock) = stock.get(&ingredient_id) {
+        if stock.unit.dimension == line.unit.dimension {
+            allotments.push(Allotment {
+                line,
+                ingredient_id,
+                stock: Some(stock),
+                base_amount,
+            });
+        }
+        return;
+    }
+
+    let Some(parts) = parts_of.get(&ingredient_id) else {
+        allotments.push(Allotment {
+            line,
+            ingredient_id,
+            stock: None,
+            base_amount,
+        });
+        return;
+    };
+    if !visiting.insert(ingredient_id) {
+        return;
+    }
+
+    let total: f64 = parts.iter().map(|p| p.parts as f64).sum();
+    for part in parts {
+        allot(
+            line,
+            part.ingredient_id,
+            base_amount * part.parts as f64 / total,
+            stock,
+            parts_of,
+            allotments,
+            visiting,
+        );
+    }
+    visiting.remove(&ingredient_id);
+}
+
+pub(crate) fn deductions(
+    lines: &[RecipeIngredientModel],
+    servings: f64,
+    stock: &[BarIngredientModel],
+    parts: &[IngredientPartModel],
+) -> Vec<CreateDeductionSchema> {
+    let mut deductions: Vec<CreateDeductionSchema> = Vec::new();
+    for allotment in allotments(lines, servings, stock, parts) {
+        let Some(stock) = allotment.stock else {
+            continue;
+        };
+
+        let quantity = (allotment.base_amount / stock.unit.base_quantity) as f32;
+        match deductions
+            .iter_mut()
+            .find(|d| d.bar_ingredient_id == stock.id)
+        {
+            Some(deduction) => deduction.quantity += quantity,
+            None => deductions.push(CreateDeductionSchema {
+                bar_ingredient_id: stock.id,
+                quantity,
+            }),
+        }
+    }
+
+    deductions.retain(|d| d.quantity > 0.0);
+    deductions
+}
+
+fn whole_bottles(needed: f64, bottle_size: Option<f32>) -> (Option<u32>, f64) {
+    match bottle_size {
+        Some(size) => {
+            let bottles = round(needed / size as f64).ceil();
+            (Some(bottles as u32), bottles * size as f64)
+        }
+        None => (None, round(needed)),
+    }
+}
+
+pub(crate) fn shopping_list(
+    bar: &BarModel,
+    stock: &[BarIngredientModel],
+    recipes: &[Vec<RecipeIngredientModel>],
+    servings: f64,
+    ingredients: &[IngredientModel],
+    parts: &[IngredientPartModel],
+) -> ShoppingListModel {
+    let mut for_stock: HashMap<Uuid, f64> = HashMap::new();
+    let mut unstocked: Vec<(Uuid, f64, &UnitModel)> = Vec::new();
+    for lines in recipes {
+        for allotment in allotments(lines, servings, stock, parts) {
+            if let Some(stock) = allotment.stock {
+                *for_stock.entry(stock.id).or_default() +=
+                    allotment.base_amount / stock.unit.base_quantity;
+                continue;
+            }
+
+            let unit = &allotment.line.unit;
+            match unstocked
+                .iter_mut()
+                .find(|(id, _, u)| *id == allotment.ingredient_id && u.dimension == unit.dimension)
+            {
+                Some((_, amount, u)) => *amount += allotment.base_amount / u.base_quantity,
+                None => unstocked.push((
+                    allotment.ingredient_id,
+                    allotment.base_amount / unit.base_quantity,
+                    unit,
+                )),
+            }
+        }
+    }
+
+    let mut items: Vec<ShoppingItemModel> = stock
+        .iter()
+        .filter_map(|s| {
+            let in_stock = s.quantity as f64;
+            let for_recipes = for_stock.get(&s.id).copied().unwrap_or_default();
+            let remaining = in_stock - for_recipes;
+            let restock_at = s.minimum.or(s.par).map(f64::from);
+            let restock_to = s.par.or(s.minimum).map(f64::from).unwrap_or_default();
+            let needed = match restock_at {
+                Some(level) if remaining < level => restock_to - remaining,
+                _ => -remaining,
+            };
+            if round(needed) <= 0.0 {
+                return None;
+            }
+
+            let (bottles, quantity) = whole_bottles(needed, s.bottle_size);
+            Some(ShoppingItemModel {
+                ingredient_id: s.ingredient.id,
+                name: s.ingredient.name.clone(),
+                bar_ingredient_id: Some(s.id),
+                in_stock: round(in_stock),
+                for_recipes: round(for_recipes),
+                needed: round(needed),
+                bottles,
+                bottle_size: s.bottle_size.map(f64::from),
+                quantity,
+                unit: s.unit.clone(),
+            })
+        })
+        .collect();
+
+    let names: HashMap<Uuid, &str> = ingredients
+        .iter()
+        .map(|i| (i.id, i.name.as_str()))
+        .collect();
+    items.extend(
+        unstocked
+            .into_iter()
+            .filter(|(_, amount, _)| round(*amount) > 0.0)
+            .map(|(id, amount, unit)| ShoppingItemModel {
+                ingredient_id: id,
+                name: names.get(&id).copied().unwrap_or_default().to_string(),
+                bar_ingredient_id: None,
+                in_stock: 0.0,
+                for_recipes: round(amount),
+                needed: round(amount),
+                bottles: None,
+                bottle_size: None,
+                quantity: round(amount),
+                unit: unit.clone(),
+            }),
+    );
+    items.sort_by(|a, b| a.name.cmp(&b.name));
+
+    ShoppingListModel {
+        bar_id: bar.id,
+        items,
+    }
+}
+
+/// Prefixes fields a spreadsheet would read as a formula with `'`.
+fn csv_field(field: &str) -> String {
+    let field = if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{field}")
+    } else {
+        field.to_string()
+    };
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+impl ShoppingListModel {
+    pub(crate) fn to_text(&self, bar: &BarModel) -> String {
+        let mut text = format!("Shopping list for {}\n\n", bar.name);
+        for item in &self.items {
+            let unit = &item.unit.abbreviation;
+            let line = match (item.bottles, item.bottle_size) {
+                (Some(bottles), Some(size)) => format!(
+                    "- {}: {bottles} x {}{unit} ({}{unit})\n",
+                    item.name,
+                    round(size),
+                    round(item.quantity),
+                ),
+                _ => format!("- {}: {}{unit}\n", item.name, round(item.quantity)),
+            };
+            text.push_str(&line);
+        }
+        if self.items.is_empty() {
+            text.push_str("Nothing to buy.\n");
+        }
+
+        text
+    }
+
+    pub(crate) fn to_csv(&self) -> String {
+        let mut csv = String::from("ingredient,quantity,unit,bottles,bottle_size\r\n");
+        for item in &self.items {
+            csv.push_str(&format!(
+                "{},{},{},{},{}\r\n",
+                csv_field(&item.name),
+                round(item.quantity),
+                csv_field(&item.unit.abbreviation),
+                item.bottles.map(|b| b.to_string()).unwrap_or_default(),
+                item.bottle_size
+                    .map(|s| round(s).to_string())
+                    .unwrap_or_default(),
+            ));
+        }
+
+        csv
+    }
+}
+
+/// The alcohol term is fitted to water and ethanol mixtures at 20°C.
+fn estimated_density(abv: f64, sugar: f64) -> f64 {
+    1.0 - 0.00076 * abv - 0.0000135 * abv * abv + 0.0037 * sugar
+}
+
+pub(crate) fn counted_quantity(
+    stock: &BarIngredientModel,
+    count: &CreateStockCountSchema,
+    unit: &UnitModel,
+) -> f64 {
+    let mut base_amount = 0.0;
+    if let (Some(bottles), Some(size)) = (count.bottles, stock.bottle_size) {
+        base_amount += bottles as f64 * size as f64 * stock.unit.base_quantity;
+    }
+    if let Some(quantity) = count.quantity {
+        base_amount += quantity as f64 * unit.base_quantity;
+    }
+    if let (Some(weight), Some(tare_weight)) = (count.weight, count.tare_weight) {
+        let grams = (weight - tare_weight) as f64;
+        base_amount += match stock.unit.dimension.as_str() {
+            VOLUME => {
+                let ingredient = &stock.ingredient;
+                grams
+                    / count.density.map(f64::from).unwrap_or_else(|| {
+                        estimated_density(
+                            ingredient.abv.unwrap_or_default() as f64,
+                            ingredient.sugar.unwrap_or_default() as f64,
+                        )
+                    })
+            }
+            _ => grams,
+        };
+    }
+
+    base_amount / stock.unit.base_quantity
+}
+
+pub(crate) fn variance_report(
+    stock_take: &StockTakeModel,
+    stock_takes: &[StockTakeModel],
+    stock: &[BarIngredientModel],
+    usage: &[StockUsageModel],
+) -> VarianceReportModel {
+    let stock_by_id: HashMap<Uuid, &BarIngredientModel> = stock.iter().map(|s| (s.id, s)).collect();
+    let usage: HashMap<Uuid, f64> = usage
+        .iter()
+        .map(|u| (u.bar_ingredient_id, u.quantity))
+        .collect();
+
+    let ingredients = stock_take
+        .counts
+        .iter()
+        .map(|count| {
+            let expected = count
+                .expected
+                .or(stock_by_id
+                    .get(&count.bar_ingredient_id)
+                    .map(|s| s.quantity))
+                .unwrap_or_default() as f64;
+            let variance = count.quantity as f64 - expected;
+            let usage = usage
+                .get(&count.bar_ingredient_id)
+                .copied()
+                .unwrap_or_default();
+
+            VarianceModel {
+                bar_ingredient_id: count.bar_ingredient_id,
+                ingredient_id: count.ingredient_id,
+                name: count.name.clone(),
+                expected: round(expected),
+                counted: round(count.quantity as f64),
+                variance: round(variance),
+                usage: round(usage),
+                variance_of_usage: (usage > 0.0).then(|| round(variance / usage * 100.0)),
+                unit: count.unit.clone(),
+            }
+        })
+        .collect();
+    let uncounted = stock
+        .iter()
+        .filter(|s| {
+            !stock_take
+                .counts
+                .iter()
+                .any(|c| c.bar_ingredient_id == s.id)
+        })
+        .map(|s| s.id)
+        .collect();
+
+    VarianceReportModel {
+        stock_take_id: stock_take.id,
+        bar_id: stock_take.bar_id,
+        since: stock_takes
+            .iter()
+            .filter(|t| t.applied_at.is_some())
+            .filter_map(|t| t.closed_at)
+            .filter(|closed_at| *closed_at <= stock_take.created_at)
+            .max(),
+        until: stock_take.closed_at,
+        ingredients,
+        uncounted,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ingredient(abv: Option<f32>, sugar: Option<f32>) -> IngredientModel {
+        IngredientModel {
+            id: Uuid::new_v4(),
+            name: "ingredient".to_string(),
+            description: String::new(),
+            abv,
+            sugar,
+            acid: None,
+            shelf_life_id: None,
+            category_id: None,
+            origin: None,
+            producer: None,
+            tags: Vec::new(),
+            flavours: Vec::new(),
+            owner: None,
+            created_at: NaiveDateTime::default(),
+            thumbnail: None,
+        }
+    }
+
+    fn part(
+        compound: &IngredientModel,
+        ingredient: &IngredientModel,
+        parts: i16,
+    ) -> IngredientPartModel {
+        IngredientPartModel {
+            compound_id: compound.id,
+            ingredient_id: ingredient.id,
+            parts,
+        }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn dilutes_by_method() {
+        assert_close(dilution(Some("shaken"), 0.25), 0.7364375);
+        assert_close(dilution(Some("stirred"), 0.25), 0.380875);
+        assert_close(dilution(Some("built"), 0.25), 0.380875);
+        assert_close(dilution(Some("blended"), 0.25), 0.5);
+        assert_close(dilution(None, 0.25), 0.0);
+    }
+
+    #[test]
+    fn resolves_compositions_through_nested_parts() {
+        let rum = ingredient(Some(40.0), Some(0.0));
+        let falernum = ingredient(Some(11.0), Some(20.0));
+        let spiced = ingredient(None, None);
+        let juice = ingredient(None, Some(10.0));
+        let punch = ingredient(None, None);
+        // A compound's own values win over those of its parts.
+        let strong = ingredient(Some(60.0), None);
+        let parts = [
+            part(&spiced, &rum, 3),
+            part(&spiced, &falernum, 1),
+            part(&punch, &spiced, 1),
+            part(&punch, &juice, 1),
+            part(&strong, &rum, 1),
+        ];
+
+        let resolved = compositions(
+            &[
+                rum,
+                falernum,
+                spiced.clone(),
+                juice,
+                punch.clone(),
+                strong.clone(),
+            ],
+            &parts,
+        );
+
+        assert_close(resolved[&spiced.id].abv, 32.75);
+        assert_close(resolved[&spiced.id].sugar, 5.0);
+        assert_close(resolved[&punch.id].abv, 16.375);
+        assert_close(resolved[&punch.id].sugar, 7.5);
+        assert_close(resolved[&strong.id].abv, 60.0);
+    }
+
+    #[test]
+    fn finds_parts_of_parts() {
+        let (a, b, c) = (
+            ingredient(None, None),
+            ingredient(None, None),
+            ingredient(None, None),
+        );
+        let parts = [part(&a, &b, 1), part(&b, &c, 1)];
+
+        assert!(contains(&parts, a.id, a.id));
+        assert!(contains(&parts, a.id, c.id));
+        assert!(!contains(&parts, c.id, a.id));
+    }
+}
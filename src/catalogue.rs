@@ -0,0 +1,284 @@
+use std::{fmt, path::Path};
+
+use serde::Deserialize;
+
+const MAX_NAME_LENGTH: usize = 255;
+
+const MAX_INGREDIENT_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProductRecord {
+    pub barcode: String,
+    pub name: String,
+    pub ingredient: String,
+    pub size: f32,
+    pub unit: String,
+    pub abv: Option<f32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasetFormat {
+    Csv,
+    Json,
+}
+
+impl DatasetFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(Self::Csv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    Record { record: usize, message: String },
+    Dataset(String),
+    Database(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Record { record, message } => write!(f, "record {record}: {message}"),
+            Self::Dataset(message) => write!(f, "invalid dataset: {message}"),
+            Self::Database(message) => write!(f, "failed to import products: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+pub fn read_products(
+    dataset: &str,
+    format: DatasetFormat,
+) -> Result<Vec<ProductRecord>, ImportError> {
+    let records = match format {
+        DatasetFormat::Csv => read_csv(dataset)?,
+        DatasetFormat::Json => {
+            serde_json::from_str(dataset).map_err(|e| ImportError::Dataset(e.to_string()))?
+        }
+    };
+
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(i, record)| {
+            check_record(record).map_err(|message| ImportError::Record {
+                record: i + 1,
+                message,
+            })
+        })
+        .collect()
+}
+
+fn check_record(record: ProductRecord) -> Result<ProductRecord, String> {
+    let barcode = normalize_barcode(&record.barcode)
+        .ok_or_else(|| format!("invalid barcode {}", record.barcode))?;
+    let name = record.name.trim().to_string();
+    if !(2..=MAX_NAME_LENGTH).contains(&name.chars().count()) {
+        return Err(format!(
+            "name must be between 2 and {MAX_NAME_LENGTH} characters"
+        ));
+    }
+    let ingredient = record.ingredient.trim().to_lowercase();
+    if !(2..=MAX_INGREDIENT_LENGTH).contains(&ingredient.chars().count()) {
+        return Err(format!(
+            "ingredient must be between 2 and {MAX_INGREDIENT_LENGTH} characters"
+        ));
+    }
+    if !(record.size.is_finite() && record.size > 0.0) {
+        return Err("size must be positive".to_string());
+    }
+    if record.abv.is_some_and(|abv| !(0.0..=100.0).contains(&abv)) {
+        return Err("abv must be between 0 and 100".to_string());
+    }
+
+    Ok(ProductRecord {
+        barcode,
+        name,
+        ingredient,
+        unit: record.unit.trim().to_string(),
+        ..record
+    })
+}
+
+/// Pads to GTIN-14 so a product is found whichever form of its barcode is
+/// scanned.
+pub(crate) fn normalize_barcode(barcode: &str) -> Option<String> {
+    let barcode = barcode.trim();
+    if !matches!(barcode.len(), 8 | 12 | 13 | 14) || !barcode.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    // Digits are weighted 3 and 1 alternately, from the one next to the check
+    // digit, and the check digit brings the sum up to a multiple of ten.
+    let digits: Vec<u32> = barcode.bytes().map(|b| u32::from(b - b'0')).collect();
+    let (check, body) = digits.split_last()?;
+    let sum: u32 = body
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+        .sum();
+    if (10 - sum % 10) % 10 != *check {
+        return None;
+    }
+
+    Some(format!("{barcode:0>14}"))
+}
+
+fn read_csv(dataset: &str) -> Result<Vec<ProductRecord>, ImportError> {
+    let mut rows = parse_csv(dataset)?.into_iter();
+    let header: Vec<String> = rows
+        .next()
+        .ok_or_else(|| ImportError::Dataset("missing header row".to_string()))?
+        .into_iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect();
+    let column = |name: &str| header.iter().position(|c| c == name);
+    let required = |name: &str| {
+        column(name).ok_or_else(|| ImportError::Dataset(format!("missing {name} column")))
+    };
+    let (barcode, name, ingredient, size, unit) = (
+        required("barcode")?,
+        required("name")?,
+        required("ingredient")?,
+        required("size")?,
+        required("unit")?,
+    );
+    let abv = column("abv");
+
+    rows.enumerate()
+        .map(|(i, row)| {
+            let error = |message: String| ImportError::Record {
+                record: i + 1,
+                message,
+            };
+            if row.len() != header.len() {
+                return Err(error(format!(
+                    "expected {} fields but found {}",
+                    header.len(),
+                    row.len()
+                )));
+            }
+            let number = |field: &str, value: &str| {
+                value
+                    .trim()
+                    .parse::<f32>()
+                    .map_err(|_| error(format!("{field} must be a number")))
+            };
+
+            Ok(ProductRecord {
+                barcode: row[barcode].clone(),
+                name: row[name].clone(),
+                ingredient: row[ingredient].clone(),
+                size: number("size", &row[size])?,
+                unit: row[unit].clone(),
+                abv: match abv.map(|abv| row[abv].trim()) {
+                    None | Some("") => None,
+                    Some(value) => Some(number("abv", value)?),
+                },
+            })
+        })
+        .collect()
+}
+
+fn parse_csv(dataset: &str) -> Result<Vec<Vec<String>>, ImportError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = dataset.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => row.push(std::mem::take(&mut field)),
+            '\r' if !quoted && chars.peek() == Some(&'\n') => {}
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|f| !f.trim().is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            c => field.push(c),
+        }
+    }
+    if quoted {
+        return Err(ImportError::Dataset(
+            "unterminated quoted field".to_string(),
+        ));
+    }
+    row.push(field);
+    if row.iter().any(|f| !f.trim().is_empty()) {
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quoted_fields() {
+        let rows =
+            parse_csv("name,notes\n\"Gin, London Dry\",\"a \"\"dry\"\"\nfinish\"\n").unwrap();
+
+        assert_eq!(
+            rows,
+            [
+                vec!["name", "notes"],
+                vec!["Gin, London Dry", "a \"dry\"\nfinish"],
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_crlf_line_endings() {
+        let rows =
+            parse_csv("barcode,name\r\n080812345674,gin\r\n\r\n5000299000014,vermouth").unwrap();
+
+        assert_eq!(
+            rows,
+            [
+                vec!["barcode", "name"],
+                vec!["080812345674", "gin"],
+                vec!["5000299000014", "vermouth"],
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(parse_csv("name\n\"gin\n").is_err());
+    }
+
+    #[test]
+    fn normalizes_barcodes_of_the_same_product() {
+        let gtin = Some("00080812345674".to_string());
+
+        assert_eq!(normalize_barcode("080812345674"), gtin);
+        assert_eq!(normalize_barcode("0080812345674"), gtin);
+        assert_eq!(normalize_barcode("00080812345674"), gtin);
+        assert_eq!(normalize_barcode(" 080812345674 "), gtin);
+    }
+
+    #[test]
+    fn rejects_invalid_barcodes() {
+        for barcode in ["080812345675", "08081234567", "08081234567a", ""] {
+            assert_eq!(normalize_barcode(barcode), None, "barcode {barcode}");
+        }
+    }
+}
@@ -0,0 +1,732 @@
+use std::{
+    fmt, fs,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::Duration;
+use serde::Deserialize;
+
+use crate::MEDIA_BUCKET;
+
+macro_rules! layer {
+    ($name:ident { $($field:ident: $ty:ty),* $(,)? }) => {
+        #[derive(Debug, Default, Clone, Deserialize)]
+        #[serde(default, deny_unknown_fields)]
+        pub struct $name {
+            $(pub $field: Option<$ty>,)*
+        }
+
+        impl $name {
+            fn overlay(self, higher: Self) -> Self {
+                Self {
+                    $($field: higher.$field.or(self.$field),)*
+                }
+            }
+        }
+    };
+}
+
+layer!(ServerLayer {
+    bind: String,
+    readiness_timeout_ms: u64,
+    pre_stop_delay_secs: u64,
+    drain_timeout_secs: u64,
+});
+layer!(TlsLayer {
+    cert_path: PathBuf,
+    key_path: PathBuf,
+    redirect_bind: String,
+});
+layer!(HttpLayer {
+    cors_origins: Vec<String>,
+    compression: bool,
+    max_body_bytes: usize,
+    request_timeout_secs: u64,
+    security_headers: bool,
+    hsts_max_age_secs: u64,
+});
+layer!(DatabaseLayer {
+    url: String,
+    max_connections: u32,
+    auto_migrate: bool,
+});
+layer!(S3Layer {
+    url: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    bucket: String,
+});
+layer!(AuthLayer {
+    signing_key: String,
+    token_ttl_hours: i64,
+});
+layer!(LogLayer {
+    level: String,
+    format: String,
+    otlp_endpoint: String,
+});
+layer!(RateLimitLayer {
+    enabled: bool,
+    auth_per_minute: u32,
+    auth_burst: u32,
+    api_per_minute: u32,
+    api_burst: u32,
+    trust_forwarded_for: bool,
+    trusted_proxies: usize,
+});
+layer!(MetricsLayer {
+    token: String,
+    stats_interval_secs: u64,
+});
+layer!(LimitsLayer {
+    storage_quota: i64,
+    max_upload_size: i64,
+    uploads_per_hour: i64,
+});
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigLayer {
+    pub server: ServerLayer,
+    pub tls: TlsLayer,
+    pub http: HttpLayer,
+    pub database: DatabaseLayer,
+    pub s3: S3Layer,
+    pub auth: AuthLayer,
+    pub log: LogLayer,
+    pub metrics: MetricsLayer,
+    pub limits: LimitsLayer,
+    pub rate_limit: RateLimitLayer,
+}
+
+impl ConfigLayer {
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .map_err(|e| ConfigError(vec![format!("{}: {e}", path.display())]))?;
+
+        toml::from_str(&contents).map_err(|e| ConfigError(vec![format!("{}: {e}", path.display())]))
+    }
+
+    fn from_env(errors: &mut Vec<String>) -> Self {
+        Self::from_vars(|name| std::env::var(name).ok(), errors)
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>, errors: &mut Vec<String>) -> Self {
+        Self {
+            server: ServerLayer {
+                bind: var("BIND_ADDRESS"),
+                readiness_timeout_ms: parse_var(&var, "READINESS_TIMEOUT_MS", errors),
+                pre_stop_delay_secs: parse_var(&var, "PRE_STOP_DELAY_SECS", errors),
+                drain_timeout_secs: parse_var(&var, "DRAIN_TIMEOUT_SECS", errors),
+            },
+            tls: TlsLayer {
+                cert_path: var("TLS_CERT_PATH").map(PathBuf::from),
+                key_path: var("TLS_KEY_PATH").map(PathBuf::from),
+                redirect_bind: var("TLS_REDIRECT_BIND"),
+            },
+            http: HttpLayer {
+                cors_origins: var("CORS_ORIGINS").map(|origins| {
+                    origins
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|origin| !origin.is_empty())
+                        .map(String::from)
+                        .collect()
+                }),
+                compression: parse_var(&var, "COMPRESSION", errors),
+                max_body_bytes: parse_var(&var, "MAX_BODY_BYTES", errors),
+                request_timeout_secs: parse_var(&var, "REQUEST_TIMEOUT_SECS", errors),
+                security_headers: parse_var(&var, "SECURITY_HEADERS", errors),
+                hsts_max_age_secs: parse_var(&var, "HSTS_MAX_AGE_SECS", errors),
+            },
+            database: DatabaseLayer {
+                url: var("DATABASE_URL"),
+                max_connections: parse_var(&var, "DATABASE_MAX_CONNECTIONS", errors),
+                auto_migrate: parse_var(&var, "AUTO_MIGRATE", errors),
+            },
+            s3: S3Layer {
+                url: var("S3_URL"),
+                region: var("S3_REGION"),
+                access_key_id: var("S3_ACCESS_KEY_ID"),
+                secret_access_key: var("S3_SECRET_ACCESS_KEY"),
+                bucket: var("S3_BUCKET"),
+            },
+            auth: AuthLayer {
+                signing_key: var("SIGNING_KEY"),
+                token_ttl_hours: parse_var(&var, "TOKEN_TTL_HOURS", errors),
+            },
+            log: LogLayer {
+                level: var("LOG_LEVEL").or_else(|| var("RUST_LOG")),
+                format: var("LOG_FORMAT"),
+                otlp_endpoint: var("OTEL_EXPORTER_OTLP_ENDPOINT"),
+            },
+            metrics: MetricsLayer {
+                token: var("METRICS_TOKEN"),
+                stats_interval_secs: parse_var(&var, "METRICS_STATS_INTERVAL_SECS", errors),
+            },
+            limits: LimitsLayer {
+                storage_quota: parse_var(&var, "STORAGE_QUOTA_BYTES", errors),
+                max_upload_size: parse_var(&var, "MAX_UPLOAD_BYTES", errors),
+                uploads_per_hour: parse_var(&var, "UPLOADS_PER_HOUR", errors),
+            },
+            rate_limit: RateLimitLayer {
+                enabled: parse_var(&var, "RATE_LIMIT_ENABLED", errors),
+                auth_per_minute: parse_var(&var, "RATE_LIMIT_AUTH_PER_MINUTE", errors),
+                auth_burst: parse_var(&var, "RATE_LIMIT_AUTH_BURST", errors),
+                api_per_minute: parse_var(&var, "RATE_LIMIT_API_PER_MINUTE", errors),
+                api_burst: parse_var(&var, "RATE_LIMIT_API_BURST", errors),
+                trust_forwarded_for: parse_var(&var, "TRUST_FORWARDED_FOR", errors),
+                trusted_proxies: parse_var(&var, "TRUSTED_PROXIES", errors),
+            },
+        }
+    }
+
+    /// Later sources take precedence: file, then environment, then flags.
+    fn stack(file: Option<&Path>, flags: Self, errors: &mut Vec<String>) -> Self {
+        let file = match file.map(Self::from_file) {
+            Some(Ok(layer)) => layer,
+            Some(Err(e)) => {
+                errors.extend(e.0);
+                Self::default()
+            }
+            None => Self::default(),
+        };
+
+        file.overlay(Self::from_env(errors)).overlay(flags)
+    }
+
+    pub fn overlay(self, higher: Self) -> Self {
+        Self {
+            server: self.server.overlay(higher.server),
+            tls: self.tls.overlay(higher.tls),
+            http: self.http.overlay(higher.http),
+            database: self.database.overlay(higher.database),
+            s3: self.s3.overlay(higher.s3),
+            auth: self.auth.overlay(higher.auth),
+            log: self.log.overlay(higher.log),
+            metrics: self.metrics.overlay(higher.metrics),
+            limits: self.limits.overlay(higher.limits),
+            rate_limit: self.rate_limit.overlay(higher.rate_limit),
+        }
+    }
+}
+
+fn parse_var<T>(
+    var: &impl Fn(&str) -> Option<String>,
+    name: &str,
+    errors: &mut Vec<String>,
+) -> Option<T>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let value = var(name)?;
+    value
+        .parse()
+        .map_err(|e| errors.push(format!("{name}: invalid value {value:?}: {e}")))
+        .ok()
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub bind: SocketAddr,
+    pub readiness_timeout: std::time::Duration,
+    /// Readiness fails during this delay so load balancers stop sending traffic
+    /// before the listener closes.
+    pub pre_stop_delay: std::time::Duration,
+    pub drain_timeout: std::time::Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    pub cert_path: PathBuf,
+    pub key_path: PathBuf,
+    pub redirect_bind: Option<SocketAddr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub cors_origins: Vec<String>,
+    pub compression: bool,
+    pub max_body_size: usize,
+    pub request_timeout: std::time::Duration,
+    pub security_headers: bool,
+    /// Only enable this when the API is served over HTTPS.
+    pub hsts_max_age: Option<std::time::Duration>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub max_connections: u32,
+    pub auto_migrate: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct S3Config {
+    pub url: String,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub bucket: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Limits {
+    pub storage_quota: i64,
+    pub max_upload_size: i64,
+    pub uploads_per_hour: i64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            storage_quota: 1024 * 1024 * 1024,
+            max_upload_size: 10 * 1024 * 1024,
+            uploads_per_hour: 120,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub per_minute: u32,
+    pub burst: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub auth: Quota,
+    pub api: Quota,
+    /// Only enable this behind a reverse proxy that sets it.
+    pub trust_forwarded_for: bool,
+    /// The client is the entry this many from the right, as anything further
+    /// left was sent by the client itself.
+    pub trusted_proxies: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct AuthConfig {
+    pub signing_key: String,
+    pub token_ttl: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Json,
+    Text,
+}
+
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    pub level: String,
+    pub format: LogFormat,
+    pub otlp_endpoint: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsConfig {
+    pub token: Option<String>,
+    pub stats_interval: std::time::Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub tls: Option<TlsConfig>,
+    pub http: HttpConfig,
+    pub database: DatabaseConfig,
+    pub s3: S3Config,
+    pub auth: AuthConfig,
+    pub log: LogConfig,
+    pub metrics: MetricsConfig,
+    pub limits: Limits,
+    pub rate_limit: RateLimitConfig,
+}
+
+impl Config {
+    pub fn load(file: Option<&Path>, flags: ConfigLayer) -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+        let layer = ConfigLayer::stack(file, flags, &mut errors);
+
+        let server = ServerConfig::resolve(layer.server, &mut errors);
+        let tls = TlsConfig::resolve(layer.tls, &mut errors);
+        let http = HttpConfig::resolve(layer.http, &mut errors);
+        let database = DatabaseConfig::resolve(layer.database, &mut errors);
+        let s3 = S3Config::resolve(layer.s3, &mut errors);
+        let auth = AuthConfig::resolve(layer.auth, &mut errors);
+        let log = LogConfig::resolve(layer.log, &mut errors);
+        let metrics = MetricsConfig::resolve(layer.metrics, &mut errors);
+        let limits = resolve_limits(layer.limits, &mut errors);
+        let rate_limit = RateLimitConfig::resolve(layer.rate_limit, &mut errors);
+
+        ConfigError::check(errors)?;
+        Ok(Self {
+            server: server.expect("server config was validated"),
+            tls,
+            http: http.expect("http config was validated"),
+            database: database.expect("database config was validated"),
+            s3: s3.expect("s3 config was validated"),
+            auth: auth.expect("auth config was validated"),
+            log: log.expect("log config was validated"),
+            metrics,
+            limits,
+            rate_limit,
+        })
+    }
+}
+
+impl ServerConfig {
+    fn resolve(layer: ServerLayer, errors: &mut Vec<String>) -> Option<Self> {
+        let bind = layer.bind.unwrap_or("0.0.0.0:8000".to_string());
+        let bind = bind
+            .parse()
+            .map_err(|e| errors.push(format!("server.bind: invalid address {bind:?}: {e}")))
+            .ok();
+
+        let readiness_timeout_ms = layer.readiness_timeout_ms.unwrap_or(2000);
+        if readiness_timeout_ms == 0 {
+            errors.push("server.readiness_timeout_ms: must be positive".to_string());
+        }
+
+        Some(Self {
+            bind: bind?,
+            readiness_timeout: std::time::Duration::from_millis(readiness_timeout_ms),
+            pre_stop_delay: std::time::Duration::from_secs(layer.pre_stop_delay_secs.unwrap_or(5)),
+            drain_timeout: std::time::Duration::from_secs(layer.drain_timeout_secs.unwrap_or(30)),
+        })
+        .filter(|_| readiness_timeout_ms > 0)
+    }
+}
+
+impl TlsConfig {
+    fn resolve(layer: TlsLayer, errors: &mut Vec<String>) -> Option<Self> {
+        let redirect_bind = layer.redirect_bind.and_then(|bind| {
+            bind.parse()
+                .map_err(|e| {
+                    errors.push(format!("tls.redirect_bind: invalid address {bind:?}: {e}"))
+                })
+                .ok()
+        });
+
+        let (cert_path, key_path) = match (layer.cert_path, layer.key_path) {
+            (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+            (None, None) => {
+                if redirect_bind.is_some() {
+                    errors.push(
+                        "tls.redirect_bind: requires tls.cert_path and tls.key_path".to_string(),
+                    );
+                }
+                return None;
+            }
+            (cert_path, _) => {
+                let missing = if cert_path.is_none() {
+                    "tls.cert_path"
+                } else {
+                    "tls.key_path"
+                };
+                errors.push(format!("{missing}: required when TLS is enabled"));
+                return None;
+            }
+        };
+
+        let mut readable = true;
+        for (name, path) in [("tls.cert_path", &cert_path), ("tls.key_path", &key_path)] {
+            if let Err(e) = fs::metadata(path) {
+                errors.push(format!("{name}: {}: {e}", path.display()));
+                readable = false;
+            }
+        }
+
+        Some(Self {
+            cert_path,
+            key_path,
+            redirect_bind,
+        })
+        .filter(|_| readable)
+    }
+}
+
+impl HttpConfig {
+    fn resolve(layer: HttpLayer, errors: &mut Vec<String>) -> Option<Self> {
+        let cors_origins = layer.cors_origins.unwrap_or_default();
+        let invalid_origins: Vec<_> = cors_origins
+            .iter()
+            .filter(|origin| !is_origin(origin))
+            .collect();
+        for origin in &invalid_origins {
+            errors.push(format!(
+                "http.cors_origins: invalid origin {origin:?}, expected \"*\" or a scheme and host such as \"https://example.com\""
+            ));
+        }
+
+        let max_body_size = layer.max_body_bytes.unwrap_or(2 * 1024 * 1024);
+        if max_body_size == 0 {
+            errors.push("http.max_body_bytes: must be positive".to_string());
+        }
+
+        let request_timeout_secs = layer.request_timeout_secs.unwrap_or(30);
+        if request_timeout_secs == 0 {
+            errors.push("http.request_timeout_secs: must be positive".to_string());
+        }
+
+        let valid = invalid_origins.is_empty() && max_body_size > 0 && request_timeout_secs > 0;
+        Some(Self {
+            cors_origins,
+            compression: layer.compression.unwrap_or(true),
+            max_body_size,
+            request_timeout: std::time::Duration::from_secs(request_timeout_secs),
+            security_headers: layer.security_headers.unwrap_or(true),
+            hsts_max_age: layer
+                .hsts_max_age_secs
+                .filter(|secs| *secs > 0)
+                .map(std::time::Duration::from_secs),
+        })
+        .filter(|_| valid)
+    }
+}
+
+fn is_origin(origin: &str) -> bool {
+    if origin == "*" {
+        return true;
+    }
+
+    let Some((scheme, host)) = origin.split_once("://") else {
+        return false;
+    };
+
+    matches!(scheme, "http" | "https")
+        && !host.is_empty()
+        && !host.contains(['/', '?', '#'])
+        && origin.is_ascii()
+        && !origin.contains(char::is_whitespace)
+}
+
+impl DatabaseConfig {
+    pub fn load(file: Option<&Path>, flags: ConfigLayer) -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+        let layer = ConfigLayer::stack(file, flags, &mut errors);
+        let config = Self::resolve(layer.database, &mut errors);
+
+        ConfigError::check(errors)?;
+        Ok(config.expect("database config was validated"))
+    }
+
+    fn resolve(layer: DatabaseLayer, errors: &mut Vec<String>) -> Option<Self> {
+        let url = required(layer.url, "database.url", errors).filter(|url| {
+            let supported = ["postgres://", "postgresql://", "sqlite:"]
+                .iter()
+                .any(|scheme| url.starts_with(scheme));
+            if !supported {
+                errors.push(
+                    "database.url: must be a postgres:// or sqlite: connection string".to_string(),
+                );
+            }
+            supported
+        });
+        let max_connections = layer.max_connections.unwrap_or(16);
+        if max_connections == 0 {
+            errors.push("database.max_connections: must be at least 1".to_string());
+        }
+
+        Some(Self {
+            url: url?,
+            max_connections,
+            auto_migrate: layer.auto_migrate.unwrap_or(false),
+        })
+        .filter(|_| max_connections > 0)
+    }
+
+    pub fn is_sqlite(&self) -> bool {
+        self.url.starts_with("sqlite:")
+    }
+}
+
+impl S3Config {
+    fn resolve(layer: S3Layer, errors: &mut Vec<String>) -> Option<Self> {
+        let url = required(layer.url, "s3.url", errors);
+        let access_key_id = required(layer.access_key_id, "s3.access_key_id", errors);
+        let secret_access_key = required(layer.secret_access_key, "s3.secret_access_key", errors);
+        let bucket = layer.bucket.unwrap_or(MEDIA_BUCKET.to_string());
+
+        // https://docs.aws.amazon.com/AmazonS3/latest/userguide/bucketnamingrules.html
+        let valid_bucket = (3..=63).contains(&bucket.len())
+            && bucket
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '.')
+            && !bucket.starts_with(['-', '.'])
+            && !bucket.ends_with(['-', '.']);
+        if !valid_bucket {
+            errors.push(format!("s3.bucket: invalid bucket name {bucket:?}"));
+        }
+
+        Some(Self {
+            url: url?,
+            region: layer.region.unwrap_or("us-east-1".to_string()),
+            access_key_id: access_key_id?,
+            secret_access_key: secret_access_key?,
+            bucket,
+        })
+        .filter(|_| valid_bucket)
+    }
+}
+
+impl AuthConfig {
+    fn resolve(layer: AuthLayer, errors: &mut Vec<String>) -> Option<Self> {
+        let signing_key = required(layer.signing_key, "auth.signing_key", errors);
+        let token_ttl_hours = layer.token_ttl_hours.unwrap_or(24 * 14);
+        if token_ttl_hours <= 0 {
+            errors.push("auth.token_ttl_hours: must be positive".to_string());
+        }
+
+        Some(Self {
+            signing_key: signing_key?,
+            token_ttl: Duration::hours(token_ttl_hours),
+        })
+        .filter(|_| token_ttl_hours > 0)
+    }
+}
+
+impl LogConfig {
+    fn resolve(layer: LogLayer, errors: &mut Vec<String>) -> Option<Self> {
+        let level = layer.level.unwrap_or("info".to_string());
+        let valid_level = tracing_subscriber::EnvFilter::try_new(&level)
+            .map_err(|e| errors.push(format!("log.level: invalid filter {level:?}: {e}")))
+            .is_ok();
+
+        let format = match layer.format.as_deref().unwrap_or("json") {
+            "json" => Some(LogFormat::Json),
+            "text" => Some(LogFormat::Text),
+            format => {
+                errors.push(format!(
+                    "log.format: expected \"json\" or \"text\", got {format:?}"
+                ));
+                None
+            }
+        };
+
+        let otlp_supported = layer.otlp_endpoint.is_none() || cfg!(feature = "otlp");
+        if !otlp_supported {
+            errors.push(
+                "log.otlp_endpoint: this build does not include the otlp feature".to_string(),
+            );
+        }
+
+        Some(Self {
+            level,
+            format: format?,
+            otlp_endpoint: layer.otlp_endpoint,
+        })
+        .filter(|_| valid_level && otlp_supported)
+    }
+}
+
+impl MetricsConfig {
+    fn resolve(layer: MetricsLayer, errors: &mut Vec<String>) -> Self {
+        if layer.token.as_ref().is_some_and(|token| token.is_empty()) {
+            errors.push("metrics.token: must not be empty".to_string());
+        }
+        let stats_interval_secs = layer.stats_interval_secs.unwrap_or(60);
+        if stats_interval_secs == 0 {
+            errors.push("metrics.stats_interval_secs: must be positive".to_string());
+        }
+
+        Self {
+            token: layer.token,
+            stats_interval: std::time::Duration::from_secs(stats_interval_secs),
+        }
+    }
+}
+
+impl RateLimitConfig {
+    fn resolve(layer: RateLimitLayer, errors: &mut Vec<String>) -> Self {
+        let config = Self {
+            enabled: layer.enabled.unwrap_or(true),
+            auth: Quota {
+                per_minute: layer.auth_per_minute.unwrap_or(10),
+                burst: layer.auth_burst.unwrap_or(5),
+            },
+            api: Quota {
+                per_minute: layer.api_per_minute.unwrap_or(600),
+                burst: layer.api_burst.unwrap_or(60),
+            },
+            trust_forwarded_for: layer.trust_forwarded_for.unwrap_or(false),
+            trusted_proxies: layer.trusted_proxies.unwrap_or(1),
+        };
+
+        if config.trusted_proxies == 0 {
+            errors.push("rate_limit.trusted_proxies: must be positive".to_string());
+        }
+
+        for (name, quota) in [("auth", config.auth), ("api", config.api)] {
+            if quota.per_minute == 0 {
+                errors.push(format!("rate_limit.{name}_per_minute: must be positive"));
+            }
+            if quota.burst == 0 {
+                errors.push(format!("rate_limit.{name}_burst: must be positive"));
+            }
+        }
+
+        config
+    }
+}
+
+fn resolve_limits(layer: LimitsLayer, errors: &mut Vec<String>) -> Limits {
+    let defaults = Limits::default();
+    let limits = Limits {
+        storage_quota: layer.storage_quota.unwrap_or(defaults.storage_quota),
+        max_upload_size: layer.max_upload_size.unwrap_or(defaults.max_upload_size),
+        uploads_per_hour: layer.uploads_per_hour.unwrap_or(defaults.uploads_per_hour),
+    };
+
+    if limits.storage_quota < 0 {
+        errors.push("limits.storage_quota: must not be negative".to_string());
+    }
+    if limits.max_upload_size <= 0 {
+        errors.push("limits.max_upload_size: must be positive".to_string());
+    }
+    if limits.uploads_per_hour < 0 {
+        errors.push("limits.uploads_per_hour: must not be negative".to_string());
+    }
+
+    limits
+}
+
+fn required<T>(value: Option<T>, name: &str, errors: &mut Vec<String>) -> Option<T> {
+    if value.is_none() {
+        errors.push(format!("{name}: missing required setting"));
+    }
+
+    value
+}
+
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl ConfigError {
+    fn check(errors: Vec<String>) -> Result<(), Self> {
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Self(errors))
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "invalid configuration:")?;
+        for error in &self.0 {
+            writeln!(f, "  {error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -1,14 +1,35 @@
 use aws_sdk_s3::error::SdkError;
 use axum::{
     body::Body,
-    extract::multipart::MultipartError,
-    http::{header::InvalidHeaderValue, StatusCode},
+    extract::{multipart::MultipartError, Request},
+    http::{header, header::InvalidHeaderValue, StatusCode},
+    middleware::Next,
     response::{IntoResponse, Response},
 };
+use serde_json::{json, Value};
+use sqlx::error::ErrorKind;
+
+use crate::REQUEST_ID_HEADER;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+pub(crate) async fn scope_request_id(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+
+    REQUEST_ID.scope(id, next.run(request)).await
+}
 
 pub struct Error {
     status_code: StatusCode,
     message: String,
+    details: Option<Value>,
 }
 
 impl Error {
@@ -16,15 +37,48 @@ impl Error {
         Self {
             status_code: status,
             message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn with_details<S: Into<String>>(status: StatusCode, message: S, details: Value) -> Self {
+        Self {
+            status_code: status,
+            message: message.into(),
+            details: Some(details),
         }
     }
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.status_code, self.message)
+    }
+}
+
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
+        metrics::counter!(crate::monitoring::ERRORS, "status" => self.status_code.as_u16().to_string())
+            .increment(1);
+
+        if self.status_code.is_server_error() {
+            tracing::error!(status = %self.status_code, message = %self.message);
+        } else {
+            tracing::debug!(status = %self.status_code, message = %self.message);
+        }
+
+        let mut body = json!({ "message": self.message });
+        if let Some(details) = self.details {
+            body["details"] = details;
+        }
+        if let Ok(id) = REQUEST_ID.try_with(|id| id.clone()) {
+            body["requestId"] = json!(id);
+        }
+
         Response::builder()
             .status(self.status_code)
-            .body(Body::from(self.message))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.to_string()))
             .expect("failed to build error response")
     }
 }
@@ -35,6 +89,12 @@ impl From<InvalidHeaderValue> for Error {
     }
 }
 
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, value.to_string())
+    }
+}
+
 impl From<jsonwebtoken::errors::Error> for Error {
     fn from(value: jsonwebtoken::errors::Error) -> Self {
         match value.kind() {
@@ -63,20 +123,33 @@ impl<E, R> From<SdkError<E, R>> for Error {
 
 impl From<sqlx::Error> for Error {
     fn from(value: sqlx::Error) -> Self {
+        // Queries are scoped to their owner, so a row belonging to someone
+        // else is reported the same as one that does not exist.
+        if let sqlx::Error::RowNotFound = value {
+            return Self::new(StatusCode::NOT_FOUND, "no rows");
+        }
+
+        // Constraint violations are matched by kind rather than by code, as
+        // each backend has its own codes for them.
         if let Some(e) = value.as_database_error() {
-            if let Some(code) = e.code() {
-                return match code.to_string().as_str() {
-                    "02000" => Self::new(StatusCode::NOT_FOUND, "no rows"),
-                    "23502" => Self::new(StatusCode::BAD_REQUEST, "not null violation"),
-                    "23503" => Self::new(StatusCode::NOT_FOUND, "foreign key violation"),
-                    "23505" => Self::new(StatusCode::CONFLICT, "unique violation"),
-                    "23514" => Self::new(StatusCode::BAD_REQUEST, "check violation"),
-                    code => Self::new(
+            return match e.kind() {
+                ErrorKind::NotNullViolation => {
+                    Self::new(StatusCode::BAD_REQUEST, "not null violation")
+                }
+                ErrorKind::ForeignKeyViolation => {
+                    Self::new(StatusCode::NOT_FOUND, "foreign key violation")
+                }
+                ErrorKind::UniqueViolation => Self::new(StatusCode::CONFLICT, "unique violation"),
+                ErrorKind::CheckViolation => Self::new(StatusCode::BAD_REQUEST, "check violation"),
+                _ => match e.code().as_deref() {
+                    Some("02000") => Self::new(StatusCode::NOT_FOUND, "no rows"),
+                    Some(code) => Self::new(
                         StatusCode::INTERNAL_SERVER_ERROR,
                         format!("unknown error code: {code}"),
                     ),
-                };
-            }
+                    None => Self::new(StatusCode::INTERNAL_SERVER_ERROR, e.message().to_string()),
+                },
+            };
         };
 
         Self::new(StatusCode::INTERNAL_SERVER_ERROR, value.to_string())
@@ -1,17 +1,26 @@
 use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode},
-    response::IntoResponse,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use serde::Deserialize;
 use std::sync::Arc;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
 use crate::{
-    model::{BarModel, CreateBarSchema},
+    calculator::{self, IngredientCostModel, RecipeCostModel, ShoppingListModel},
+    error::Error,
+    model::{
+        BarIngredientLevelsSchema, BarIngredientModel, BarModel, CreateBarIngredientSchema,
+        CreateBarSchema, CreatePurchaseSchema, PurchaseModel,
+    },
     AppState, Auth,
 };
 
+const MAX_SHOPPING_RECIPES: usize = 50;
+
 #[utoipa::path(
     post,
     path = "/bars",
@@ -24,6 +33,7 @@ use crate::{
     ),
     tag = crate::BAR_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn create_bar_handler(
     header: HeaderMap,
     State(data): State<Arc<AppState>>,
@@ -33,7 +43,7 @@ pub(crate) async fn create_bar_handler(
 
     Ok((
         StatusCode::CREATED,
-        Json(BarModel::create(&data.db, owner_id, body).await?),
+        Json(data.repo.create_bar(owner_id, body).await?),
     ))
 }
 
@@ -48,13 +58,14 @@ pub(crate) async fn create_bar_handler(
     ),
     tag = crate::BAR_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn list_bars_handler(
     header: HeaderMap,
     State(data): State<Arc<AppState>>,
 ) -> crate::Result<impl IntoResponse> {
     let owner_id = Auth::decode_header(&data.signing_key, header)?;
 
-    Ok(Json(BarModel::all(&data.db, owner_id).await?))
+    Ok(Json(data.repo.list_bars(owner_id).await?))
 }
 
 #[utoipa::path(
@@ -71,6 +82,7 @@ pub(crate) async fn list_bars_handler(
     ),
     tag = crate::BAR_TAG
 )]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
 pub(crate) async fn get_bar_handler(
     header: HeaderMap,
     Path(bar_id): Path<Uuid>,
@@ -78,5 +90,416 @@ pub(crate) async fn get_bar_handler(
 ) -> crate::Result<impl IntoResponse> {
     let owner_id = Auth::decode_header(&data.signing_key, header)?;
 
-    Ok(Json(BarModel::retrieve(&data.db, owner_id, bar_id).await?))
+    Ok(Json(data.repo.retrieve_bar(owner_id, bar_id).await?))
+}
+
+fn check_levels(
+    par: Option<f32>,
+    minimum: Option<f32>,
+    bottle_size: Option<f32>,
+) -> crate::Result<()> {
+    let bad_request = |message: &str| Error::new(StatusCode::BAD_REQUEST, message);
+    if par.is_some_and(|p| p < 0.0) || minimum.is_some_and(|m| m < 0.0) {
+        return Err(bad_request("par and minimum must not be negative"));
+    }
+    if bottle_size.is_some_and(|s| s <= 0.0) {
+        return Err(bad_request("bottleSize must be positive"));
+    }
+    if let (Some(par), Some(minimum)) = (par, minimum)
+        && minimum > par
+    {
+        return Err(bad_request("minimum must not be above par"));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/ingredients",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to stock")
+    ),
+    request_body(content = CreateBarIngredientSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = BarIngredientModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The quantity or a stock level is out of range"),
+        (status = CONFLICT, description = "The bar already stocks the ingredient")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn add_bar_ingredient_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateBarIngredientSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    if body.quantity < 0.0 {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            "quantity must not be negative",
+        ));
+    }
+    check_levels(body.par, body.minimum, body.bottle_size)?;
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    data.repo
+        .retrieve_ingredient(owner_id, body.ingredient_id)
+        .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.add_bar_ingredient(&bar, body).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/ingredients",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = Vec<BarIngredientModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn list_bar_ingredients_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(data.repo.bar_ingredients(&bar).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/bars/{bar_id}/ingredients/{bar_ingredient_id}/levels",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("bar_ingredient_id" = Uuid, Path, description = "ID of the bar ingredient to update")
+    ),
+    request_body(content = BarIngredientLevelsSchema, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Success", body = BarIngredientModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "A level is out of range, or the minimum is above par")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, bar_ingredient_id = %bar_ingredient_id))]
+pub(crate) async fn set_bar_ingredient_levels_handler(
+    header: HeaderMap,
+    Path((bar_id, bar_ingredient_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<BarIngredientLevelsSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    check_levels(body.par, body.minimum, body.bottle_size)?;
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(
+        data.repo
+            .set_bar_ingredient_levels(&bar, bar_ingredient_id, body)
+            .await?,
+    ))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub(crate) struct ShoppingListQuery {
+    recipes: Option<String>,
+    servings: Option<f64>,
+    format: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/shopping-list",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to shop for"),
+        ShoppingListQuery
+    ),
+    responses(
+        (status = OK, description = "Success", content(
+            (ShoppingListModel = "application/json"),
+            (String = "text/plain"),
+            (String = "text/csv")
+        )),
+        (status = BAD_REQUEST, description = "A recipe ID or the format is invalid, too many recipes are selected, or the servings are not a positive number")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn get_shopping_list_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    Query(query): Query<ShoppingListQuery>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<Response> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bad_request = |message: &str| Error::new(StatusCode::BAD_REQUEST, message);
+    let servings = query.servings.unwrap_or(1.0);
+    if !servings.is_finite() || servings <= 0.0 {
+        return Err(bad_request("servings must be a positive number"));
+    }
+    let format = query.format.as_deref().unwrap_or("json");
+    if !["json", "text", "csv"].contains(&format) {
+        return Err(bad_request("format must be one of json, text or csv"));
+    }
+    let mut recipe_ids = query
+        .recipes
+        .iter()
+        .flat_map(|r| r.split(','))
+        .filter(|r| !r.trim().is_empty())
+        .map(|r| r.trim().parse::<Uuid>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| bad_request("recipes must be comma separated recipe IDs"))?;
+    recipe_ids.sort();
+    recipe_ids.dedup();
+    if recipe_ids.len() > MAX_SHOPPING_RECIPES {
+        return Err(bad_request(&format!(
+            "at most {MAX_SHOPPING_RECIPES} recipes can be shopped for at once"
+        )));
+    }
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock = data.repo.bar_ingredients(&bar).await?;
+    let categories = data.repo.list_categories(owner_id).await?;
+    let mut recipes = Vec::new();
+    for id in recipe_ids {
+        let recipe = data.repo.retrieve_recipe(owner_id, id).await?;
+        let lines = data.repo.recipe_ingredients(&recipe).await?;
+        recipes.push(calculator::substitute(lines, &stock, &categories));
+    }
+    let list = calculator::shopping_list(
+        &bar,
+        &stock,
+        &recipes,
+        servings,
+        &data.repo.list_ingredients(owner_id).await?,
+        &data.repo.ingredient_parts(owner_id).await?,
+    );
+
+    Ok(match format {
+        "text" => (
+            [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+            list.to_text(&bar),
+        )
+            .into_response(),
+        "csv" => (
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"shopping-list.csv\"",
+                ),
+            ],
+            list.to_csv(),
+        )
+            .into_response(),
+        _ => Json(list).into_response(),
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/ingredients/{bar_ingredient_id}/purchases",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("bar_ingredient_id" = Uuid, Path, description = "ID of the bar ingredient bought")
+    ),
+    request_body(content = CreatePurchaseSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = PurchaseModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The price or quantity is out of range, or the unit measures something else than the bar ingredient")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, bar_ingredient_id = %bar_ingredient_id))]
+pub(crate) async fn create_purchase_handler(
+    header: HeaderMap,
+    Path((bar_id, bar_ingredient_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreatePurchaseSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bad_request = |message: &str| Error::new(StatusCode::BAD_REQUEST, message);
+    if body.price < 0.0 {
+        return Err(bad_request("price must not be negative"));
+    }
+    if body.quantity <= 0.0 {
+        return Err(bad_request("quantity must be positive"));
+    }
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock = data
+        .repo
+        .retrieve_bar_ingredient(&bar, bar_ingredient_id)
+        .await?;
+    let unit = data
+        .repo
+        .list_units()
+        .await?
+        .into_iter()
+        .find(|u| u.id == body.unit_id)
+        .ok_or(Error::new(StatusCode::NOT_FOUND, "unit not found"))?;
+    if unit.dimension != stock.unit.dimension {
+        return Err(bad_request(&format!(
+            "unit must be a unit of {}",
+            stock.unit.dimension
+        )));
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.create_purchase(&stock, body).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/ingredients/{bar_ingredient_id}/purchases",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("bar_ingredient_id" = Uuid, Path, description = "ID of the bar ingredient to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = Vec<PurchaseModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, bar_ingredient_id = %bar_ingredient_id))]
+pub(crate) async fn list_purchases_handler(
+    header: HeaderMap,
+    Path((bar_id, bar_ingredient_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock = data
+        .repo
+        .retrieve_bar_ingredient(&bar, bar_ingredient_id)
+        .await?;
+
+    Ok(Json(data.repo.list_purchases(&stock).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/ingredients/{bar_ingredient_id}/cost",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("bar_ingredient_id" = Uuid, Path, description = "ID of the bar ingredient to cost")
+    ),
+    responses(
+        (status = OK, description = "Success", body = IngredientCostModel, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, bar_ingredient_id = %bar_ingredient_id))]
+pub(crate) async fn get_bar_ingredient_cost_handler(
+    header: HeaderMap,
+    Path((bar_id, bar_ingredient_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock = data
+        .repo
+        .retrieve_bar_ingredient(&bar, bar_ingredient_id)
+        .await?;
+    let purchases = data.repo.list_purchases(&stock).await?;
+
+    Ok(Json(calculator::ingredient_cost(&stock, &purchases)))
+}
+
+const DEFAULT_POUR_COST: f64 = 20.0;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub(crate) struct RecipeCostQuery {
+    pour_cost: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/recipes/{recipe_id}/cost",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar whose purchases to cost by"),
+        ("recipe_id" = Uuid, Path, description = "ID of the recipe to cost"),
+        RecipeCostQuery
+    ),
+    responses(
+        (status = OK, description = "Success", body = RecipeCostModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The pour cost is not between 0 and 100")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, recipe_id = %recipe_id))]
+pub(crate) async fn get_recipe_cost_handler(
+    header: HeaderMap,
+    Path((bar_id, recipe_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<RecipeCostQuery>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let pour_cost = query.pour_cost.unwrap_or(DEFAULT_POUR_COST);
+    if !pour_cost.is_finite() || pour_cost <= 0.0 || pour_cost > 100.0 {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            "pourCost must be above 0 and at most 100",
+        ));
+    }
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let recipe = data.repo.retrieve_recipe(owner_id, recipe_id).await?;
+    let stock = data.repo.bar_ingredients(&bar).await?;
+    let lines = calculator::substitute(
+        data.repo.recipe_ingredients(&recipe).await?,
+        &stock,
+        &data.repo.list_categories(owner_id).await?,
+    );
+    let purchases = data.repo.bar_purchases(&bar).await?;
+
+    Ok(Json(calculator::recipe_cost(
+        &recipe,
+        &bar,
+        &lines,
+        &stock,
+        &purchases,
+        pour_cost / 100.0,
+    )))
 }
@@ -0,0 +1,246 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    error::Error,
+    model::{BottleModel, CreateBottleSchema, UpdateBottleSchema},
+    AppState, Auth,
+};
+
+const DEFAULT_EXPIRING_DAYS: i32 = 7;
+
+const MAX_EXPIRING_DAYS: i32 = 3650;
+
+const MAX_LOCATION_LENGTH: usize = 64;
+
+fn check_bottle(fill: Option<f32>, location: Option<&str>) -> crate::Result<()> {
+    if fill.is_some_and(|f| !(0.0..=1.0).contains(&f)) {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            "fill must be between 0 and 1",
+        ));
+    }
+    if location.is_some_and(|l| l.chars().count() > MAX_LOCATION_LENGTH) {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            format!("location must be at most {MAX_LOCATION_LENGTH} characters"),
+        ));
+    }
+
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/bottles",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar")
+    ),
+    request_body(content = CreateBottleSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = BottleModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The size or fill is out of range, or there is no size to default to")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn create_bottle_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(mut body): Json<CreateBottleSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    if body.size.is_some_and(|s| s <= 0.0) {
+        return Err(Error::new(StatusCode::BAD_REQUEST, "size must be positive"));
+    }
+    check_bottle(body.fill, body.location.as_deref())?;
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock = data
+        .repo
+        .retrieve_bar_ingredient(&bar, body.bar_ingredient_id)
+        .await?;
+    body.size = body.size.or(stock.bottle_size);
+    if body.size.is_none() {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            "a bottle needs a size, or the bottle size of the bar ingredient",
+        ));
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.create_bottle(&bar, body).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/bottles",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = Vec<BottleModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn list_bottles_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(data.repo.list_bottles(&bar).await?))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub(crate) struct ExpiringBottlesQuery {
+    days: Option<i32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/bottles/expiring",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to retrieve"),
+        ExpiringBottlesQuery
+    ),
+    responses(
+        (status = OK, description = "Success", body = Vec<BottleModel>, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The days are negative or too many")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn list_expiring_bottles_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    Query(query): Query<ExpiringBottlesQuery>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let days = query.days.unwrap_or(DEFAULT_EXPIRING_DAYS);
+    if !(0..=MAX_EXPIRING_DAYS).contains(&days) {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            format!("days must be between 0 and {MAX_EXPIRING_DAYS}"),
+        ));
+    }
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(data.repo.expiring_bottles(&bar, days).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/bottles/{bottle_id}",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("bottle_id" = Uuid, Path, description = "ID of the bottle to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = BottleModel, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, bottle_id = %bottle_id))]
+pub(crate) async fn get_bottle_handler(
+    header: HeaderMap,
+    Path((bar_id, bottle_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(data.repo.retrieve_bottle(&bar, bottle_id).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/bars/{bar_id}/bottles/{bottle_id}",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("bottle_id" = Uuid, Path, description = "ID of the bottle to update")
+    ),
+    request_body(content = UpdateBottleSchema, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Success", body = BottleModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The fill is out of range")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, bottle_id = %bottle_id))]
+pub(crate) async fn update_bottle_handler(
+    header: HeaderMap,
+    Path((bar_id, bottle_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<UpdateBottleSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    check_bottle(Some(body.fill), body.location.as_deref())?;
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let bottle = data.repo.retrieve_bottle(&bar, bottle_id).await?;
+
+    Ok(Json(data.repo.update_bottle(&bar, &bottle, body).await?))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/bars/{bar_id}/bottles/{bottle_id}",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("bottle_id" = Uuid, Path, description = "ID of the bottle to delete")
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Success")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, bottle_id = %bottle_id))]
+pub(crate) async fn delete_bottle_handler(
+    header: HeaderMap,
+    Path((bar_id, bottle_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let bottle = data.repo.retrieve_bottle(&bar, bottle_id).await?;
+    data.repo.delete_bottle(&bottle).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
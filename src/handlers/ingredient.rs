@@ -1,60 +1,185 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Json,
 };
+use serde::Deserialize;
 use std::sync::Arc;
+use utoipa::IntoParams;
 use uuid::Uuid;
 
 use crate::{
-    model::{CreateIngredientSchema, IngredientModel, SubIngredientModel},
+    calculator,
+    error::Error,
+    model::{
+        CategoryModel, CreateCategorySchema, CreateIngredientSchema, CreateShelfLifeSchema,
+        CreateSubIngredientSchema, IngredientAttributesSchema, IngredientModel,
+        IngredientShelfLifeSchema, ShelfLifeModel, SubIngredientModel,
+    },
     AppState, Auth,
 };
 
+const MAX_ATTRIBUTE_LENGTH: usize = 64;
+
+const MAX_LABELS: usize = 20;
+
+const MAX_LABEL_LENGTH: usize = 32;
+
+fn check_owned(ingredient: &IngredientModel) -> crate::Result<()> {
+    if ingredient.owner.is_none() {
+        return Err(Error::new(
+            StatusCode::FORBIDDEN,
+            format!(
+                "{} is in the ingredient catalogue and cannot be changed, fork it first",
+                ingredient.name
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+fn normalize_attribute(field: &str, value: Option<String>) -> crate::Result<Option<String>> {
+    let Some(value) = value.map(|v| v.trim().to_string()) else {
+        return Ok(None);
+    };
+    if value.chars().count() > MAX_ATTRIBUTE_LENGTH {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            format!("{field} must be at most {MAX_ATTRIBUTE_LENGTH} characters"),
+        ));
+    }
+
+    Ok((!value.is_empty()).then_some(value))
+}
+
+fn normalize_labels(field: &str, labels: Vec<String>) -> crate::Result<Vec<String>> {
+    let mut labels: Vec<String> = labels
+        .into_iter()
+        .map(|l| l.trim().to_lowercase())
+        .filter(|l| !l.is_empty())
+        .collect();
+    labels.sort();
+    labels.dedup();
+    if labels.len() > MAX_LABELS {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            format!("an ingredient can have at most {MAX_LABELS} {field}"),
+        ));
+    }
+    if labels.iter().any(|l| l.chars().count() > MAX_LABEL_LENGTH) {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            format!("{field} must be at most {MAX_LABEL_LENGTH} characters each"),
+        ));
+    }
+
+    Ok(labels)
+}
+
 #[utoipa::path(
     post,
     path = "/ingredients",
     request_body(content = CreateIngredientSchema, content_type = "application/json"),
     responses(
-        (status = CREATED, description = "Success", body = IngredientModel, content_type = "application/json")
+        (status = CREATED, description = "Success", body = IngredientModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "An attribute, tag or flavour note is too long, or there are too many"),
+        (status = NOT_FOUND, description = "The shelf life or category does not exist")
     ),
     security(
         ("http" = [])
     ),
     tag = crate::INGREDIENT_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn create_ingredient_handler(
     header: HeaderMap,
     State(data): State<Arc<AppState>>,
-    Json(body): Json<CreateIngredientSchema>,
+    Json(mut body): Json<CreateIngredientSchema>,
 ) -> crate::Result<impl IntoResponse> {
     let owner = Auth::decode_header(&data.signing_key, header)?;
+    body.origin = normalize_attribute("origin", body.origin)?;
+    body.producer = normalize_attribute("producer", body.producer)?;
+    body.tags = normalize_labels("tags", body.tags)?;
+    body.flavours = normalize_labels("flavours", body.flavours)?;
+    if let Some(shelf_life_id) = body.shelf_life_id {
+        data.repo.retrieve_shelf_life(owner, shelf_life_id).await?;
+    }
+    if let Some(category_id) = body.category_id {
+        data.repo.retrieve_category(owner, category_id).await?;
+    }
 
     Ok((
         StatusCode::CREATED,
-        Json(IngredientModel::create(&data.db, owner, body).await?),
+        Json(data.repo.create_ingredient(owner, body).await?),
     ))
 }
 
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub(crate) struct IngredientsQuery {
+    catalogue: Option<bool>,
+    category: Option<Uuid>,
+    tag: Option<String>,
+    flavour: Option<String>,
+    origin: Option<String>,
+    producer: Option<String>,
+    min_abv: Option<f32>,
+    max_abv: Option<f32>,
+}
+
 #[utoipa::path(
     get,
     path = "/ingredients",
+    params(IngredientsQuery),
     responses(
-        (status = OK, description = "Success", body = Vec<IngredientModel>, content_type = "application/json")
+        (status = OK, description = "Success", body = Vec<IngredientModel>, content_type = "application/json"),
+        (status = NOT_FOUND, description = "The category does not exist")
     ),
     security(
         ("http" = [])
     ),
     tag = crate::INGREDIENT_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn list_ingredients_handler(
     header: HeaderMap,
+    Query(query): Query<IngredientsQuery>,
     State(data): State<Arc<AppState>>,
 ) -> crate::Result<impl IntoResponse> {
     let owner = Auth::decode_header(&data.signing_key, header)?;
+    let mut ingredients = data.repo.list_ingredients(owner).await?;
+    if let Some(catalogue) = query.catalogue {
+        ingredients.retain(|i| i.owner.is_none() == catalogue);
+    }
+    if let Some(category_id) = query.category {
+        data.repo.retrieve_category(owner, category_id).await?;
+        let within = CategoryModel::within(&data.repo.list_categories(owner).await?, category_id);
+        ingredients.retain(|i| i.category_id.is_some_and(|c| within.contains(&c)));
+    }
+    let matches = |value: &str, filter: &str| value.eq_ignore_ascii_case(filter.trim());
+    if let Some(tag) = &query.tag {
+        ingredients.retain(|i| i.tags.iter().any(|t| matches(t, tag)));
+    }
+    if let Some(flavour) = &query.flavour {
+        ingredients.retain(|i| i.flavours.iter().any(|f| matches(f, flavour)));
+    }
+    if let Some(origin) = &query.origin {
+        ingredients.retain(|i| i.origin.as_deref().is_some_and(|o| matches(o, origin)));
+    }
+    if let Some(producer) = &query.producer {
+        ingredients.retain(|i| i.producer.as_deref().is_some_and(|p| matches(p, producer)));
+    }
+    if let Some(min_abv) = query.min_abv {
+        ingredients.retain(|i| i.abv.is_some_and(|abv| abv >= min_abv));
+    }
+    if let Some(max_abv) = query.max_abv {
+        ingredients.retain(|i| i.abv.is_some_and(|abv| abv <= max_abv));
+    }
 
-    Ok(Json(IngredientModel::all(&data.db, owner).await?))
+    Ok(Json(ingredients))
 }
 
 #[utoipa::path(
@@ -71,6 +196,7 @@ pub(crate) async fn list_ingredients_handler(
     ),
     tag = crate::INGREDIENT_TAG
 )]
+#[tracing::instrument(skip_all, fields(ingredient_id = %ingredient_id))]
 pub(crate) async fn get_ingredient_handler(
     header: HeaderMap,
     Path(ingredient_id): Path<Uuid>,
@@ -79,7 +205,52 @@ pub(crate) async fn get_ingredient_handler(
     let owner = Auth::decode_header(&data.signing_key, header)?;
 
     Ok(Json(
-        IngredientModel::retrieve(&data.db, owner, ingredient_id).await?,
+        data.repo.retrieve_ingredient(owner, ingredient_id).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/ingredients/{ingredient_id}/ingredients",
+    params(
+        ("ingredient_id" = Uuid, Path, description = "ID of the compound ingredient to add to")
+    ),
+    request_body(content = CreateSubIngredientSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = SubIngredientModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The ingredient is, or is made up of, the compound ingredient"),
+        (status = FORBIDDEN, description = "The compound ingredient is in the catalogue")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all, fields(ingredient_id = %ingredient_id))]
+pub(crate) async fn add_ingredient_ingredient_handler(
+    header: HeaderMap,
+    Path(ingredient_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateSubIngredientSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    let ingredient = data.repo.retrieve_ingredient(owner, ingredient_id).await?;
+    check_owned(&ingredient)?;
+    data.repo
+        .retrieve_ingredient(owner, body.ingredient_id)
+        .await?;
+    let parts = data.repo.ingredient_parts(owner).await?;
+    if calculator::contains(&parts, body.ingredient_id, ingredient_id) {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            "an ingredient cannot be a part of itself",
+        ));
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.add_sub_ingredient(&ingredient, body).await?),
     ))
 }
 
@@ -97,13 +268,241 @@ pub(crate) async fn get_ingredient_handler(
     ),
     tag = crate::INGREDIENT_TAG
 )]
+#[tracing::instrument(skip_all, fields(ingredient_id = %ingredient_id))]
 pub(crate) async fn get_ingredient_ingredients_handler(
     header: HeaderMap,
     Path(ingredient_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
 ) -> crate::Result<impl IntoResponse> {
     let owner = Auth::decode_header(&data.signing_key, header)?;
-    let ingredient = IngredientModel::retrieve(&data.db, owner, ingredient_id).await?;
+    let ingredient = data.repo.retrieve_ingredient(owner, ingredient_id).await?;
+
+    Ok(Json(data.repo.sub_ingredients(ingredient).await?))
+}
+
+#[utoipa::path(
+    put,
+    path = "/ingredients/{ingredient_id}/shelf-life",
+    params(
+        ("ingredient_id" = Uuid, Path, description = "ID of the ingredient to update")
+    ),
+    request_body(content = IngredientShelfLifeSchema, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Success", body = IngredientModel, content_type = "application/json"),
+        (status = FORBIDDEN, description = "The ingredient is in the catalogue")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all, fields(ingredient_id = %ingredient_id))]
+pub(crate) async fn set_ingredient_shelf_life_handler(
+    header: HeaderMap,
+    Path(ingredient_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<IngredientShelfLifeSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    let ingredient = data.repo.retrieve_ingredient(owner, ingredient_id).await?;
+    check_owned(&ingredient)?;
+    if let Some(shelf_life_id) = body.shelf_life_id {
+        data.repo.retrieve_shelf_life(owner, shelf_life_id).await?;
+    }
+
+    Ok(Json(
+        data.repo
+            .set_ingredient_shelf_life(owner, &ingredient, body.shelf_life_id)
+            .await?,
+    ))
+}
+
+#[utoipa::path(
+    put,
+    path = "/ingredients/{ingredient_id}/attributes",
+    params(
+        ("ingredient_id" = Uuid, Path, description = "ID of the ingredient to update")
+    ),
+    request_body(content = IngredientAttributesSchema, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Success", body = IngredientModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "An attribute, tag or flavour note is too long, or there are too many"),
+        (status = FORBIDDEN, description = "The ingredient is in the catalogue"),
+        (status = NOT_FOUND, description = "The ingredient or category does not exist")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all, fields(ingredient_id = %ingredient_id))]
+pub(crate) async fn set_ingredient_attributes_handler(
+    header: HeaderMap,
+    Path(ingredient_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(mut body): Json<IngredientAttributesSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    body.origin = normalize_attribute("origin", body.origin)?;
+    body.producer = normalize_attribute("producer", body.producer)?;
+    body.tags = normalize_labels("tags", body.tags)?;
+    body.flavours = normalize_labels("flavours", body.flavours)?;
+
+    let ingredient = data.repo.retrieve_ingredient(owner, ingredient_id).await?;
+    check_owned(&ingredient)?;
+    if let Some(category_id) = body.category_id {
+        data.repo.retrieve_category(owner, category_id).await?;
+    }
+
+    Ok(Json(
+        data.repo
+            .set_ingredient_attributes(owner, &ingredient, body)
+            .await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/ingredients/{ingredient_id}/fork",
+    params(
+        ("ingredient_id" = Uuid, Path, description = "ID of the ingredient to fork")
+    ),
+    responses(
+        (status = CREATED, description = "Success", body = IngredientModel, content_type = "application/json"),
+        (status = CONFLICT, description = "The user already has an ingredient with the name")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all, fields(ingredient_id = %ingredient_id))]
+pub(crate) async fn fork_ingredient_handler(
+    header: HeaderMap,
+    Path(ingredient_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    let ingredient = data.repo.retrieve_ingredient(owner, ingredient_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.fork_ingredient(owner, &ingredient).await?),
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/shelf-lives",
+    request_body(content = CreateShelfLifeSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = ShelfLifeModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The days are not positive"),
+        (status = CONFLICT, description = "There is already a shelf life of the user or a shared one with the name")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn create_shelf_life_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateShelfLifeSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    if body.days <= 0 {
+        return Err(Error::new(StatusCode::BAD_REQUEST, "days must be positive"));
+    }
+    // Shared shelf lives never change, so checking them up front cannot race.
+    let name = body.name.to_lowercase();
+    let shelf_lives = data.repo.list_shelf_lives(owner).await?;
+    if shelf_lives
+        .iter()
+        .any(|s| s.owner.is_none() && s.name == name)
+    {
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "there is already a shared shelf life with the name",
+        ));
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.create_shelf_life(owner, body).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/shelf-lives",
+    responses(
+        (status = OK, description = "Success", body = Vec<ShelfLifeModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn list_shelf_lives_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    Ok(Json(data.repo.list_shelf_lives(owner).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/categories",
+    request_body(content = CreateCategorySchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = CategoryModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The name is too short"),
+        (status = NOT_FOUND, description = "The parent category does not exist")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn create_category_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateCategorySchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    if let Some(parent_id) = body.parent_id {
+        data.repo.retrieve_category(owner, parent_id).await?;
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.create_category(owner, body).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/categories",
+    responses(
+        (status = OK, description = "Success", body = Vec<CategoryModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn list_categories_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
 
-    Ok(Json(ingredient.ingredients(&data.db).await?))
+    Ok(Json(data.repo.list_categories(owner).await?))
 }
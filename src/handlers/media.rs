@@ -1,23 +1,29 @@
-use aws_sdk_s3::primitives::ByteStream;
 use axum::{
-    body::Body,
-    extract::{Multipart, Path, State},
+    extract::{Multipart, Path, Query, State},
     http::{header, HeaderMap, HeaderValue, StatusCode},
     response::IntoResponse,
     Json,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use std::sync::Arc;
-use tokio_util::io::ReaderStream;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 use crate::{
     error::Error,
-    model::{MediaModel, UpdateMediaSchema},
-    AppState, Auth, MEDIA_BUCKET,
+    model::{CreateMediaSchema, MediaLibraryModel, MediaModel},
+    monitoring::{UPLOADS, UPLOAD_BYTES},
+    placeholder::Placeholder,
+    repository::{RemoveBlob, StoreBlob},
+    storage::Spool,
+    AppState, Auth,
 };
 
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
 #[derive(Deserialize, ToSchema)]
 #[allow(unused)]
 struct MediaForm {
@@ -30,21 +36,30 @@ struct MediaForm {
     path = "/media",
     request_body(content = MediaForm, content_type = "multipart/form-data"),
     responses(
-        (status = CREATED, description = "Success", body = MediaModel, content_type = "application/json")
+        (status = CREATED, description = "Success", body = MediaModel, content_type = "application/json"),
+        (status = PAYLOAD_TOO_LARGE, description = "Upload exceeds the maximum upload size"),
+        (status = TOO_MANY_REQUESTS, description = "Too many uploads within the last hour"),
+        (status = INSUFFICIENT_STORAGE, description = "Upload exceeds the storage quota")
     ),
     security(
         ("http" = [])
     ),
     tag = crate::MEDIA_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn create_media_handler(
     header: HeaderMap,
     State(data): State<Arc<AppState>>,
     mut multipart: Multipart,
 ) -> crate::Result<impl IntoResponse> {
     let owner = Auth::decode_header(&data.signing_key, header)?;
+    let limits = &data.limits;
+    // Checked again once the upload is in, as others may have been made
+    // meanwhile.
+    let usage = data.repo.usage(owner, limits.storage_quota).await?;
+    usage.check_upload(0, limits)?;
 
-    while let Some(field) = multipart
+    while let Some(mut field) = multipart
         .next_field()
         .await
         .map_err(|_| Error::new(StatusCode::BAD_REQUEST, "invalid multipart"))?
@@ -54,32 +69,75 @@ pub(crate) async fn create_media_handler(
                 .content_type()
                 .unwrap_or("application/octet-stream")
                 .to_string();
-            let bytes = field.bytes().await?;
-            let stream = ByteStream::from(bytes);
-
-            let media = MediaModel::create(&data.db, owner).await?;
-            let key = media.id.to_string();
-
-            let obj = data
-                .s3
-                .put_object()
-                .bucket(MEDIA_BUCKET)
-                .key(&key)
-                .body(stream)
-                .content_type(&content_type)
-                .send()
-                .await?;
 
-            let media = media
-                .update(
-                    &data.db,
+            // The upload is hashed as it streams in and spooled to disk, as
+            // its hash decides where it is stored.
+            let mut hasher = Sha256::new();
+            let mut spool = Spool::create().await?;
+            let mut size = 0;
+            while let Some(chunk) = field.chunk().await? {
+                size += chunk.len() as i64;
+
+                // Bail out as soon as the upload is known to be too big
+                // rather than spooling the rest of it.
+                if size > limits.max_upload_size {
+                    return Err(Error::with_details(
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        "upload exceeds maximum size",
+                        json!({ "limit": limits.max_upload_size }),
+                    ));
+                }
+                usage.check_upload(size, limits)?;
+
+                hasher.update(&chunk);
+                spool.write(&chunk).await?;
+            }
+            let hash = format!("{:x}", hasher.finalize());
+            let path = spool.finish().await?;
+
+            // Decoding and resampling the image is CPU bound, so keep it off
+            // the async runtime.
+            let placeholder = if content_type.starts_with("image/") {
+                let path = path.to_path_buf();
+                tokio::task::spawn_blocking(move || Placeholder::from_path(&path))
+                    .await
+                    .map_err(|e| Error::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            } else {
+                None
+            };
+
+            // Only bytes the bucket has not seen before are written to it;
+            // identical uploads share the existing object.
+            let mut stored = false;
+            let storage = &data.storage;
+            let object_type = content_type.clone();
+            let store: StoreBlob = Box::new(|key| {
+                stored = true;
+                Box::pin(async move { storage.put(&key, path, &object_type).await })
+            });
+
+            let media = data
+                .repo
+                .create_media(
                     owner,
-                    UpdateMediaSchema {
-                        size: obj.size(),
-                        content_type: Some(content_type),
+                    &hash,
+                    CreateMediaSchema {
+                        size,
+                        content_type,
+                        width: placeholder.as_ref().map(|p| p.width),
+                        height: placeholder.as_ref().map(|p| p.height),
+                        blurhash: placeholder.as_ref().map(|p| p.blurhash.clone()),
+                        dominant_color: placeholder.map(|p| p.dominant_color),
                     },
+                    limits,
+                    store,
                 )
                 .await?;
+            let deduplicated = !stored;
+
+            metrics::counter!(UPLOADS, "deduplicated" => deduplicated.to_string()).increment(1);
+            metrics::counter!(UPLOAD_BYTES).increment(size as u64);
+
             return Ok((StatusCode::CREATED, Json(media)));
         }
     }
@@ -104,26 +162,17 @@ pub(crate) async fn create_media_handler(
     ),
     tag = crate::MEDIA_TAG
 )]
+#[tracing::instrument(skip_all, fields(media_id = %media_id))]
 pub(crate) async fn get_media_handler(
     header: HeaderMap,
     Path(media_id): Path<Uuid>,
     State(data): State<Arc<AppState>>,
 ) -> crate::Result<impl IntoResponse> {
     let owner = Auth::decode_header(&data.signing_key, header)?;
-    let media = MediaModel::retrieve(&data.db, owner, media_id).await?;
-
-    let reader = data
-        .s3
-        .get_object()
-        .bucket(MEDIA_BUCKET)
-        .key(media.id)
-        .send()
-        .await?
-        .body
-        .into_async_read();
+    let media = data.repo.retrieve_media(owner, media_id).await?;
+    let blob = data.repo.retrieve_blob(media.blob_id).await?;
 
-    let stream = ReaderStream::new(reader);
-    let body = Body::from_stream(stream);
+    let body = data.storage.get(&blob.key).await?;
 
     let mut headers = HeaderMap::new();
     headers.insert(
@@ -133,3 +182,185 @@ pub(crate) async fn get_media_handler(
 
     Ok((headers, body))
 }
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub(crate) struct ListMediaQuery {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    content_type: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/media",
+    params(ListMediaQuery),
+    responses(
+        (status = OK, description = "Success", body = Vec<MediaLibraryModel>, content_type = "application/json",
+            headers(("x-total-count" = i64, description = "Number of media matching the filter")))
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::MEDIA_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn list_media_handler(
+    header: HeaderMap,
+    Query(query): Query<ListMediaQuery>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0).max(0);
+    // Only a trailing `*` is a wildcard, so the LIKE wildcards and escape
+    // character are escaped everywhere else.
+    let content_type = query.content_type.map(|t| {
+        let (exact, wildcard) = match t.strip_suffix('*') {
+            Some(prefix) => (prefix, "%"),
+            None => (t.as_str(), ""),
+        };
+        let escaped = exact
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        format!("{escaped}{wildcard}")
+    });
+
+    let total = data
+        .repo
+        .count_media(owner, content_type.as_deref())
+        .await?;
+    let media = data
+        .repo
+        .media_library(owner, content_type.as_deref(), limit, offset)
+        .await?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert("x-total-count", HeaderValue::from(total));
+
+    Ok((headers, Json(media)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/media/{media_id}",
+    params(
+        ("media_id" = Uuid, Path, description = "ID of the media to delete")
+    ),
+    responses(
+        (status = NO_CONTENT, description = "Success")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::MEDIA_TAG
+)]
+#[tracing::instrument(skip_all, fields(media_id = %media_id))]
+pub(crate) async fn delete_media_handler(
+    header: HeaderMap,
+    Path(media_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    let orphaned = data.repo.delete_media(owner, media_id).await?;
+    remove_objects(&data, orphaned).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DeletedMedia {
+    deleted: Vec<Uuid>,
+}
+
+#[utoipa::path(
+    delete,
+    path = "/media/unused",
+    responses(
+        (status = OK, description = "Success", body = DeletedMedia, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::MEDIA_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn delete_unused_media_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    let deleted = data.repo.delete_unused_media(owner).await?;
+    remove_objects(&data, deleted.orphaned).await;
+
+    Ok(Json(DeletedMedia {
+        deleted: deleted.media.into_iter().map(|m| m.id).collect(),
+    }))
+}
+
+const MAX_DELETE_MEDIA: usize = 200;
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DeleteMediaSchema {
+    ids: Vec<Uuid>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/media/delete",
+    request_body(content = DeleteMediaSchema, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Success", body = DeletedMedia, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "Too many media to delete at once"),
+        (status = NOT_FOUND, description = "One of the media is not the caller's")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::MEDIA_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn delete_media_list_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+    Json(mut body): Json<DeleteMediaSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    body.ids.sort();
+    body.ids.dedup();
+    if body.ids.len() > MAX_DELETE_MEDIA {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            format!("at most {MAX_DELETE_MEDIA} media can be deleted at once"),
+        ));
+    }
+
+    let deleted = data.repo.delete_media_list(owner, &body.ids).await?;
+    remove_objects(&data, deleted.orphaned).await;
+
+    Ok(Json(DeletedMedia {
+        deleted: deleted.media.into_iter().map(|m| m.id).collect(),
+    }))
+}
+
+/// Runs after the media are deleted so a failed commit never loses data. A blob
+/// that cannot be removed stays unreferenced until the same bytes are uploaded.
+async fn remove_objects(data: &AppState, blobs: Vec<Uuid>) {
+    for blob in blobs {
+        let storage = &data.storage;
+        let remove: RemoveBlob =
+            Box::new(|key| Box::pin(async move { storage.delete(&key).await }));
+
+        if let Err(e) = data.repo.remove_blob(blob, remove).await {
+            tracing::warn!(blob_id = %blob, error = %e, "failed to remove orphaned blob");
+        }
+    }
+}
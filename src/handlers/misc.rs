@@ -1,14 +1,42 @@
-use axum::{extract::State, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use metrics::gauge;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use sha2::{Digest, Sha256};
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use utoipa::ToSchema;
 
-use crate::{model::UnitModel, AppState};
+use crate::{error::Error, model::UnitModel, monitoring::*, AppState};
+
+const SERVICE: &str = "tapster-api";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+const COMMIT: &str = env!("TAPSTER_GIT_COMMIT");
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct HealthCheck {
     status: String,
     service: String,
+    version: String,
+    commit: String,
+}
+
+impl HealthCheck {
+    fn ok() -> Self {
+        Self {
+            status: "OK".to_string(),
+            service: SERVICE.to_string(),
+            version: VERSION.to_string(),
+            commit: COMMIT.to_string(),
+        }
+    }
 }
 
 #[utoipa::path(
@@ -19,13 +47,155 @@ pub(crate) struct HealthCheck {
     ),
     tag = crate::MISC_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn healthcheck_handler() -> impl IntoResponse {
-    let healthcheck = HealthCheck {
-        status: "OK".to_string(),
-        service: "tapster-api".to_string(),
+    Json(HealthCheck::ok())
+}
+
+#[utoipa::path(
+    get,
+    path = "/livez",
+    responses(
+        (status = OK, description = "The process is up", body = HealthCheck, content_type = "application/json")
+    ),
+    tag = crate::MISC_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn livez_handler() -> impl IntoResponse {
+    Json(HealthCheck::ok())
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DependencyCheck {
+    status: String,
+    latency_ms: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl DependencyCheck {
+    fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct Dependencies {
+    database: DependencyCheck,
+    storage: DependencyCheck,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ReadinessCheck {
+    status: String,
+    service: String,
+    version: String,
+    commit: String,
+    dependencies: Dependencies,
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    responses(
+        (status = OK, description = "Every dependency is reachable", body = ReadinessCheck, content_type = "application/json"),
+        (status = SERVICE_UNAVAILABLE, description = "A dependency is unreachable or the server is shutting down", body = ReadinessCheck, content_type = "application/json")
+    ),
+    tag = crate::MISC_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn readyz_handler(State(data): State<Arc<AppState>>) -> impl IntoResponse {
+    let timeout = data.readiness_timeout;
+    let (database, storage) = tokio::join!(
+        check(timeout, data.repo.ping()),
+        check(timeout, data.storage.ping()),
+    );
+
+    let (status_code, status) = if data.readiness.is_draining() {
+        (StatusCode::SERVICE_UNAVAILABLE, "DRAINING")
+    } else if database.is_ok() && storage.is_ok() {
+        (StatusCode::OK, "OK")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "UNAVAILABLE")
     };
 
-    Json(healthcheck)
+    let readiness = ReadinessCheck {
+        status: status.to_string(),
+        service: SERVICE.to_string(),
+        version: VERSION.to_string(),
+        commit: COMMIT.to_string(),
+        dependencies: Dependencies { database, storage },
+    };
+
+    (status_code, Json(readiness))
+}
+
+async fn check<E: std::fmt::Display>(
+    timeout: Duration,
+    ping: impl Future<Output = Result<(), E>>,
+) -> DependencyCheck {
+    let start = Instant::now();
+    let error = match tokio::time::timeout(timeout, ping).await {
+        Ok(Ok(())) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some(format!("timed out after {}ms", timeout.as_millis())),
+    };
+
+    DependencyCheck {
+        status: if error.is_none() { "OK" } else { "DOWN" }.to_string(),
+        latency_ms: start.elapsed().as_millis(),
+        error,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = OK, description = "Prometheus metrics", body = str, content_type = "text/plain"),
+        (status = UNAUTHORIZED, description = "The metrics token is missing or wrong"),
+        (status = NOT_FOUND, description = "No metrics token is configured")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::MISC_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn metrics_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let Some(token) = &data.metrics_token else {
+        return Err(Error::new(StatusCode::NOT_FOUND, "metrics are not enabled"));
+    };
+    let sent = header
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    // Comparing digests rather than the tokens themselves takes the same time
+    // however much of the token a guess gets right.
+    if Sha256::digest(sent) != Sha256::digest(token) {
+        return Err(Error::new(
+            StatusCode::UNAUTHORIZED,
+            "invalid metrics token",
+        ));
+    }
+
+    // Pool gauges are cheap and sampled on every scrape, while the domain
+    // totals are counted again only once their interval has passed.
+    let pool = data.repo.pool_status();
+    let idle = pool.idle as f64;
+    gauge!(DB_POOL_CONNECTIONS, "state" => "idle").set(idle);
+    gauge!(DB_POOL_CONNECTIONS, "state" => "in_use").set(pool.size as f64 - idle);
+    gauge!(DB_POOL_MAX_CONNECTIONS).set(pool.max as f64);
+    data.stats.refresh(data.repo.as_ref()).await;
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        data.metrics.render(),
+    ))
 }
 
 #[utoipa::path(
@@ -36,8 +206,9 @@ pub(crate) async fn healthcheck_handler() -> impl IntoResponse {
     ),
     tag = crate::MISC_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn list_units_handler(
     State(data): State<Arc<AppState>>,
 ) -> crate::Result<impl IntoResponse> {
-    Ok(Json(UnitModel::all(&data.db).await?))
+    Ok(Json(data.repo.list_units().await?))
 }
@@ -1,11 +1,21 @@
 pub(crate) use bar::*;
+pub(crate) use bottle::*;
 pub(crate) use ingredient::*;
 pub(crate) use media::*;
 pub(crate) use misc::*;
+pub(crate) use product::*;
+pub(crate) use recipe::*;
+pub(crate) use serve::*;
+pub(crate) use stock_take::*;
 pub(crate) use user::*;
 
 mod bar;
+mod bottle;
 mod ingredient;
 mod media;
 mod misc;
+mod product;
+mod recipe;
+mod serve;
+mod stock_take;
 mod user;
@@ -0,0 +1,158 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    catalogue::normalize_barcode,
+    error::Error,
+    model::{CreateScanSchema, ProductModel, RestockSchema, ScanModel},
+    AppState, Auth,
+};
+
+const MAX_SCAN_BOTTLES: i32 = 100;
+
+/// How many times a scan is worked out again after losing a race with
+/// another scan.
+const MAX_SCAN_ATTEMPTS: usize = 3;
+
+#[utoipa::path(
+    get,
+    path = "/products/{barcode}",
+    params(
+        ("barcode" = String, Path, description = "Barcode of the product to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = ProductModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The barcode is invalid")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::INGREDIENT_TAG
+)]
+#[tracing::instrument(skip_all, fields(barcode = %barcode))]
+pub(crate) async fn get_product_handler(
+    header: HeaderMap,
+    Path(barcode): Path<String>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    Auth::decode_header(&data.signing_key, header)?;
+    let barcode = normalize_barcode(&barcode)
+        .ok_or(Error::new(StatusCode::BAD_REQUEST, "invalid barcode"))?;
+
+    Ok(Json(data.repo.retrieve_product(&barcode).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/scan",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar")
+    ),
+    request_body(content = CreateScanSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "The ingredient was stocked for the first time", body = ScanModel, content_type = "application/json"),
+        (status = OK, description = "The ingredient was restocked", body = ScanModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The barcode is invalid or the bottles are out of range"),
+        (status = NOT_FOUND, description = "No product has the barcode"),
+        (status = CONFLICT, description = "The bar stocks the ingredient in a unit of another dimension, or its stock kept changing during the scan")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn scan_product_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateScanSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let barcode = normalize_barcode(&body.barcode)
+        .ok_or(Error::new(StatusCode::BAD_REQUEST, "invalid barcode"))?;
+    let bottles = body.bottles.unwrap_or(1);
+    if !(1..=MAX_SCAN_BOTTLES).contains(&bottles) {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            format!("bottles must be between 1 and {MAX_SCAN_BOTTLES}"),
+        ));
+    }
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let product = data.repo.retrieve_product(&barcode).await?;
+
+    // A concurrent scan of the same product can create its ingredient or
+    // stock after the restock is worked out, in which case it is worked out
+    // again from what that scan made.
+    for _ in 0..MAX_SCAN_ATTEMPTS {
+        // The product is the user's oldest ingredient by its name, or else
+        // the catalogue's, if there is one, so that scanning never duplicates
+        // it.
+        let ingredient = data
+            .repo
+            .list_ingredients(owner_id)
+            .await?
+            .into_iter()
+            .filter(|i| i.name == product.ingredient)
+            .min_by_key(|i| (i.owner.is_none(), i.created_at));
+        let stock = match &ingredient {
+            Some(ingredient) => data
+                .repo
+                .bar_ingredients(&bar)
+                .await?
+                .into_iter()
+                .find(|s| s.ingredient.id == ingredient.id),
+            None => None,
+        };
+
+        let mut bottle_size = product.size;
+        if let Some(stock) = &stock {
+            if stock.unit.dimension != product.unit.dimension {
+                return Err(Error::new(
+                    StatusCode::CONFLICT,
+                    format!(
+                        "the bar stocks {} by {}, but the product is measured by {}",
+                        product.ingredient, stock.unit.dimension, product.unit.dimension
+                    ),
+                ));
+            }
+            bottle_size = (f64::from(product.size) * product.unit.base_quantity
+                / stock.unit.base_quantity) as f32;
+        }
+
+        let status = if stock.is_some() {
+            StatusCode::OK
+        } else {
+            StatusCode::CREATED
+        };
+        let scan = data
+            .repo
+            .scan_product(
+                &bar,
+                RestockSchema {
+                    product: product.clone(),
+                    ingredient_id: ingredient.map(|i| i.id),
+                    bar_ingredient_id: stock.map(|s| s.id),
+                    bottle_size,
+                    bottles,
+                },
+            )
+            .await?;
+
+        if let Some(scan) = scan {
+            return Ok((status, Json(scan)));
+        }
+    }
+
+    Err(Error::new(
+        StatusCode::CONFLICT,
+        "the bar's stock kept changing during the scan",
+    ))
+}
@@ -0,0 +1,336 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use utoipa::IntoParams;
+use uuid::Uuid;
+
+use crate::{
+    calculator::{self, RecipeAnalysisModel, ScaleTarget, ScaledRecipeModel, VOLUME},
+    error::Error,
+    model::{
+        CategoryModel, CreateRecipeIngredientSchema, CreateRecipeSchema, RecipeIngredientModel,
+        RecipeModel,
+    },
+    AppState, Auth,
+};
+
+#[utoipa::path(
+    post,
+    path = "/recipes",
+    request_body(content = CreateRecipeSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = RecipeModel, content_type = "application/json"),
+        (status = NOT_FOUND, description = "The thumbnail is not one of the caller's media")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::RECIPE_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn create_recipe_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateRecipeSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    if let Some(media_id) = body.thumbnail_id {
+        data.repo.retrieve_media(owner, media_id).await?;
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.create_recipe(owner, body).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/recipes",
+    responses(
+        (status = OK, description = "Success", body = Vec<RecipeModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::RECIPE_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn list_recipes_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    Ok(Json(data.repo.list_recipes(owner).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/recipes/{recipe_id}",
+    params(
+        ("recipe_id" = Uuid, Path, description = "ID of the recipe to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = RecipeModel, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::RECIPE_TAG
+)]
+#[tracing::instrument(skip_all, fields(recipe_id = %recipe_id))]
+pub(crate) async fn get_recipe_handler(
+    header: HeaderMap,
+    Path(recipe_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    Ok(Json(data.repo.retrieve_recipe(owner, recipe_id).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/recipes/{recipe_id}/ingredients",
+    params(
+        ("recipe_id" = Uuid, Path, description = "ID of the recipe to add to")
+    ),
+    request_body(content = CreateRecipeIngredientSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = RecipeIngredientModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The line has neither an ingredient nor a category, or the ingredient is not of the category")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::RECIPE_TAG
+)]
+#[tracing::instrument(skip_all, fields(recipe_id = %recipe_id))]
+pub(crate) async fn add_recipe_ingredient_handler(
+    header: HeaderMap,
+    Path(recipe_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(mut body): Json<CreateRecipeIngredientSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    let bad_request = |message: String| Error::new(StatusCode::BAD_REQUEST, message);
+    if body.quantity <= 0.0 {
+        return Err(bad_request("quantity must be positive".to_string()));
+    }
+
+    let recipe = data.repo.retrieve_recipe(owner, recipe_id).await?;
+    let ingredient = match body.ingredient_id {
+        Some(id) => Some(data.repo.retrieve_ingredient(owner, id).await?),
+        None => None,
+    };
+    if let Some(category_id) = body.category_id {
+        let category = data.repo.retrieve_category(owner, category_id).await?;
+        let within = CategoryModel::within(&data.repo.list_categories(owner).await?, category_id);
+        let of_category = |c: Option<Uuid>| c.is_some_and(|c| within.contains(&c));
+        match &ingredient {
+            Some(ingredient) if !of_category(ingredient.category_id) => {
+                return Err(bad_request(format!(
+                    "{} is not a {}",
+                    ingredient.name, category.name
+                )));
+            }
+            Some(_) => {}
+            None => {
+                let stand_in = data
+                    .repo
+                    .list_ingredients(owner)
+                    .await?
+                    .into_iter()
+                    .filter(|i| of_category(i.category_id))
+                    .min_by_key(|i| (i.owner.is_some(), i.created_at))
+                    .ok_or_else(|| bad_request(format!("there is no {} to use", category.name)))?;
+                body.ingredient_id = Some(stand_in.id);
+            }
+        }
+    } else if ingredient.is_none() {
+        return Err(bad_request(
+            "a line needs an ingredient or a category".to_string(),
+        ));
+    }
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.add_recipe_ingredient(&recipe, body).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/recipes/{recipe_id}/ingredients",
+    params(
+        ("recipe_id" = Uuid, Path, description = "ID of the recipe to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = Vec<RecipeIngredientModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::RECIPE_TAG
+)]
+#[tracing::instrument(skip_all, fields(recipe_id = %recipe_id))]
+pub(crate) async fn get_recipe_ingredients_handler(
+    header: HeaderMap,
+    Path(recipe_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    let recipe = data.repo.retrieve_recipe(owner, recipe_id).await?;
+
+    Ok(Json(data.repo.recipe_ingredients(&recipe).await?))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub(crate) struct ScaleRecipeQuery {
+    servings: Option<f64>,
+    volume: Option<f64>,
+    unit: Option<String>,
+    dilution: Option<f64>,
+    system: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/recipes/{recipe_id}/scale",
+    params(
+        ("recipe_id" = Uuid, Path, description = "ID of the recipe to scale"),
+        ScaleRecipeQuery
+    ),
+    responses(
+        (status = OK, description = "Success", body = ScaledRecipeModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "Neither or both of servings and volume were given, or a value is out of range")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::RECIPE_TAG
+)]
+#[tracing::instrument(skip_all, fields(recipe_id = %recipe_id))]
+pub(crate) async fn scale_recipe_handler(
+    header: HeaderMap,
+    Path(recipe_id): Path<Uuid>,
+    Query(query): Query<ScaleRecipeQuery>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    let recipe = data.repo.retrieve_recipe(owner, recipe_id).await?;
+    let lines = data.repo.recipe_ingredients(&recipe).await?;
+    let units = data.repo.list_units().await?;
+
+    let bad_request = |message: &str| Error::new(StatusCode::BAD_REQUEST, message);
+
+    if [query.servings, query.volume, query.dilution]
+        .into_iter()
+        .flatten()
+        .any(|value| !value.is_finite())
+    {
+        return Err(bad_request("servings, volume and dilution must be finite"));
+    }
+
+    let target = match (query.servings, query.volume) {
+        (Some(servings), None) if servings > 0.0 => ScaleTarget::Servings(servings),
+        (None, Some(volume)) if volume > 0.0 => {
+            let abbreviation = query.unit.as_deref().unwrap_or("ml");
+            let unit = units
+                .iter()
+                .find(|u| u.abbreviation == abbreviation && u.dimension == VOLUME)
+                .ok_or(bad_request("unit must be a unit of volume"))?;
+
+            ScaleTarget::Volume(volume * unit.base_quantity)
+        }
+        (Some(_), Some(_)) | (None, None) => {
+            return Err(bad_request("exactly one of servings or volume is required"));
+        }
+        _ => return Err(bad_request("servings and volume must be positive")),
+    };
+
+    let dilution = query.dilution.unwrap_or(0.0);
+    if !(0.0..=100.0).contains(&dilution) {
+        return Err(bad_request("dilution must be between 0 and 100"));
+    }
+
+    let system = query.system.as_deref();
+    if system.is_some_and(|s| s != "metric" && s != "imperial") {
+        return Err(bad_request("system must be metric or imperial"));
+    }
+
+    Ok(Json(calculator::scale(
+        &recipe,
+        &lines,
+        &units,
+        target,
+        dilution / 100.0,
+        system,
+    )?))
+}
+
+const DEFAULT_STANDARD_DRINK: f64 = 14.0;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+#[into_params(parameter_in = Query, rename_all = "camelCase")]
+pub(crate) struct AnalyseRecipeQuery {
+    standard_drink: Option<f64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/recipes/{recipe_id}/analysis",
+    params(
+        ("recipe_id" = Uuid, Path, description = "ID of the recipe to analyse"),
+        AnalyseRecipeQuery
+    ),
+    responses(
+        (status = OK, description = "Success", body = RecipeAnalysisModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The standard drink is not positive")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::RECIPE_TAG
+)]
+#[tracing::instrument(skip_all, fields(recipe_id = %recipe_id))]
+pub(crate) async fn analyse_recipe_handler(
+    header: HeaderMap,
+    Path(recipe_id): Path<Uuid>,
+    Query(query): Query<AnalyseRecipeQuery>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+    let standard_drink = query.standard_drink.unwrap_or(DEFAULT_STANDARD_DRINK);
+    if !standard_drink.is_finite() || standard_drink <= 0.0 {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            "standardDrink must be positive",
+        ));
+    }
+
+    let recipe = data.repo.retrieve_recipe(owner, recipe_id).await?;
+    let lines = data.repo.recipe_ingredients(&recipe).await?;
+    let units = data.repo.list_units().await?;
+    let compositions = calculator::compositions(
+        &data.repo.list_ingredients(owner).await?,
+        &data.repo.ingredient_parts(owner).await?,
+    );
+
+    Ok(Json(calculator::analyse(
+        &recipe,
+        &lines,
+        &units,
+        &compositions,
+        standard_drink,
+    )?))
+}
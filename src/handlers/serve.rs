@@ -0,0 +1,155 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    calculator,
+    error::Error,
+    model::{CreateServeSchema, ServeModel},
+    AppState, Auth,
+};
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/serves",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar the recipe was made at")
+    ),
+    request_body(content = CreateServeSchema, content_type = "application/json"),
+    responses(
+        (status = CREATED, description = "Success", body = ServeModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The servings are not positive")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn create_serve_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateServeSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    if body.servings <= 0.0 {
+        return Err(Error::new(
+            StatusCode::BAD_REQUEST,
+            "servings must be positive",
+        ));
+    }
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let recipe = data.repo.retrieve_recipe(owner_id, body.recipe_id).await?;
+    let stock = data.repo.bar_ingredients(&bar).await?;
+    let lines = calculator::substitute(
+        data.repo.recipe_ingredients(&recipe).await?,
+        &stock,
+        &data.repo.list_categories(owner_id).await?,
+    );
+    let deductions = calculator::deductions(
+        &lines,
+        body.servings as f64,
+        &stock,
+        &data.repo.ingredient_parts(owner_id).await?,
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.create_serve(&bar, body, deductions).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/serves",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = Vec<ServeModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn list_serves_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(data.repo.list_serves(&bar).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/serves/{serve_id}",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("serve_id" = Uuid, Path, description = "ID of the serve to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = ServeModel, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, serve_id = %serve_id))]
+pub(crate) async fn get_serve_handler(
+    header: HeaderMap,
+    Path((bar_id, serve_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(data.repo.retrieve_serve(&bar, serve_id).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/serves/{serve_id}/undo",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("serve_id" = Uuid, Path, description = "ID of the serve to undo")
+    ),
+    responses(
+        (status = OK, description = "Success", body = ServeModel, content_type = "application/json"),
+        (status = CONFLICT, description = "The serve has already been undone")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, serve_id = %serve_id))]
+pub(crate) async fn undo_serve_handler(
+    header: HeaderMap,
+    Path((bar_id, serve_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let serve = data.repo.retrieve_serve(&bar, serve_id).await?;
+    if serve.undone_at.is_some() {
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "serve has already been undone",
+        ));
+    }
+
+    Ok(Json(data.repo.undo_serve(&bar, &serve).await?))
+}
@@ -0,0 +1,332 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{
+    calculator::{self, VarianceReportModel},
+    error::Error,
+    model::{AppliedStockTakeModel, BarModel, CreateStockCountSchema, StockTakeModel},
+    AppState, Auth,
+};
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/stock-takes",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to count")
+    ),
+    responses(
+        (status = CREATED, description = "Success", body = StockTakeModel, content_type = "application/json"),
+        (status = CONFLICT, description = "The bar already has a stock take open")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn create_stock_take_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(data.repo.create_stock_take(&bar).await?),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/stock-takes",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = Vec<StockTakeModel>, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id))]
+pub(crate) async fn list_stock_takes_handler(
+    header: HeaderMap,
+    Path(bar_id): Path<Uuid>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(data.repo.list_stock_takes(&bar).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/stock-takes/{stock_take_id}",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("stock_take_id" = Uuid, Path, description = "ID of the stock take to retrieve")
+    ),
+    responses(
+        (status = OK, description = "Success", body = StockTakeModel, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, stock_take_id = %stock_take_id))]
+pub(crate) async fn get_stock_take_handler(
+    header: HeaderMap,
+    Path((bar_id, stock_take_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+
+    Ok(Json(
+        data.repo.retrieve_stock_take(&bar, stock_take_id).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/stock-takes/{stock_take_id}/counts",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("stock_take_id" = Uuid, Path, description = "ID of the stock take to count in")
+    ),
+    request_body(content = CreateStockCountSchema, content_type = "application/json"),
+    responses(
+        (status = OK, description = "Success", body = StockTakeModel, content_type = "application/json"),
+        (status = BAD_REQUEST, description = "The count is empty, incomplete or out of range"),
+        (status = CONFLICT, description = "The stock take is closed")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, stock_take_id = %stock_take_id))]
+pub(crate) async fn count_stock_handler(
+    header: HeaderMap,
+    Path((bar_id, stock_take_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+    Json(body): Json<CreateStockCountSchema>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bad_request = |message: &str| Error::new(StatusCode::BAD_REQUEST, message);
+    if body.bottles.is_none() && body.quantity.is_none() && body.weight.is_none() {
+        return Err(bad_request("a count needs bottles, a quantity or a weight"));
+    }
+    let values = [body.bottles, body.quantity, body.weight, body.tare_weight];
+    if values.into_iter().flatten().any(|v| v < 0.0) {
+        return Err(bad_request("counts must not be negative"));
+    }
+    if body.density.is_some_and(|d| d <= 0.0) {
+        return Err(bad_request("density must be positive"));
+    }
+    if let Some(weight) = body.weight {
+        match body.tare_weight {
+            None => return Err(bad_request("a weight needs a tareWeight")),
+            Some(tare_weight) if weight < tare_weight => {
+                return Err(bad_request("weight must not be below the tareWeight"));
+            }
+            Some(_) => {}
+        }
+    }
+
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock_take = data.repo.retrieve_stock_take(&bar, stock_take_id).await?;
+    let stock = data
+        .repo
+        .retrieve_bar_ingredient(&bar, body.bar_ingredient_id)
+        .await?;
+    if body.bottles.is_some() && stock.bottle_size.is_none() {
+        return Err(bad_request(
+            "counting bottles needs the bottle size of the bar ingredient",
+        ));
+    }
+    let unit = match body.unit_id {
+        Some(unit_id) => data
+            .repo
+            .list_units()
+            .await?
+            .into_iter()
+            .find(|u| u.id == unit_id)
+            .ok_or(Error::new(StatusCode::NOT_FOUND, "unit not found"))?,
+        None => stock.unit.clone(),
+    };
+    if unit.dimension != stock.unit.dimension {
+        return Err(bad_request(&format!(
+            "unit must be a unit of {}",
+            stock.unit.dimension
+        )));
+    }
+    if stock_take.closed_at.is_some() {
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "stock take has been closed",
+        ));
+    }
+
+    let quantity = calculator::counted_quantity(&stock, &body, &unit);
+
+    Ok(Json(
+        data.repo
+            .count_stock(&bar, &stock_take, stock.id, quantity as f32)
+            .await?,
+    ))
+}
+
+async fn report(
+    data: &AppState,
+    bar: &BarModel,
+    stock_take: &StockTakeModel,
+) -> crate::Result<VarianceReportModel> {
+    Ok(calculator::variance_report(
+        stock_take,
+        &data.repo.list_stock_takes(bar).await?,
+        &data.repo.bar_ingredients(bar).await?,
+        &data.repo.stock_take_usage(stock_take).await?,
+    ))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/stock-takes/{stock_take_id}/close",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("stock_take_id" = Uuid, Path, description = "ID of the stock take to close")
+    ),
+    responses(
+        (status = OK, description = "Success", body = VarianceReportModel, content_type = "application/json"),
+        (status = CONFLICT, description = "The stock take is already closed")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, stock_take_id = %stock_take_id))]
+pub(crate) async fn close_stock_take_handler(
+    header: HeaderMap,
+    Path((bar_id, stock_take_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock_take = data.repo.retrieve_stock_take(&bar, stock_take_id).await?;
+    if stock_take.closed_at.is_some() {
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "stock take has already been closed",
+        ));
+    }
+
+    let stock_take = data.repo.close_stock_take(&bar, &stock_take).await?;
+
+    Ok(Json(report(&data, &bar, &stock_take).await?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/bars/{bar_id}/stock-takes/{stock_take_id}/report",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("stock_take_id" = Uuid, Path, description = "ID of the stock take to report on")
+    ),
+    responses(
+        (status = OK, description = "Success", body = VarianceReportModel, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, stock_take_id = %stock_take_id))]
+pub(crate) async fn get_stock_take_report_handler(
+    header: HeaderMap,
+    Path((bar_id, stock_take_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock_take = data.repo.retrieve_stock_take(&bar, stock_take_id).await?;
+
+    Ok(Json(report(&data, &bar, &stock_take).await?))
+}
+
+#[utoipa::path(
+    post,
+    path = "/bars/{bar_id}/stock-takes/{stock_take_id}/apply",
+    params(
+        ("bar_id" = Uuid, Path, description = "ID of the bar"),
+        ("stock_take_id" = Uuid, Path, description = "ID of the stock take to apply")
+    ),
+    responses(
+        (status = OK, description = "Success", body = AppliedStockTakeModel, content_type = "application/json"),
+        (status = CONFLICT, description = "The stock take is still open, or it or a later one has already been applied")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::BAR_TAG
+)]
+#[tracing::instrument(skip_all, fields(bar_id = %bar_id, stock_take_id = %stock_take_id))]
+pub(crate) async fn apply_stock_take_handler(
+    header: HeaderMap,
+    Path((bar_id, stock_take_id)): Path<(Uuid, Uuid)>,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner_id = Auth::decode_header(&data.signing_key, header)?;
+    let bar = data.repo.retrieve_bar(owner_id, bar_id).await?;
+    let stock_take = data.repo.retrieve_stock_take(&bar, stock_take_id).await?;
+    if stock_take.closed_at.is_none() {
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "stock take must be closed before it is applied",
+        ));
+    }
+    if stock_take.applied_at.is_some() {
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "stock take has already been applied",
+        ));
+    }
+
+    // Applying an older count would undo the corrections of the newer one.
+    let superseded = data
+        .repo
+        .list_stock_takes(&bar)
+        .await?
+        .iter()
+        .any(|t| t.created_at > stock_take.created_at && t.applied_at.is_some());
+    if superseded {
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "a later stock take has already been applied",
+        ));
+    }
+
+    let applied = data
+        .repo
+        .apply_stock_take(&bar, &stock_take)
+        .await?
+        .ok_or(Error::new(
+            StatusCode::CONFLICT,
+            "stock take has already been applied, or a later one has",
+        ))?;
+
+    Ok(Json(applied))
+}
@@ -1,10 +1,15 @@
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use crate::{model::UserModel, AppState, Auth};
+use crate::{model::UsageModel, AppState, Auth};
 
 #[utoipa::path(
     post,
@@ -14,11 +19,12 @@ use crate::{model::UserModel, AppState, Auth};
     ),
     tag = crate::USER_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn register_user_handler(
     State(data): State<Arc<AppState>>,
 ) -> crate::Result<impl IntoResponse> {
-    let user = UserModel::create(&data.db).await?;
-    let auth = Auth::create(&data.signing_key, user)?;
+    let user = data.repo.create_user().await?;
+    let auth = Auth::create(&data.signing_key, data.token_ttl, user)?;
 
     Ok((StatusCode::CREATED, Json(auth)))
 }
@@ -37,12 +43,36 @@ pub(crate) struct SignIn {
     ),
     tag = crate::USER_TAG
 )]
+#[tracing::instrument(skip_all)]
 pub(crate) async fn sign_in_handler(
     State(data): State<Arc<AppState>>,
     Json(body): Json<SignIn>,
 ) -> crate::Result<impl IntoResponse> {
-    let user = UserModel::retrieve(&data.db, body.id).await?;
-    let auth = Auth::create(&data.signing_key, user)?;
+    let user = data.repo.retrieve_user(body.id).await?;
+    let auth = Auth::create(&data.signing_key, data.token_ttl, user)?;
 
     Ok(Json(auth))
 }
+
+#[utoipa::path(
+    get,
+    path = "/me/usage",
+    responses(
+        (status = OK, description = "Success", body = UsageModel, content_type = "application/json")
+    ),
+    security(
+        ("http" = [])
+    ),
+    tag = crate::USER_TAG
+)]
+#[tracing::instrument(skip_all)]
+pub(crate) async fn get_usage_handler(
+    header: HeaderMap,
+    State(data): State<Arc<AppState>>,
+) -> crate::Result<impl IntoResponse> {
+    let owner = Auth::decode_header(&data.signing_key, header)?;
+
+    Ok(Json(
+        data.repo.usage(owner, data.limits.storage_quota).await?,
+    ))
+}
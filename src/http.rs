@@ -0,0 +1,80 @@
+use axum::{
+    http::{header, HeaderName, HeaderValue, Method},
+    Router,
+};
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+    set_header::SetResponseHeaderLayer,
+};
+
+use crate::{HttpConfig, REQUEST_ID_HEADER};
+
+const CORS_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// CORS runs outermost so preflight requests are answered before anything else.
+pub(crate) fn apply_layers(mut router: Router, config: &HttpConfig) -> Router {
+    if config.compression {
+        router = router.layer(CompressionLayer::new());
+    }
+
+    if config.security_headers {
+        router = router
+            .layer(SetResponseHeaderLayer::if_not_present(
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ))
+            .layer(SetResponseHeaderLayer::if_not_present(
+                header::X_FRAME_OPTIONS,
+                HeaderValue::from_static("DENY"),
+            ))
+            .layer(SetResponseHeaderLayer::if_not_present(
+                header::REFERRER_POLICY,
+                HeaderValue::from_static("no-referrer"),
+            ));
+    }
+
+    if let Some(max_age) = config.hsts_max_age {
+        let value = format!("max-age={}; includeSubDomains", max_age.as_secs());
+        router = router.layer(SetResponseHeaderLayer::if_not_present(
+            header::STRICT_TRANSPORT_SECURITY,
+            HeaderValue::from_str(&value).expect("hsts header is valid"),
+        ));
+    }
+
+    if !config.cors_origins.is_empty() {
+        router = router.layer(cors(&config.cors_origins));
+    }
+
+    router
+}
+
+fn cors(origins: &[String]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            origins
+                .iter()
+                .map(|origin| HeaderValue::from_str(origin).expect("cors origins were validated")),
+        )
+    };
+    let request_id = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            request_id.clone(),
+        ])
+        .expose_headers([request_id, HeaderName::from_static("x-total-count")])
+        .max_age(CORS_MAX_AGE)
+}
@@ -1,43 +1,120 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 
-use axum::Router;
-use sqlx::{Pool, Postgres};
+use axum::{
+    body::Body,
+    extract::DefaultBodyLimit,
+    http::{HeaderName, Request},
+    middleware, Router,
+};
+use chrono::Duration;
+use sqlx::migrate::Migrator;
 
 use auth::*;
+pub use catalogue::*;
+pub use config::*;
 use error::*;
 use handlers::*;
+use http::apply_layers;
+use monitoring::*;
+use ratelimit::*;
+pub use repository::Database;
+use repository::*;
+pub use storage::Storage;
+use storage::*;
+pub use telemetry::*;
+pub use tls::*;
+use tower_http::{
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    timeout::TimeoutLayer,
+    trace::{DefaultOnResponse, TraceLayer},
+};
+use tracing::Level;
 use utoipa::OpenApi;
 use utoipa_axum::{router::OpenApiRouter, routes};
 use utoipa_swagger_ui::SwaggerUi;
 
 mod auth;
+mod calculator;
+mod catalogue;
+mod config;
 mod error;
 mod handlers;
+mod http;
 mod model;
+mod monitoring;
+mod placeholder;
+mod ratelimit;
+mod repository;
+mod storage;
+mod telemetry;
+mod tls;
 
 pub const MEDIA_BUCKET: &str = "tapsters-media";
+pub static MIGRATOR: Migrator = sqlx::migrate!();
+pub static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
 pub(crate) const BAR_TAG: &str = "bar";
 pub(crate) const INGREDIENT_TAG: &str = "ingredient";
 pub(crate) const MEDIA_TAG: &str = "media";
 pub(crate) const MISC_TAG: &str = "misc";
+pub(crate) const RECIPE_TAG: &str = "recipe";
 pub(crate) const USER_TAG: &str = "user";
+pub(crate) const REQUEST_ID_HEADER: &str = "x-request-id";
 
 type Result<T> = std::result::Result<T, crate::Error>;
 
+/// Fails readiness once draining so load balancers stop routing here.
+#[derive(Debug, Clone, Default)]
+pub struct Readiness(Arc<AtomicBool>);
+
+impl Readiness {
+    pub fn drain(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_draining(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
 pub struct AppState {
-    db: Pool<Postgres>,
-    s3: aws_sdk_s3::Client,
+    repo: Arc<dyn Repository>,
+    storage: Arc<dyn ObjectStore>,
     signing_key: String,
+    token_ttl: Duration,
+    http: HttpConfig,
+    limits: Limits,
+    rate_limit: RateLimitConfig,
+    readiness_timeout: std::time::Duration,
+    readiness: Readiness,
+    metrics: Metrics,
+    metrics_token: Option<String>,
+    stats: StatsGauges,
 }
 
 impl AppState {
-    pub fn new(db: Pool<Postgres>, s3: aws_sdk_s3::Client, signing_key: String) -> Self {
+    pub fn new(db: Database, storage: Storage, config: &Config) -> Self {
         Self {
-            db,
-            s3,
-            signing_key,
+            repo: db.repo,
+            storage: storage.store,
+            signing_key: config.auth.signing_key.clone(),
+            token_ttl: config.auth.token_ttl,
+            http: config.http.clone(),
+            limits: config.limits.clone(),
+            rate_limit: config.rate_limit.clone(),
+            readiness_timeout: config.server.readiness_timeout,
+            readiness: Readiness::default(),
+            metrics: Metrics::install(),
+            metrics_token: config.metrics.token.clone(),
+            stats: StatsGauges::new(config.metrics.stats_interval),
         }
     }
+
+    pub fn readiness(&self) -> Readiness {
+        self.readiness.clone()
+    }
 }
 
 #[derive(OpenApi)]
@@ -58,25 +135,139 @@ impl AppState {
         (name = INGREDIENT_TAG, description = "Ingredient API endpoints"),
         (name = MEDIA_TAG, description = "Media API endpoints"),
         (name = MISC_TAG, description = "Miscellaneous API endpoints"),
+        (name = RECIPE_TAG, description = "Recipe API endpoints"),
         (name = USER_TAG, description = "User and auth API endpoints"),
     )
 )]
 pub(crate) struct ApiDoc;
 
 pub fn router(app_state: AppState) -> Router {
-    let (router, docs) = OpenApiRouter::with_openapi(ApiDoc::openapi())
-        .routes(routes!(healthcheck_handler))
+    let http = app_state.http.clone();
+    let timeout = TimeoutLayer::new(http.request_timeout);
+
+    // Uploads are streamed and checked against the configured limits by the
+    // handler itself, and can take far longer than any other request, so
+    // neither the default body limit nor the request timeout apply to them.
+    let uploads = OpenApiRouter::new()
+        .routes(routes!(create_media_handler))
+        .layer(DefaultBodyLimit::disable());
+
+    let mut auth = OpenApiRouter::new()
+        .routes(routes!(register_user_handler))
+        .routes(routes!(sign_in_handler))
+        .layer(timeout);
+
+    let mut api = OpenApiRouter::new()
         .routes(routes!(list_units_handler))
-        .routes(routes!(create_media_handler, get_media_handler,))
+        .routes(routes!(list_media_handler))
+        .routes(routes!(get_media_handler, delete_media_handler))
+        .routes(routes!(delete_media_list_handler))
+        .routes(routes!(delete_unused_media_handler))
         .routes(routes!(create_bar_handler, list_bars_handler,))
         .routes(routes!(get_bar_handler))
-        .routes(routes!(register_user_handler))
-        .routes(routes!(sign_in_handler))
+        .routes(routes!(
+            add_bar_ingredient_handler,
+            list_bar_ingredients_handler
+        ))
+        .routes(routes!(set_bar_ingredient_levels_handler))
+        .routes(routes!(get_shopping_list_handler))
+        .routes(routes!(create_purchase_handler, list_purchases_handler))
+        .routes(routes!(get_bar_ingredient_cost_handler))
+        .routes(routes!(get_recipe_cost_handler))
+        .routes(routes!(create_serve_handler, list_serves_handler))
+        .routes(routes!(get_serve_handler))
+        .routes(routes!(undo_serve_handler))
+        .routes(routes!(create_stock_take_handler, list_stock_takes_handler))
+        .routes(routes!(get_stock_take_handler))
+        .routes(routes!(count_stock_handler))
+        .routes(routes!(close_stock_take_handler))
+        .routes(routes!(get_stock_take_report_handler))
+        .routes(routes!(apply_stock_take_handler))
+        .routes(routes!(create_bottle_handler, list_bottles_handler))
+        .routes(routes!(list_expiring_bottles_handler))
+        .routes(routes!(
+            get_bottle_handler,
+            update_bottle_handler,
+            delete_bottle_handler
+        ))
+        .routes(routes!(scan_product_handler))
+        .routes(routes!(get_usage_handler))
         .routes(routes!(create_ingredient_handler, list_ingredients_handler))
         .routes(routes!(get_ingredient_handler))
-        .routes(routes!(get_ingredient_ingredients_handler))
+        .routes(routes!(
+            add_ingredient_ingredient_handler,
+            get_ingredient_ingredients_handler
+        ))
+        .routes(routes!(set_ingredient_shelf_life_handler))
+        .routes(routes!(set_ingredient_attributes_handler))
+        .routes(routes!(fork_ingredient_handler))
+        .routes(routes!(create_shelf_life_handler, list_shelf_lives_handler))
+        .routes(routes!(create_category_handler, list_categories_handler))
+        .routes(routes!(get_product_handler))
+        .routes(routes!(create_recipe_handler, list_recipes_handler))
+        .routes(routes!(get_recipe_handler))
+        .routes(routes!(
+            add_recipe_ingredient_handler,
+            get_recipe_ingredients_handler
+        ))
+        .routes(routes!(scale_recipe_handler))
+        .routes(routes!(analyse_recipe_handler))
+        .layer(timeout)
+        .merge(uploads);
+
+    let limits = &app_state.rate_limit;
+    if limits.enabled {
+        let limiter = RateLimiter::per_ip(limits.auth, limits);
+        auth = auth.layer(middleware::from_fn_with_state(limiter, rate_limit));
+
+        let limiter = RateLimiter::per_user(limits.api, limits, &app_state.signing_key);
+        api = api.layer(middleware::from_fn_with_state(limiter, rate_limit));
+    }
+
+    // Health checks and metrics are polled by infrastructure and are never
+    // rate limited.
+    let (router, docs) = OpenApiRouter::with_openapi(ApiDoc::openapi())
+        .routes(routes!(healthcheck_handler))
+        .routes(routes!(livez_handler))
+        .routes(routes!(readyz_handler))
+        .routes(routes!(metrics_handler))
+        .layer(timeout)
+        .merge(auth)
+        .merge(api)
         .with_state(Arc::new(app_state))
         .split_for_parts();
 
-    router.merge(SwaggerUi::new("/swagger-ui").url("/docs/openapi.json", docs))
+    let request_id = HeaderName::from_static(REQUEST_ID_HEADER);
+
+    // Layers run outermost last: every request gets an id before its span is
+    // created, and the id is echoed back on every response and carried in
+    // every error body. Request metrics are recorded per route, after routing
+    // has matched one.
+    let router = router
+        .merge(SwaggerUi::new("/swagger-ui").url("/docs/openapi.json", docs))
+        .layer(DefaultBodyLimit::max(http.max_body_size))
+        .layer(middleware::from_fn(track_requests))
+        .layer(middleware::from_fn(scope_request_id));
+
+    apply_layers(router, &http)
+        .layer(PropagateRequestIdLayer::new(request_id.clone()))
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(|request: &Request<Body>| {
+                    let request_id = request
+                        .headers()
+                        .get(REQUEST_ID_HEADER)
+                        .and_then(|id| id.to_str().ok())
+                        .unwrap_or_default();
+
+                    tracing::info_span!(
+                        "request",
+                        method = %request.method(),
+                        uri = %request.uri(),
+                        request_id,
+                    )
+                })
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        .layer(SetRequestIdLayer::new(request_id, MakeRequestUuid))
 }
@@ -1,68 +1,325 @@
-use std::{env, io};
+use std::{collections::HashMap, fs, io, net::SocketAddr, path::PathBuf, process};
 
-use aws_config::{BehaviorVersion, Region};
-use aws_sdk_s3::config::Credentials;
+use axum_server::Handle;
+use clap::{Args, Parser, Subcommand};
 use dotenvy::dotenv;
-use sqlx::postgres::PgPoolOptions;
-use tapster_api::AppState;
+use tapster_api::{
+    load_certificate, read_products, redirect_router, watch_certificate, AppState, Config,
+    ConfigError, ConfigLayer, Database, DatabaseConfig, DatasetFormat, Storage, Telemetry,
+};
+use tokio::signal;
+
+#[derive(Parser)]
+#[command(version, about = "The REST CRUD API for Tapster")]
+struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Args)]
+struct GlobalArgs {
+    /// Path to a TOML config file
+    #[arg(long, global = true, env = "TAPSTER_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Postgres or SQLite connection URL
+    #[arg(long, global = true)]
+    database_url: Option<String>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the API server (the default)
+    Serve(ServeArgs),
+    /// Manage the database schema
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Manage the barcode product catalogue
+    #[command(subcommand)]
+    Products(ProductsCommand),
+}
+
+#[derive(Args, Default)]
+struct ServeArgs {
+    /// Address to listen on
+    #[arg(long)]
+    bind: Option<String>,
+
+    /// Apply pending migrations before serving
+    #[arg(long)]
+    migrate: bool,
+}
+
+#[derive(Subcommand)]
+enum MigrateCommand {
+    /// Apply all pending migrations
+    Up,
+    /// Revert applied migrations
+    Down {
+        /// Revert every migration newer than this version instead of only
+        /// the latest one
+        #[arg(long)]
+        target: Option<i64>,
+    },
+    /// List migrations and whether they have been applied
+    Status,
+}
+
+#[derive(Subcommand)]
+enum ProductsCommand {
+    /// Import products from a CSV or JSON dataset, replacing any with the
+    /// same barcode
+    Import {
+        /// Path to a .csv or .json file
+        path: PathBuf,
+    },
+}
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
     let _ = dotenv().ok();
+    let cli = Cli::parse();
 
-    let database_url = env::var("DATABASE_URL").expect("missing DATABASE_URL environment variable");
-    let pool = match PgPoolOptions::new()
-        .max_connections(16)
-        .connect(&database_url)
-        .await
-    {
-        Ok(pool) => pool,
+    match cli.command {
+        Some(Command::Migrate(command)) => {
+            let config = exit_on_error(DatabaseConfig::load(
+                cli.global.config.as_deref(),
+                cli.global.layer(),
+            ));
+            run_migrations(&config, command).await
+        }
+        Some(Command::Products(command)) => {
+            let config = exit_on_error(DatabaseConfig::load(
+                cli.global.config.as_deref(),
+                cli.global.layer(),
+            ));
+            run_products(&config, command).await
+        }
+        Some(Command::Serve(args)) => serve(&cli.global, args).await,
+        None => serve(&cli.global, ServeArgs::default()).await,
+    }
+}
+
+impl GlobalArgs {
+    fn layer(&self) -> ConfigLayer {
+        let mut flags = ConfigLayer::default();
+        flags.database.url = self.database_url.clone();
+
+        flags
+    }
+}
+
+fn exit_on_error<T>(result: Result<T, ConfigError>) -> T {
+    result.unwrap_or_else(|e| {
+        eprint!("{e}");
+        process::exit(1);
+    })
+}
+
+async fn connect_database(config: &DatabaseConfig) -> Database {
+    match Database::connect(config).await {
+        Ok(db) => db,
         Err(e) => {
             panic!("failed to connect to database: {:?}", e);
         }
-    };
+    }
+}
+
+async fn serve(global: &GlobalArgs, args: ServeArgs) -> io::Result<()> {
+    let mut flags = global.layer();
+    flags.server.bind = args.bind;
+    if args.migrate {
+        flags.database.auto_migrate = Some(true);
+    }
+
+    let config = exit_on_error(Config::load(global.config.as_deref(), flags));
+    let telemetry = Telemetry::init(&config.log);
+    let db = connect_database(&config.database).await;
+
+    if config.database.auto_migrate {
+        tracing::info!("applying pending migrations");
+        db.run_migrations()
+            .await
+            .expect("failed to run database migrations");
+    }
 
-    let s3_key =
-        env::var("S3_ACCESS_KEY_ID").expect("missing S3_ACCESS_KEY_ID environment variable");
-    let s3_secret = env::var("S3_SECRET_ACCESS_KEY")
-        .expect("missing S3_SECRET_ACCESS_KEY environment variable");
-    let s3_url = env::var("S3_URL").expect("missing S3_URL environment variable");
-    let s3_region = env::var("S3_REGION").unwrap_or("us-east-1".to_string());
-    let cred = Credentials::new(s3_key, s3_secret, None, None, "loaded-from-custom-env");
-    let config = aws_sdk_s3::config::Builder::new()
-        .behavior_version(BehaviorVersion::latest())
-        .endpoint_url(s3_url)
-        .credentials_provider(cred)
-        .region(Region::new(s3_region))
-        .force_path_style(true)
-        .build();
-    let client = aws_sdk_s3::Client::from_conf(config);
-
-    if let Err(e) = client
-        .head_bucket()
-        .bucket(tapster_api::MEDIA_BUCKET)
-        .send()
+    let storage = Storage::s3(&config.s3)
         .await
-    {
-        if e.into_service_error().is_not_found() {
-            client
-                .create_bucket()
-                .bucket(tapster_api::MEDIA_BUCKET)
-                .send()
+        .expect("failed to create media s3 bucket");
+
+    let state = AppState::new(db.clone(), storage, &config);
+    let readiness = state.readiness();
+    // Connection info lets rate limits be applied per client address.
+    let app = tapster_api::router(state).into_make_service_with_connect_info::<SocketAddr>();
+    let addr = config.server.bind;
+
+    // Readiness fails first, and requests are still served for the pre-stop
+    // delay while load balancers take the instance out. In-flight requests,
+    // such as stalled uploads, are then dropped once the drain timeout has
+    // passed.
+    let handle = Handle::new();
+    let pre_stop_delay = config.server.pre_stop_delay;
+    let drain_timeout = config.server.drain_timeout;
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            shutdown_signal().await;
+            tracing::info!(
+                delay_secs = pre_stop_delay.as_secs(),
+                "shutting down, failing readiness checks"
+            );
+            readiness.drain();
+            tokio::time::sleep(pre_stop_delay).await;
+
+            tracing::info!(
+                timeout_secs = drain_timeout.as_secs(),
+                "closing listener, draining in-flight requests"
+            );
+            handle.graceful_shutdown(Some(drain_timeout));
+        }
+    });
+
+    match &config.tls {
+        Some(tls) => {
+            let rustls = load_certificate(tls)
                 .await
-                .expect("failed to create media s3 bucket");
+                .expect("failed to load tls certificate");
+            tokio::spawn(watch_certificate(rustls.clone(), tls.clone()));
+
+            if let Some(redirect_addr) = tls.redirect_bind {
+                let redirect = axum_server::bind(redirect_addr)
+                    .handle(handle.clone())
+                    .serve(redirect_router(addr.port()).into_make_service());
+                tokio::spawn(async move {
+                    if let Err(e) = redirect.await {
+                        tracing::error!(error = %e, "https redirect listener failed");
+                    }
+                });
+                tracing::info!(addr = %redirect_addr, "redirecting http to https");
+            }
+
+            tracing::info!(%addr, "listening with tls");
+            axum_server::bind_rustls(addr, rustls)
+                .handle(handle)
+                .serve(app)
+                .await?;
+        }
+        None => {
+            tracing::info!(%addr, "listening");
+            axum_server::bind(addr).handle(handle).serve(app).await?;
         }
     }
 
-    let signing_key = env::var("SIGNING_KEY").expect("missing SIGNING_KEY environment variable");
+    db.close().await;
+    telemetry.shutdown();
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("failed to install SIGINT handler");
+    };
 
-    let app = tapster_api::router(AppState::new(pool.clone(), client, signing_key));
-    let addr = "0.0.0.0:8000";
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
 
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .expect("failed to bind tcp listener");
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    println!("Listening on {addr}");
-    axum::serve(listener, app).await
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+async fn run_migrations(config: &DatabaseConfig, command: MigrateCommand) -> io::Result<()> {
+    let db = connect_database(config).await;
+
+    match command {
+        MigrateCommand::Up => {
+            db.run_migrations()
+                .await
+                .expect("failed to run database migrations");
+        }
+        MigrateCommand::Down { target } => {
+            let applied = applied_migrations(&db).await;
+            let target = target.unwrap_or_else(|| {
+                // Step back to whichever migration precedes the latest one.
+                let mut versions: Vec<i64> = applied.keys().copied().collect();
+                versions.sort_unstable();
+                versions.iter().rev().nth(1).copied().unwrap_or(0)
+            });
+
+            db.undo_migrations(target)
+                .await
+                .expect("failed to revert database migrations");
+        }
+        MigrateCommand::Status => {
+            let applied = applied_migrations(&db).await;
+
+            for migration in db
+                .migrator()
+                .iter()
+                .filter(|m| !m.migration_type.is_down_migration())
+            {
+                let status = match applied.get(&migration.version) {
+                    Some(checksum) if *checksum != *migration.checksum => "changed",
+                    Some(_) => "applied",
+                    None => "pending",
+                };
+                println!(
+                    "{:<16} {:<8} {}",
+                    migration.version, status, migration.description
+                );
+            }
+        }
+    }
+
+    db.close().await;
+    Ok(())
+}
+
+async fn run_products(config: &DatabaseConfig, command: ProductsCommand) -> io::Result<()> {
+    match command {
+        ProductsCommand::Import { path } => {
+            let Some(format) = DatasetFormat::from_path(&path) else {
+                eprintln!("{}: expected a .csv or .json file", path.display());
+                process::exit(1);
+            };
+            let dataset = fs::read_to_string(&path)?;
+            let products = read_products(&dataset, format).unwrap_or_else(|e| {
+                eprintln!("{}: {e}", path.display());
+                process::exit(1);
+            });
+
+            let db = connect_database(config).await;
+            let imported = db.import_products(products).await;
+            db.close().await;
+            match imported {
+                Ok(count) => println!("imported {count} products"),
+                Err(e) => {
+                    eprintln!("{}: {e}", path.display());
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn applied_migrations(db: &Database) -> HashMap<i64, Vec<u8>> {
+    db.applied_migrations()
+        .await
+        .expect("failed to list applied migrations")
 }
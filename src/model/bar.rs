@@ -4,7 +4,7 @@ use sqlx::{Executor, FromRow, Postgres};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-use super::MediaModel;
+use super::{IngredientModel, MediaModel, UnitModel};
 
 #[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
 pub(crate) struct BarModel {
@@ -16,6 +16,7 @@ pub(crate) struct BarModel {
 }
 
 impl BarModel {
+    #[tracing::instrument(skip(executor))]
     pub async fn create<'a, E>(
         executor: E,
         owner: Uuid,
@@ -48,8 +49,13 @@ impl BarModel {
                     ELSE json_build_object(
                         'id', m.media_id,
                         'size', m.size,
-                        'mime_type', m.mime_type,
-                        'created_at', m.created_at
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
                     )
                 END AS "thumbnail: MediaModel"
             FROM new_bars b
@@ -64,6 +70,7 @@ impl BarModel {
         .map_err(|e| e.into())
     }
 
+    #[tracing::instrument(skip(executor))]
     pub async fn all<'a, E>(executor: E, owner: Uuid) -> crate::Result<Vec<Self>>
     where
         E: Executor<'a, Database = Postgres>,
@@ -81,8 +88,13 @@ impl BarModel {
                     ELSE json_build_object(
                         'id', m.media_id,
                         'size', m.size,
-                        'mime_type', m.mime_type,
-                        'created_at', m.created_at
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
                     )
                 END AS "thumbnail: MediaModel"
             FROM bars b
@@ -96,6 +108,7 @@ impl BarModel {
         .map_err(|e| e.into())
     }
 
+    #[tracing::instrument(skip(executor))]
     pub async fn retrieve<'a, E>(executor: E, owner: Uuid, id: Uuid) -> crate::Result<Self>
     where
         E: Executor<'a, Database = Postgres>,
@@ -113,8 +126,13 @@ impl BarModel {
                     ELSE json_build_object(
                         'id', m.media_id,
                         'size', m.size,
-                        'mime_type', m.mime_type,
-                        'created_at', m.created_at
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
                     )
                 END AS "thumbnail: MediaModel"
             FROM bars b
@@ -129,6 +147,329 @@ impl BarModel {
         .await
         .map_err(|e| e.into())
     }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn add_ingredient<'a, E>(
+        &self,
+        executor: E,
+        stock: CreateBarIngredientSchema,
+    ) -> crate::Result<BarIngredientModel>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            BarIngredientModel,
+            r#"
+            WITH new_bar_ingredients AS (
+                INSERT INTO bar_ingredients (
+                    quantity,
+                    par,
+                    minimum,
+                    bottle_size,
+                    bar_id,
+                    ingredient_id,
+                    unit_id
+                ) VALUES (
+                    $1,
+                    $2,
+                    $3,
+                    $4,
+                    $5,
+                    $6,
+                    $7
+                ) RETURNING *
+            )
+            SELECT
+                bi.bar_ingredient_id AS id,
+                bi.bar_id,
+                bi.quantity,
+                bi.par,
+                bi.minimum,
+                bi.bottle_size,
+                json_build_object(
+                    'id', i.ingredient_id,
+                    'name', i.name,
+                    'description', i.description,
+                    'abv', i.abv,
+                    'sugar', i.sugar,
+                    'acid', i.acid,
+                    'shelf_life_id', i.shelf_life_id,
+                    'category_id', i.category_id,
+                    'origin', i.origin,
+                    'producer', i.producer,
+                    'tags', i.tags,
+                    'flavours', i.flavours,
+                    'owner', i.user_id,
+                    'created_at', i.created_at,
+                    'thumbnail', CASE
+                        WHEN i.media_id IS NULL THEN NULL
+                        ELSE json_build_object(
+                            'id', m.media_id,
+                            'size', m.size,
+                            'content_type', m.mime_type,
+                            'owner', m.user_id,
+                            'created_at', m.created_at,
+                            'width', m.width,
+                            'height', m.height,
+                            'blurhash', m.blurhash,
+                            'dominant_color', m.dominant_color
+                        )
+                    END
+                ) AS "ingredient!: IngredientModel",
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel"
+            FROM new_bar_ingredients bi
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            "#,
+            stock.quantity,
+            stock.par,
+            stock.minimum,
+            stock.bottle_size,
+            self.id,
+            stock.ingredient_id,
+            stock.unit_id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn ingredients<'a, E>(&self, executor: E) -> crate::Result<Vec<BarIngredientModel>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            BarIngredientModel,
+            r#"
+            SELECT
+                bi.bar_ingredient_id AS id,
+                bi.bar_id,
+                bi.quantity,
+                bi.par,
+                bi.minimum,
+                bi.bottle_size,
+                json_build_object(
+                    'id', i.ingredient_id,
+                    'name', i.name,
+                    'description', i.description,
+                    'abv', i.abv,
+                    'sugar', i.sugar,
+                    'acid', i.acid,
+                    'shelf_life_id', i.shelf_life_id,
+                    'category_id', i.category_id,
+                    'origin', i.origin,
+                    'producer', i.producer,
+                    'tags', i.tags,
+                    'flavours', i.flavours,
+                    'owner', i.user_id,
+                    'created_at', i.created_at,
+                    'thumbnail', CASE
+                        WHEN i.media_id IS NULL THEN NULL
+                        ELSE json_build_object(
+                            'id', m.media_id,
+                            'size', m.size,
+                            'content_type', m.mime_type,
+                            'owner', m.user_id,
+                            'created_at', m.created_at,
+                            'width', m.width,
+                            'height', m.height,
+                            'blurhash', m.blurhash,
+                            'dominant_color', m.dominant_color
+                        )
+                    END
+                ) AS "ingredient!: IngredientModel",
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel"
+            FROM bar_ingredients bi
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND bi.bar_id = $2
+            ORDER BY i.name
+            "#,
+            self.owner,
+            self.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn ingredient<'a, E>(
+        &self,
+        executor: E,
+        id: Uuid,
+    ) -> crate::Result<BarIngredientModel>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            BarIngredientModel,
+            r#"
+            SELECT
+                bi.bar_ingredient_id AS id,
+                bi.bar_id,
+                bi.quantity,
+                bi.par,
+                bi.minimum,
+                bi.bottle_size,
+                json_build_object(
+                    'id', i.ingredient_id,
+                    'name', i.name,
+                    'description', i.description,
+                    'abv', i.abv,
+                    'sugar', i.sugar,
+                    'acid', i.acid,
+                    'shelf_life_id', i.shelf_life_id,
+                    'category_id', i.category_id,
+                    'origin', i.origin,
+                    'producer', i.producer,
+                    'tags', i.tags,
+                    'flavours', i.flavours,
+                    'owner', i.user_id,
+                    'created_at', i.created_at,
+                    'thumbnail', CASE
+                        WHEN i.media_id IS NULL THEN NULL
+                        ELSE json_build_object(
+                            'id', m.media_id,
+                            'size', m.size,
+                            'content_type', m.mime_type,
+                            'owner', m.user_id,
+                            'created_at', m.created_at,
+                            'width', m.width,
+                            'height', m.height,
+                            'blurhash', m.blurhash,
+                            'dominant_color', m.dominant_color
+                        )
+                    END
+                ) AS "ingredient!: IngredientModel",
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel"
+            FROM bar_ingredients bi
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND bi.bar_id = $2
+                AND bi.bar_ingredient_id = $3
+            "#,
+            self.owner,
+            self.id,
+            id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn set_ingredient_levels<'a, E>(
+        &self,
+        executor: E,
+        id: Uuid,
+        levels: &BarIngredientLevelsSchema,
+    ) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE bar_ingredients
+            SET par = $1,
+                minimum = $2,
+                bottle_size = $3
+            WHERE bar_id = $4
+                AND bar_ingredient_id = $5
+            RETURNING bar_ingredient_id
+            "#,
+            levels.par,
+            levels.minimum,
+            levels.bottle_size,
+            self.id,
+            id,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn restock_ingredient<'a, E>(
+        &self,
+        executor: E,
+        id: Uuid,
+        quantity: f32,
+    ) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE bar_ingredients
+            SET quantity = quantity + $1
+            WHERE bar_id = $2
+                AND bar_ingredient_id = $3
+            RETURNING bar_ingredient_id
+            "#,
+            quantity,
+            self.id,
+            id,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Serializes changes to the bar's stock as a whole.
+    #[tracing::instrument(skip(executor))]
+    pub async fn lock<'a, E>(&self, executor: E) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            SELECT bar_id
+            FROM bars
+            WHERE bar_id = $1
+            FOR UPDATE
+            "#,
+            self.id,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
@@ -137,3 +478,36 @@ pub(crate) struct CreateBarSchema {
     #[serde(rename = "thumbnailId")]
     pub thumbnail_id: Option<Uuid>,
 }
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct BarIngredientModel {
+    pub id: Uuid,
+    pub bar_id: Uuid,
+    pub quantity: f32,
+    pub par: Option<f32>,
+    pub minimum: Option<f32>,
+    pub bottle_size: Option<f32>,
+    pub ingredient: IngredientModel,
+    pub unit: UnitModel,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateBarIngredientSchema {
+    #[serde(rename = "ingredientId")]
+    pub ingredient_id: Uuid,
+    pub quantity: f32,
+    #[serde(rename = "unitId")]
+    pub unit_id: Uuid,
+    pub par: Option<f32>,
+    pub minimum: Option<f32>,
+    #[serde(rename = "bottleSize")]
+    pub bottle_size: Option<f32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct BarIngredientLevelsSchema {
+    pub par: Option<f32>,
+    pub minimum: Option<f32>,
+    #[serde(rename = "bottleSize")]
+    pub bottle_size: Option<f32>,
+}
@@ -0,0 +1,141 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use uuid::Uuid;
+
+/// Blobs are content addressed and reference counted, so identical uploads
+/// share one object.
+#[derive(Debug, FromRow, Deserialize, Serialize)]
+pub(crate) struct BlobModel {
+    pub id: Uuid,
+    pub hash: Option<String>,
+    pub key: String,
+    pub size: i64,
+    pub reference_count: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl BlobModel {
+    pub fn key_for_hash(hash: &str) -> String {
+        format!("sha256/{hash}")
+    }
+
+    /// A `reference_count` of 1 on the result means the bytes still need
+    /// storing.
+    #[tracing::instrument(skip(executor))]
+    pub async fn acquire<'a, E>(executor: E, hash: &str, size: i64) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO blobs (
+                hash,
+                object_key,
+                size,
+                reference_count
+            ) VALUES (
+                $1,
+                $2,
+                $3,
+                1
+            )
+            ON CONFLICT (hash) DO UPDATE
+            SET reference_count = blobs.reference_count + 1
+            RETURNING
+                blob_id AS id,
+                hash,
+                object_key AS key,
+                size,
+                reference_count,
+                created_at
+            "#,
+            hash,
+            Self::key_for_hash(hash),
+            size,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                blob_id AS id,
+                hash,
+                object_key AS key,
+                size,
+                reference_count,
+                created_at
+            FROM blobs
+            WHERE blob_id = $1
+            "#,
+            id
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn release<'a, E>(executor: E, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            UPDATE blobs
+            SET reference_count = reference_count - 1
+            WHERE blob_id = $1
+            RETURNING
+                blob_id AS id,
+                hash,
+                object_key AS key,
+                size,
+                reference_count,
+                created_at
+            "#,
+            id
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    /// The deleted row stays locked until the transaction ends, so an upload of
+    /// the same bytes waits on it.
+    #[tracing::instrument(skip(executor))]
+    pub async fn delete_unreferenced<'a, E>(executor: E, id: Uuid) -> crate::Result<Option<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            DELETE FROM blobs
+            WHERE blob_id = $1
+                AND reference_count = 0
+            RETURNING
+                blob_id AS id,
+                hash,
+                object_key AS key,
+                size,
+                reference_count,
+                created_at
+            "#,
+            id
+        )
+        .fetch_optional(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+}
@@ -0,0 +1,265 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{BarModel, UnitModel};
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct BottleModel {
+    pub id: Uuid,
+    pub bar_id: Uuid,
+    pub bar_ingredient_id: Uuid,
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub size: f32,
+    /// From 0 for empty to 1 for full.
+    pub fill: f32,
+    pub unit: UnitModel,
+    pub location: Option<String>,
+    pub opened_at: Option<NaiveDateTime>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+}
+
+impl BottleModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(executor: E, bottle: &CreateBottleSchema) -> crate::Result<Uuid>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO bottles (
+                size,
+                fill,
+                location,
+                opened_at,
+                bar_ingredient_id
+            ) VALUES (
+                $1,
+                COALESCE($2::REAL, 1),
+                $3,
+                $4,
+                $5
+            ) RETURNING bottle_id
+            "#,
+            bottle.size,
+            bottle.fill,
+            bottle.location,
+            bottle.opened_at,
+            bottle.bar_ingredient_id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'a, E>(executor: E, bar: &BarModel) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                bo.bottle_id AS id,
+                bi.bar_id,
+                bo.bar_ingredient_id,
+                i.ingredient_id,
+                i.name,
+                bo.size,
+                bo.fill,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel",
+                bo.location,
+                bo.opened_at,
+                bo.opened_at + make_interval(days => s.days) AS expires_at,
+                bo.created_at
+            FROM bottles bo
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = bo.bar_ingredient_id
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN shelf_lives s ON s.shelf_life_id = i.shelf_life_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND bi.bar_id = $2
+            ORDER BY i.name, bo.created_at, bo.bottle_id
+            "#,
+            bar.owner,
+            bar.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, bar: &BarModel, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                bo.bottle_id AS id,
+                bi.bar_id,
+                bo.bar_ingredient_id,
+                i.ingredient_id,
+                i.name,
+                bo.size,
+                bo.fill,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel",
+                bo.location,
+                bo.opened_at,
+                bo.opened_at + make_interval(days => s.days) AS expires_at,
+                bo.created_at
+            FROM bottles bo
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = bo.bar_ingredient_id
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN shelf_lives s ON s.shelf_life_id = i.shelf_life_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND bi.bar_id = $2
+                AND bo.bottle_id = $3
+            "#,
+            bar.owner,
+            bar.id,
+            id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn expiring<'a, E>(executor: E, bar: &BarModel, days: i32) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                bo.bottle_id AS id,
+                bi.bar_id,
+                bo.bar_ingredient_id,
+                i.ingredient_id,
+                i.name,
+                bo.size,
+                bo.fill,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel",
+                bo.location,
+                bo.opened_at,
+                bo.opened_at + make_interval(days => s.days) AS expires_at,
+                bo.created_at
+            FROM bottles bo
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = bo.bar_ingredient_id
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            JOIN shelf_lives s ON s.shelf_life_id = i.shelf_life_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND bi.bar_id = $2
+                AND bo.fill > 0
+                AND bo.opened_at + make_interval(days => s.days)
+                    <= now() + make_interval(days => $3)
+            ORDER BY expires_at, i.name, bo.bottle_id
+            "#,
+            bar.owner,
+            bar.id,
+            days,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn update<'a, E>(&self, executor: E, bottle: &UpdateBottleSchema) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            UPDATE bottles
+            SET fill = $1,
+                location = $2,
+                opened_at = $3
+            WHERE bottle_id = $4
+            "#,
+            bottle.fill,
+            bottle.location,
+            bottle.opened_at,
+            self.id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn delete<'a, E>(&self, executor: E) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            DELETE FROM bottles
+            WHERE bottle_id = $1
+            "#,
+            self.id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateBottleSchema {
+    #[serde(rename = "barIngredientId")]
+    pub bar_ingredient_id: Uuid,
+    pub size: Option<f32>,
+    pub fill: Option<f32>,
+    pub location: Option<String>,
+    #[serde(rename = "openedAt")]
+    pub opened_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct UpdateBottleSchema {
+    pub fill: f32,
+    pub location: Option<String>,
+    #[serde(rename = "openedAt")]
+    pub opened_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CategoryModel {
+    pub id: Uuid,
+    pub name: String,
+    pub parent_id: Option<Uuid>,
+    pub owner: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+impl CategoryModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(
+        executor: E,
+        owner: Uuid,
+        category: CreateCategorySchema,
+    ) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO categories (
+                name,
+                parent_id,
+                user_id
+            ) VALUES (
+                lower($1),
+                $2,
+                $3
+            ) RETURNING
+                category_id AS id,
+                name,
+                parent_id,
+                user_id AS owner,
+                created_at
+            "#,
+            category.name,
+            category.parent_id,
+            owner,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'a, E>(executor: E, owner: Uuid) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                category_id AS "id!",
+                name AS "name!",
+                parent_id,
+                user_id AS owner,
+                created_at AS "created_at!"
+            FROM categories
+            WHERE user_id = $1
+                OR user_id IS NULL
+            ORDER BY name
+            "#,
+            owner,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, owner: Uuid, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                category_id AS "id!",
+                name AS "name!",
+                parent_id,
+                user_id AS owner,
+                created_at AS "created_at!"
+            FROM categories
+            WHERE (user_id = $1 OR user_id IS NULL)
+                AND category_id = $2
+            "#,
+            owner,
+            id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    pub fn within(categories: &[Self], id: Uuid) -> HashSet<Uuid> {
+        let mut within = HashSet::from([id]);
+        // Each pass takes in the children of what is already in, until a pass
+        // finds none.
+        loop {
+            let before = within.len();
+            for category in categories {
+                if category.parent_id.is_some_and(|p| within.contains(&p)) {
+                    within.insert(category.id);
+                }
+            }
+            if within.len() == before {
+                return within;
+            }
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateCategorySchema {
+    pub name: String,
+    #[serde(rename = "parentId")]
+    pub parent_id: Option<Uuid>,
+}
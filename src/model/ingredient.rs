@@ -1,23 +1,38 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use sqlx::{prelude::FromRow, Executor, Postgres, Type};
+use sqlx::{prelude::FromRow, Executor, Postgres};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
 use super::MediaModel;
 
-#[derive(Debug, FromRow, Type, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
 pub(crate) struct IngredientModel {
     pub id: Uuid,
     pub name: String,
     pub description: String,
-    pub owner: Uuid,
+    /// Alcohol by volume, as a percentage.
+    pub abv: Option<f32>,
+    /// Grams of sugar per 100ml.
+    pub sugar: Option<f32>,
+    /// Grams of acid per 100ml.
+    pub acid: Option<f32>,
+    pub shelf_life_id: Option<Uuid>,
+    pub category_id: Option<Uuid>,
+    pub origin: Option<String>,
+    pub producer: Option<String>,
+    pub tags: Vec<String>,
+    pub flavours: Vec<String>,
+    pub owner: Option<Uuid>,
     pub created_at: NaiveDateTime,
     #[sqlx(json(nullable))]
     pub thumbnail: Option<MediaModel>,
 }
 
+super::json_type!(IngredientModel);
+
 impl IngredientModel {
+    #[tracing::instrument(skip(executor))]
     pub async fn create<'a, E>(
         executor: E,
         owner: Uuid,
@@ -33,19 +48,46 @@ impl IngredientModel {
                 INSERT INTO ingredients (
                     name,
                     description,
+                    abv,
+                    sugar,
+                    acid,
+                    shelf_life_id,
                     media_id,
-                    user_id
+                    user_id,
+                    category_id,
+                    origin,
+                    producer,
+                    tags,
+                    flavours
                 ) VALUES (
                     lower($1),
                     $2,
                     $3,
-                    $4
+                    $4,
+                    $5,
+                    $6,
+                    $7,
+                    $8,
+                    $9,
+                    $10,
+                    $11,
+                    $12,
+                    $13
                 ) RETURNING *
             )
             SELECT
                 i.ingredient_id AS id,
                 i.name,
                 i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags AS "tags!",
+                i.flavours AS "flavours!",
                 i.user_id AS owner,
                 i.created_at,
                 CASE
@@ -53,8 +95,13 @@ impl IngredientModel {
                     ELSE json_build_object(
                         'id', m.media_id,
                         'size', m.size,
-                        'mime_type', m.mime_type,
-                        'created_at', m.created_at
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
                     )
                 END AS "thumbnail: MediaModel"
             FROM new_ingredients i
@@ -62,14 +109,24 @@ impl IngredientModel {
             "#,
             ingredient.name,
             ingredient.description,
+            ingredient.abv,
+            ingredient.sugar,
+            ingredient.acid,
+            ingredient.shelf_life_id,
             ingredient.thumbnail_id,
-            owner
+            owner,
+            ingredient.category_id,
+            ingredient.origin,
+            ingredient.producer,
+            &ingredient.tags,
+            &ingredient.flavours,
         )
         .fetch_one(executor)
         .await
         .map_err(|e| e.into())
     }
 
+    #[tracing::instrument(skip(executor))]
     pub async fn all<'a, E>(executor: E, owner: Uuid) -> crate::Result<Vec<Self>>
     where
         E: Executor<'a, Database = Postgres>,
@@ -78,23 +135,38 @@ impl IngredientModel {
             IngredientModel,
             r#"
             SELECT
-                i.ingredient_id AS id,
-                i.name,
-                i.description,
+                i.ingredient_id AS "id!",
+                i.name AS "name!",
+                i.description AS "description!",
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags AS "tags!",
+                i.flavours AS "flavours!",
                 i.user_id AS owner,
-                i.created_at,
+                i.created_at AS "created_at!",
                 CASE
                     WHEN i.media_id IS NULL THEN NULL
                     ELSE json_build_object(
                         'id', m.media_id,
                         'size', m.size,
-                        'mime_type', m.mime_type,
-                        'created_at', m.created_at
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
                     )
                 END AS "thumbnail: MediaModel"
             FROM ingredients i
             LEFT JOIN media m USING (user_id, media_id)
             WHERE i.user_id = $1
+                OR i.user_id IS NULL
             "#,
             owner
         )
@@ -103,6 +175,7 @@ impl IngredientModel {
         .map_err(|e| e.into())
     }
 
+    #[tracing::instrument(skip(executor))]
     pub async fn retrieve<'a, E>(executor: E, owner: Uuid, id: Uuid) -> crate::Result<Self>
     where
         E: Executor<'a, Database = Postgres>,
@@ -114,6 +187,15 @@ impl IngredientModel {
                 i.ingredient_id AS id,
                 i.name,
                 i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags AS "tags!",
+                i.flavours AS "flavours!",
                 i.user_id AS owner,
                 i.created_at,
                 CASE
@@ -121,13 +203,18 @@ impl IngredientModel {
                     ELSE json_build_object(
                         'id', m.media_id,
                         'size', m.size,
-                        'mime_type', m.mime_type,
-                        'created_at', m.created_at
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
                     )
                 END AS "thumbnail: MediaModel"
             FROM ingredients i
             LEFT JOIN media m USING (user_id, media_id)
-            WHERE i.user_id = $1
+            WHERE (i.user_id = $1 OR i.user_id IS NULL)
                 AND i.ingredient_id = $2
             "#,
             owner,
@@ -138,6 +225,7 @@ impl IngredientModel {
         .map_err(|e| e.into())
     }
 
+    #[tracing::instrument(skip(executor))]
     pub async fn ingredients<'a, E>(self, executor: E) -> crate::Result<Vec<SubIngredientModel>>
     where
         E: Executor<'a, Database = Postgres>,
@@ -152,6 +240,15 @@ impl IngredientModel {
                     'id', i.ingredient_id,
                     'name', i.name,
                     'description', i.description,
+                    'abv', i.abv,
+                    'sugar', i.sugar,
+                    'acid', i.acid,
+                    'shelf_life_id', i.shelf_life_id,
+                    'category_id', i.category_id,
+                    'origin', i.origin,
+                    'producer', i.producer,
+                    'tags', i.tags,
+                    'flavours', i.flavours,
                     'owner', i.user_id,
                     'created_at', i.created_at,
                     'thumbnail', CASE
@@ -159,15 +256,20 @@ impl IngredientModel {
                         ELSE json_build_object(
                             'id', m.media_id,
                             'size', m.size,
-                            'mime_type', m.mime_type,
-                            'created_at', m.created_at
+                            'content_type', m.mime_type,
+                            'owner', m.user_id,
+                            'created_at', m.created_at,
+                            'width', m.width,
+                            'height', m.height,
+                            'blurhash', m.blurhash,
+                            'dominant_color', m.dominant_color
                         )
                     END
                 ) AS "ingredient: IngredientModel"
             FROM ingredient_ingredients ii
             JOIN ingredients i USING (ingredient_id)
-            JOIN media m USING (media_id)
-            WHERE i.user_id = $1
+            LEFT JOIN media m USING (media_id)
+            WHERE (i.user_id = $1 OR i.user_id IS NULL)
                 AND ii.compound_ingredient_id = $2
             "#,
             self.owner,
@@ -177,20 +279,255 @@ impl IngredientModel {
         .await
         .map_err(|e| e.into())
     }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn add_ingredient<'a, E>(
+        &self,
+        executor: E,
+        part: CreateSubIngredientSchema,
+    ) -> crate::Result<SubIngredientModel>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            SubIngredientModel,
+            r#"
+            WITH new_ingredient_ingredients AS (
+                INSERT INTO ingredient_ingredients (
+                    parts,
+                    ingredient_id,
+                    compound_ingredient_id
+                ) VALUES (
+                    $1,
+                    $2,
+                    $3
+                ) RETURNING *
+            )
+            SELECT
+                ii.ingredient_ingredient_id AS id,
+                ii.parts,
+                json_build_object(
+                    'id', i.ingredient_id,
+                    'name', i.name,
+                    'description', i.description,
+                    'abv', i.abv,
+                    'sugar', i.sugar,
+                    'acid', i.acid,
+                    'shelf_life_id', i.shelf_life_id,
+                    'category_id', i.category_id,
+                    'origin', i.origin,
+                    'producer', i.producer,
+                    'tags', i.tags,
+                    'flavours', i.flavours,
+                    'owner', i.user_id,
+                    'created_at', i.created_at,
+                    'thumbnail', CASE
+                        WHEN i.media_id IS NULL THEN NULL
+                        ELSE json_build_object(
+                            'id', m.media_id,
+                            'size', m.size,
+                            'content_type', m.mime_type,
+                            'owner', m.user_id,
+                            'created_at', m.created_at,
+                            'width', m.width,
+                            'height', m.height,
+                            'blurhash', m.blurhash,
+                            'dominant_color', m.dominant_color
+                        )
+                    END
+                ) AS "ingredient: IngredientModel"
+            FROM new_ingredient_ingredients ii
+            JOIN ingredients i USING (ingredient_id)
+            LEFT JOIN media m USING (media_id)
+            "#,
+            part.parts,
+            part.ingredient_id,
+            self.id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn set_shelf_life<'a, E>(
+        &self,
+        executor: E,
+        shelf_life_id: Option<Uuid>,
+    ) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            UPDATE ingredients
+            SET shelf_life_id = $1
+            WHERE ingredient_id = $2
+            "#,
+            shelf_life_id,
+            self.id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn set_attributes<'a, E>(
+        &self,
+        executor: E,
+        attributes: &IngredientAttributesSchema,
+    ) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            UPDATE ingredients
+            SET category_id = $1,
+                origin = $2,
+                producer = $3,
+                tags = $4,
+                flavours = $5
+            WHERE ingredient_id = $6
+            "#,
+            attributes.category_id,
+            attributes.origin,
+            attributes.producer,
+            &attributes.tags,
+            &attributes.flavours,
+            self.id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn copy_parts<'a, E>(&self, executor: E, fork_id: Uuid) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            INSERT INTO ingredient_ingredients (
+                parts,
+                ingredient_id,
+                compound_ingredient_id
+            )
+            SELECT
+                parts,
+                ingredient_id,
+                $2
+            FROM ingredient_ingredients
+            WHERE compound_ingredient_id = $1
+            "#,
+            self.id,
+            fork_id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn parts<'a, E>(executor: E, owner: Uuid) -> crate::Result<Vec<IngredientPartModel>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            IngredientPartModel,
+            r#"
+            SELECT
+                ii.compound_ingredient_id AS compound_id,
+                ii.ingredient_id,
+                ii.parts
+            FROM ingredient_ingredients ii
+            JOIN ingredients c ON c.ingredient_id = ii.compound_ingredient_id
+            WHERE c.user_id = $1
+                OR c.user_id IS NULL
+            "#,
+            owner,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub(crate) struct CreateIngredientSchema {
     pub name: String,
     pub description: String,
+    pub abv: Option<f32>,
+    pub sugar: Option<f32>,
+    pub acid: Option<f32>,
+    #[serde(rename = "shelfLifeId")]
+    pub shelf_life_id: Option<Uuid>,
     #[serde(rename = "thumbnailId")]
     pub thumbnail_id: Option<Uuid>,
+    #[serde(rename = "categoryId")]
+    pub category_id: Option<Uuid>,
+    pub origin: Option<String>,
+    pub producer: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub flavours: Vec<String>,
 }
 
-#[derive(Debug, FromRow, Type, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct IngredientAttributesSchema {
+    #[serde(rename = "categoryId")]
+    pub category_id: Option<Uuid>,
+    pub origin: Option<String>,
+    pub producer: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub flavours: Vec<String>,
+}
+
+impl From<&IngredientModel> for CreateIngredientSchema {
+    fn from(ingredient: &IngredientModel) -> Self {
+        Self {
+            name: ingredient.name.clone(),
+            description: ingredient.description.clone(),
+            abv: ingredient.abv,
+            sugar: ingredient.sugar,
+            acid: ingredient.acid,
+            shelf_life_id: ingredient.shelf_life_id,
+            thumbnail_id: ingredient.thumbnail.as_ref().map(|t| t.id),
+            category_id: ingredient.category_id,
+            origin: ingredient.origin.clone(),
+            producer: ingredient.producer.clone(),
+            tags: ingredient.tags.clone(),
+            flavours: ingredient.flavours.clone(),
+        }
+    }
+}
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
 pub(crate) struct SubIngredientModel {
     pub id: Uuid,
     pub parts: i16,
     #[sqlx(json)]
     pub ingredient: Option<IngredientModel>,
 }
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateSubIngredientSchema {
+    #[serde(rename = "ingredientId")]
+    pub ingredient_id: Uuid,
+    pub parts: i16,
+}
+
+#[derive(Debug, FromRow)]
+pub(crate) struct IngredientPartModel {
+    pub compound_id: Uuid,
+    pub ingredient_id: Uuid,
+    pub parts: i16,
+}
@@ -1,20 +1,34 @@
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
-use sqlx::{prelude::FromRow, Executor, Postgres, Type};
+use sqlx::{prelude::FromRow, Executor, Postgres};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, FromRow, Type, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
 pub(crate) struct MediaModel {
     pub id: Uuid,
     pub size: i64,
     pub content_type: String,
     pub owner: Uuid,
     pub created_at: NaiveDateTime,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    pub dominant_color: Option<String>,
+    #[serde(skip)]
+    pub blob_id: Uuid,
 }
 
+super::json_type!(MediaModel);
+
 impl MediaModel {
-    pub async fn create<'a, E>(executor: E, owner: Uuid) -> crate::Result<Self>
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(
+        executor: E,
+        owner: Uuid,
+        blob_id: Uuid,
+        media: CreateMediaSchema,
+    ) -> crate::Result<Self>
     where
         E: Executor<'a, Database = Postgres>,
     {
@@ -22,16 +36,46 @@ impl MediaModel {
             Self,
             r#"
             WITH new_media AS (
-                INSERT INTO media (user_id) VALUES ($1) RETURNING *
+                INSERT INTO media (
+                    size,
+                    mime_type,
+                    width,
+                    height,
+                    blurhash,
+                    dominant_color,
+                    blob_id,
+                    user_id
+                ) VALUES (
+                    $1,
+                    $2,
+                    $3,
+                    $4,
+                    $5,
+                    $6,
+                    $7,
+                    $8
+                ) RETURNING *
             )
             SELECT
                 media_id AS id,
                 size,
                 mime_type AS content_type,
                 user_id AS owner,
-                created_at
+                created_at,
+                width,
+                height,
+                blurhash,
+                dominant_color,
+                blob_id
             FROM new_media
             "#,
+            media.size,
+            media.content_type,
+            media.width,
+            media.height,
+            media.blurhash,
+            media.dominant_color,
+            blob_id,
             owner
         )
         .fetch_one(executor)
@@ -39,6 +83,7 @@ impl MediaModel {
         .map_err(|e| e.into())
     }
 
+    #[tracing::instrument(skip(executor))]
     pub async fn retrieve<'a, E>(executor: E, owner: Uuid, id: Uuid) -> crate::Result<Self>
     where
         E: Executor<'a, Database = Postgres>,
@@ -51,7 +96,12 @@ impl MediaModel {
                 size,
                 mime_type AS content_type,
                 user_id AS owner,
-                created_at
+                created_at,
+                width,
+                height,
+                blurhash,
+                dominant_color,
+                blob_id
             FROM media
             WHERE user_id = $1
                 AND media_id = $2
@@ -64,38 +114,188 @@ impl MediaModel {
         .map_err(|e| e.into())
     }
 
-    pub async fn update<'a, E>(
-        self,
+    /// `content_type` is a `LIKE` pattern.
+    #[tracing::instrument(skip(executor))]
+    pub async fn library<'a, E>(
         executor: E,
         owner: Uuid,
-        media: UpdateMediaSchema,
-    ) -> crate::Result<Self>
+        content_type: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> crate::Result<Vec<MediaLibraryModel>>
     where
         E: Executor<'a, Database = Postgres>,
     {
         sqlx::query_as!(
-            Self,
+            MediaLibraryModel,
             r#"
-            WITH updated_media AS (
-                UPDATE media m
-                SET
-                    size = COALESCE($1, m.size),
-                    mime_type = COALESCE($2, m.mime_type)
-                WHERE m.user_id = $3
-                    AND m.media_id = $4
-                RETURNING *
-            )
             SELECT
+                m.media_id AS id,
+                m.size,
+                m.mime_type AS content_type,
+                m.user_id AS owner,
+                m.created_at,
+                m.width,
+                m.height,
+                m.blurhash,
+                m.dominant_color,
+                ARRAY(
+                    SELECT b.bar_id
+                    FROM bars b
+                    WHERE b.user_id = m.user_id
+                        AND b.media_id = m.media_id
+                ) AS "bars!",
+                ARRAY(
+                    SELECT i.ingredient_id
+                    FROM ingredients i
+                    WHERE i.user_id = m.user_id
+                        AND i.media_id = m.media_id
+                ) AS "ingredients!",
+                ARRAY(
+                    SELECT r.recipe_id
+                    FROM recipes r
+                    WHERE r.user_id = m.user_id
+                        AND r.media_id = m.media_id
+                ) AS "recipes!",
+                ARRAY(
+                    SELECT p.profile_id
+                    FROM profiles p
+                    WHERE p.user_id = m.user_id
+                        AND p.media_id = m.media_id
+                ) AS "profiles!"
+            FROM media m
+            WHERE m.user_id = $1
+                AND ($2::TEXT IS NULL OR m.mime_type LIKE $2 ESCAPE '\')
+            ORDER BY m.created_at DESC, m.media_id
+            LIMIT $3
+            OFFSET $4
+            "#,
+            owner,
+            content_type,
+            limit,
+            offset,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn count<'a, E>(
+        executor: E,
+        owner: Uuid,
+        content_type: Option<&str>,
+    ) -> crate::Result<i64>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM media m
+            WHERE m.user_id = $1
+                AND ($2::TEXT IS NULL OR m.mime_type LIKE $2 ESCAPE '\')
+            "#,
+            owner,
+            content_type,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn delete_many<'a, E>(
+        executor: E,
+        owner: Uuid,
+        ids: &[Uuid],
+    ) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            DELETE FROM media
+            WHERE user_id = $1
+                AND media_id = ANY($2)
+            RETURNING
                 media_id AS id,
                 size,
                 mime_type AS content_type,
                 user_id AS owner,
-                created_at
-            FROM updated_media
+                created_at,
+                width,
+                height,
+                blurhash,
+                dominant_color,
+                blob_id
             "#,
-            media.size,
-            media.content_type,
             owner,
+            ids,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn delete_unused<'a, E>(executor: E, owner: Uuid) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            DELETE FROM media m
+            WHERE m.user_id = $1
+                AND NOT EXISTS (SELECT 1 FROM bars b WHERE b.media_id = m.media_id)
+                AND NOT EXISTS (SELECT 1 FROM ingredients i WHERE i.media_id = m.media_id)
+                AND NOT EXISTS (SELECT 1 FROM recipes r WHERE r.media_id = m.media_id)
+                AND NOT EXISTS (SELECT 1 FROM profiles p WHERE p.media_id = m.media_id)
+            RETURNING
+                media_id AS id,
+                size,
+                mime_type AS content_type,
+                user_id AS owner,
+                created_at,
+                width,
+                height,
+                blurhash,
+                dominant_color,
+                blob_id
+            "#,
+            owner,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn delete<'a, E>(self, executor: E) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            DELETE FROM media
+            WHERE user_id = $1
+                AND media_id = $2
+            RETURNING
+                media_id AS id,
+                size,
+                mime_type AS content_type,
+                user_id AS owner,
+                created_at,
+                width,
+                height,
+                blurhash,
+                dominant_color,
+                blob_id
+            "#,
+            self.owner,
             self.id,
         )
         .fetch_one(executor)
@@ -104,9 +304,31 @@ impl MediaModel {
     }
 }
 
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct MediaLibraryModel {
+    pub id: Uuid,
+    pub size: i64,
+    pub content_type: String,
+    pub owner: Uuid,
+    pub created_at: NaiveDateTime,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    pub dominant_color: Option<String>,
+    pub bars: Vec<Uuid>,
+    pub ingredients: Vec<Uuid>,
+    pub recipes: Vec<Uuid>,
+    pub profiles: Vec<Uuid>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
-pub(crate) struct UpdateMediaSchema {
-    pub size: Option<i64>,
+pub(crate) struct CreateMediaSchema {
+    pub size: i64,
     #[serde(rename = "contentType")]
-    pub content_type: Option<String>,
+    pub content_type: String,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub blurhash: Option<String>,
+    #[serde(rename = "dominantColor")]
+    pub dominant_color: Option<String>,
 }
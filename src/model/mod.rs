@@ -1,11 +1,69 @@
 pub(crate) use bar::*;
+pub(crate) use blob::*;
+pub(crate) use bottle::*;
+pub(crate) use category::*;
 pub(crate) use ingredient::*;
 pub(crate) use media::*;
+pub(crate) use product::*;
+pub(crate) use purchase::*;
+pub(crate) use recipe::*;
+pub(crate) use serve::*;
+pub(crate) use shelf_life::*;
+pub(crate) use stats::*;
+pub(crate) use stock_take::*;
 pub(crate) use unit::*;
+pub(crate) use usage::*;
 pub(crate) use user::*;
 
+/// For models embedded with `json_build_object` and `array_agg`.
+macro_rules! json_type {
+    ($model:ty) => {
+        impl sqlx::Type<sqlx::Postgres> for $model {
+            fn type_info() -> sqlx::postgres::PgTypeInfo {
+                <sqlx::types::Json<Self> as sqlx::Type<sqlx::Postgres>>::type_info()
+            }
+
+            fn compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+                <sqlx::types::Json<Self> as sqlx::Type<sqlx::Postgres>>::compatible(ty)
+            }
+        }
+
+        impl sqlx::postgres::PgHasArrayType for $model {
+            fn array_type_info() -> sqlx::postgres::PgTypeInfo {
+                <sqlx::types::Json<Self> as sqlx::postgres::PgHasArrayType>::array_type_info()
+            }
+
+            fn array_compatible(ty: &sqlx::postgres::PgTypeInfo) -> bool {
+                <sqlx::types::Json<Self> as sqlx::postgres::PgHasArrayType>::array_compatible(ty)
+            }
+        }
+
+        impl<'r> sqlx::Decode<'r, sqlx::Postgres> for $model {
+            fn decode(
+                value: sqlx::postgres::PgValueRef<'r>,
+            ) -> Result<Self, sqlx::error::BoxDynError> {
+                <sqlx::types::Json<Self> as sqlx::Decode<sqlx::Postgres>>::decode(value)
+                    .map(|json| json.0)
+            }
+        }
+    };
+}
+
+pub(crate) use json_type;
+
 mod bar;
+mod blob;
+mod bottle;
+mod category;
 mod ingredient;
 mod media;
+mod product;
+mod purchase;
+mod recipe;
+mod serve;
+mod shelf_life;
+mod stats;
+mod stock_take;
 mod unit;
+mod usage;
 mod user;
@@ -0,0 +1,209 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{
+    BarIngredientModel, BarModel, BottleModel, CreateBarIngredientSchema, CreateBottleSchema,
+    CreateIngredientSchema, UnitModel,
+};
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ProductModel {
+    pub barcode: String,
+    pub name: String,
+    pub ingredient: String,
+    pub size: f32,
+    pub unit: UnitModel,
+    pub abv: Option<f32>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ProductModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn upsert<'a, E>(executor: E, product: &CreateProductSchema) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            INSERT INTO products (
+                barcode,
+                name,
+                ingredient,
+                size,
+                abv,
+                unit_id
+            ) VALUES (
+                $1,
+                $2,
+                $3,
+                $4,
+                $5,
+                $6
+            ) ON CONFLICT (barcode) DO UPDATE
+            SET name = excluded.name,
+                ingredient = excluded.ingredient,
+                size = excluded.size,
+                abv = excluded.abv,
+                unit_id = excluded.unit_id
+            "#,
+            product.barcode,
+            product.name,
+            product.ingredient,
+            product.size,
+            product.abv,
+            product.unit_id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, barcode: &str) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                p.barcode,
+                p.name,
+                p.ingredient,
+                p.size,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel",
+                p.abv,
+                p.created_at
+            FROM products p
+            JOIN units u ON u.unit_id = p.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE p.barcode = $1
+            "#,
+            barcode,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct CreateProductSchema {
+    pub barcode: String,
+    pub name: String,
+    pub ingredient: String,
+    pub size: f32,
+    pub abv: Option<f32>,
+    pub unit_id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateScanSchema {
+    pub barcode: String,
+    pub bottles: Option<i32>,
+}
+
+#[derive(Debug)]
+pub(crate) struct RestockSchema {
+    pub product: ProductModel,
+    pub ingredient_id: Option<Uuid>,
+    pub bar_ingredient_id: Option<Uuid>,
+    pub bottle_size: f32,
+    pub bottles: i32,
+}
+
+impl RestockSchema {
+    pub fn ingredient(&self) -> CreateIngredientSchema {
+        CreateIngredientSchema {
+            name: self.product.ingredient.clone(),
+            description: self.product.name.clone(),
+            abv: self.product.abv,
+            sugar: None,
+            acid: None,
+            shelf_life_id: None,
+            thumbnail_id: None,
+            category_id: None,
+            origin: None,
+            producer: None,
+            tags: Vec::new(),
+            flavours: Vec::new(),
+        }
+    }
+
+    /// Whether a concurrent scan has created what this restock would create.
+    #[tracing::instrument(skip(executor))]
+    pub async fn is_current<'a, E>(&self, executor: E, bar: &BarModel) -> crate::Result<bool>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            SELECT NOT EXISTS (
+                SELECT 1
+                FROM ingredients i
+                WHERE $1::UUID IS NULL
+                    AND i.user_id = $2
+                    AND i.name = lower($3)
+            ) AND NOT EXISTS (
+                SELECT 1
+                FROM bar_ingredients bi
+                WHERE $4::UUID IS NULL
+                    AND bi.bar_id = $5
+                    AND bi.ingredient_id = $1
+            ) AS "current!"
+            "#,
+            self.ingredient_id,
+            bar.owner,
+            self.product.ingredient,
+            self.bar_ingredient_id,
+            bar.id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    pub fn quantity(&self) -> f32 {
+        self.bottle_size * self.bottles as f32
+    }
+
+    pub fn stock(&self, ingredient_id: Uuid) -> CreateBarIngredientSchema {
+        CreateBarIngredientSchema {
+            ingredient_id,
+            quantity: self.quantity(),
+            unit_id: self.product.unit.id,
+            par: None,
+            minimum: None,
+            bottle_size: Some(self.bottle_size),
+        }
+    }
+
+    pub fn bottle(&self, bar_ingredient_id: Uuid) -> CreateBottleSchema {
+        CreateBottleSchema {
+            bar_ingredient_id,
+            size: Some(self.bottle_size),
+            fill: None,
+            location: None,
+            opened_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ScanModel {
+    pub product: ProductModel,
+    pub ingredient_created: bool,
+    pub bar_ingredient: BarIngredientModel,
+    pub bottles: Vec<BottleModel>,
+}
@@ -0,0 +1,171 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{BarIngredientModel, BarModel, UnitModel};
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct PurchaseModel {
+    pub id: Uuid,
+    pub bar_ingredient_id: Uuid,
+    pub price: f64,
+    pub quantity: f32,
+    pub unit: UnitModel,
+    pub store: Option<String>,
+    pub purchased_at: NaiveDate,
+    pub created_at: NaiveDateTime,
+}
+
+impl PurchaseModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(
+        executor: E,
+        stock: &BarIngredientModel,
+        purchase: CreatePurchaseSchema,
+    ) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            WITH new_purchases AS (
+                INSERT INTO purchases (
+                    price,
+                    quantity,
+                    store,
+                    purchased_at,
+                    bar_ingredient_id,
+                    unit_id
+                ) VALUES (
+                    $1,
+                    $2,
+                    $3,
+                    COALESCE($4, CURRENT_DATE),
+                    $5,
+                    $6
+                ) RETURNING *
+            )
+            SELECT
+                p.purchase_id AS id,
+                p.bar_ingredient_id,
+                p.price,
+                p.quantity,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel",
+                p.store,
+                p.purchased_at,
+                p.created_at
+            FROM new_purchases p
+            JOIN units u USING (unit_id)
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            "#,
+            purchase.price,
+            purchase.quantity,
+            purchase.store,
+            purchase.purchased_at,
+            stock.id,
+            purchase.unit_id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'a, E>(executor: E, stock: &BarIngredientModel) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                p.purchase_id AS id,
+                p.bar_ingredient_id,
+                p.price,
+                p.quantity,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel",
+                p.store,
+                p.purchased_at,
+                p.created_at
+            FROM purchases p
+            JOIN units u USING (unit_id)
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE p.bar_ingredient_id = $1
+            ORDER BY p.purchased_at DESC, p.created_at DESC
+            "#,
+            stock.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn for_bar<'a, E>(executor: E, bar: &BarModel) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                p.purchase_id AS id,
+                p.bar_ingredient_id,
+                p.price,
+                p.quantity,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel",
+                p.store,
+                p.purchased_at,
+                p.created_at
+            FROM purchases p
+            JOIN bar_ingredients bi USING (bar_ingredient_id)
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN units u ON u.unit_id = p.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND bi.bar_id = $2
+            ORDER BY p.purchased_at DESC, p.created_at DESC
+            "#,
+            bar.owner,
+            bar.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreatePurchaseSchema {
+    pub price: f64,
+    pub quantity: f32,
+    #[serde(rename = "unitId")]
+    pub unit_id: Uuid,
+    pub store: Option<String>,
+    #[serde(rename = "purchasedAt")]
+    pub purchased_at: Option<NaiveDate>,
+}
@@ -0,0 +1,348 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{IngredientModel, MediaModel, UnitModel};
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RecipeModel {
+    pub id: Uuid,
+    pub name: String,
+    pub description: String,
+    /// How the drink is mixed: `shaken`, `stirred`, `built` or `blended`.
+    pub method: Option<String>,
+    pub owner: Uuid,
+    pub created_at: NaiveDateTime,
+    pub thumbnail: Option<MediaModel>,
+}
+
+impl RecipeModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(
+        executor: E,
+        owner: Uuid,
+        recipe: CreateRecipeSchema,
+    ) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            WITH new_recipes AS (
+                INSERT INTO recipes (
+                    name,
+                    description,
+                    method,
+                    media_id,
+                    user_id
+                ) VALUES (
+                    lower($1),
+                    $2,
+                    $3,
+                    $4,
+                    $5
+                ) RETURNING *
+            )
+            SELECT
+                r.recipe_id AS id,
+                r.name,
+                r.description,
+                r.method,
+                r.user_id AS owner,
+                r.created_at,
+                CASE
+                    WHEN m.media_id IS NULL THEN NULL
+                    ELSE json_build_object(
+                        'id', m.media_id,
+                        'size', m.size,
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
+                    )
+                END AS "thumbnail: MediaModel"
+            FROM new_recipes r
+            LEFT JOIN media m USING (user_id, media_id)
+            "#,
+            recipe.name,
+            recipe.description,
+            recipe.method,
+            recipe.thumbnail_id,
+            owner
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'a, E>(executor: E, owner: Uuid) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                r.recipe_id AS id,
+                r.name,
+                r.description,
+                r.method,
+                r.user_id AS owner,
+                r.created_at,
+                CASE
+                    WHEN m.media_id IS NULL THEN NULL
+                    ELSE json_build_object(
+                        'id', m.media_id,
+                        'size', m.size,
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
+                    )
+                END AS "thumbnail: MediaModel"
+            FROM recipes r
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE r.user_id = $1
+            "#,
+            owner
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, owner: Uuid, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                r.recipe_id AS id,
+                r.name,
+                r.description,
+                r.method,
+                r.user_id AS owner,
+                r.created_at,
+                CASE
+                    WHEN m.media_id IS NULL THEN NULL
+                    ELSE json_build_object(
+                        'id', m.media_id,
+                        'size', m.size,
+                        'content_type', m.mime_type,
+                        'owner', m.user_id,
+                        'created_at', m.created_at,
+                        'width', m.width,
+                        'height', m.height,
+                        'blurhash', m.blurhash,
+                        'dominant_color', m.dominant_color
+                    )
+                END AS "thumbnail: MediaModel"
+            FROM recipes r
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE r.user_id = $1
+                AND r.recipe_id = $2
+            "#,
+            owner,
+            id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn add_ingredient<'a, E>(
+        &self,
+        executor: E,
+        line: CreateRecipeIngredientSchema,
+    ) -> crate::Result<RecipeIngredientModel>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            RecipeIngredientModel,
+            r#"
+            WITH new_recipe_ingredients AS (
+                INSERT INTO recipe_ingredients (
+                    quantity,
+                    recipe_id,
+                    ingredient_id,
+                    unit_id,
+                    category_id
+                ) VALUES (
+                    $1,
+                    $2,
+                    $3,
+                    $4,
+                    $5
+                ) RETURNING *
+            )
+            SELECT
+                ri.recipe_ingredient_id AS id,
+                ri.quantity,
+                json_build_object(
+                    'id', i.ingredient_id,
+                    'name', i.name,
+                    'description', i.description,
+                    'abv', i.abv,
+                    'sugar', i.sugar,
+                    'acid', i.acid,
+                    'shelf_life_id', i.shelf_life_id,
+                    'category_id', i.category_id,
+                    'origin', i.origin,
+                    'producer', i.producer,
+                    'tags', i.tags,
+                    'flavours', i.flavours,
+                    'owner', i.user_id,
+                    'created_at', i.created_at,
+                    'thumbnail', CASE
+                        WHEN m.media_id IS NULL THEN NULL
+                        ELSE json_build_object(
+                            'id', m.media_id,
+                            'size', m.size,
+                            'content_type', m.mime_type,
+                            'owner', m.user_id,
+                            'created_at', m.created_at,
+                            'width', m.width,
+                            'height', m.height,
+                            'blurhash', m.blurhash,
+                            'dominant_color', m.dominant_color
+                        )
+                    END
+                ) AS "ingredient!: IngredientModel",
+                ri.category_id,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel"
+            FROM new_recipe_ingredients ri
+            JOIN ingredients i ON i.ingredient_id = ri.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = ri.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            "#,
+            line.quantity,
+            self.id,
+            line.ingredient_id,
+            line.unit_id,
+            line.category_id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn ingredients<'a, E>(&self, executor: E) -> crate::Result<Vec<RecipeIngredientModel>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            RecipeIngredientModel,
+            r#"
+            SELECT
+                ri.recipe_ingredient_id AS id,
+                ri.quantity,
+                json_build_object(
+                    'id', i.ingredient_id,
+                    'name', i.name,
+                    'description', i.description,
+                    'abv', i.abv,
+                    'sugar', i.sugar,
+                    'acid', i.acid,
+                    'shelf_life_id', i.shelf_life_id,
+                    'category_id', i.category_id,
+                    'origin', i.origin,
+                    'producer', i.producer,
+                    'tags', i.tags,
+                    'flavours', i.flavours,
+                    'owner', i.user_id,
+                    'created_at', i.created_at,
+                    'thumbnail', CASE
+                        WHEN m.media_id IS NULL THEN NULL
+                        ELSE json_build_object(
+                            'id', m.media_id,
+                            'size', m.size,
+                            'content_type', m.mime_type,
+                            'owner', m.user_id,
+                            'created_at', m.created_at,
+                            'width', m.width,
+                            'height', m.height,
+                            'blurhash', m.blurhash,
+                            'dominant_color', m.dominant_color
+                        )
+                    END
+                ) AS "ingredient!: IngredientModel",
+                ri.category_id,
+                json_build_object(
+                    'id', u.unit_id,
+                    'name', u.name,
+                    'abbreviation', u.abbreviation,
+                    'system', us.name,
+                    'dimension', u.dimension,
+                    'base_quantity', u.base_quantity
+                ) AS "unit!: UnitModel"
+            FROM recipe_ingredients ri
+            JOIN recipes r ON r.recipe_id = ri.recipe_id
+            JOIN ingredients i ON i.ingredient_id = ri.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = ri.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE r.user_id = $1
+                AND ri.recipe_id = $2
+            ORDER BY ri.created_at, ri.recipe_ingredient_id
+            "#,
+            self.owner,
+            self.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateRecipeSchema {
+    pub name: String,
+    pub description: String,
+    pub method: Option<String>,
+    #[serde(rename = "thumbnailId")]
+    pub thumbnail_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct RecipeIngredientModel {
+    pub id: Uuid,
+    pub quantity: f32,
+    pub ingredient: IngredientModel,
+    pub category_id: Option<Uuid>,
+    pub unit: UnitModel,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateRecipeIngredientSchema {
+    #[serde(rename = "ingredientId")]
+    pub ingredient_id: Option<Uuid>,
+    #[serde(rename = "categoryId")]
+    pub category_id: Option<Uuid>,
+    pub quantity: f32,
+    #[serde(rename = "unitId")]
+    pub unit_id: Uuid,
+}
@@ -0,0 +1,264 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{BarModel, UnitModel};
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ServeModel {
+    pub id: Uuid,
+    pub bar_id: Uuid,
+    pub recipe_id: Uuid,
+    pub servings: f32,
+    pub created_at: NaiveDateTime,
+    pub undone_at: Option<NaiveDateTime>,
+    pub deductions: Vec<DeductionModel>,
+}
+
+impl ServeModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(
+        executor: E,
+        bar: &BarModel,
+        serve: &CreateServeSchema,
+    ) -> crate::Result<Uuid>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO serves (
+                servings,
+                bar_id,
+                recipe_id
+            ) VALUES (
+                $1,
+                $2,
+                $3
+            ) RETURNING serve_id
+            "#,
+            serve.servings,
+            bar.id,
+            serve.recipe_id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn deduct<'a, E>(
+        executor: E,
+        id: Uuid,
+        deduction: &CreateDeductionSchema,
+    ) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            WITH new_serve_deductions AS (
+                INSERT INTO serve_deductions (
+                    quantity,
+                    serve_id,
+                    bar_ingredient_id
+                ) VALUES (
+                    $1,
+                    $2,
+                    $3
+                ) RETURNING *
+            )
+            UPDATE bar_ingredients bi
+            SET quantity = bi.quantity - d.quantity
+            FROM new_serve_deductions d
+            WHERE bi.bar_ingredient_id = d.bar_ingredient_id
+            "#,
+            deduction.quantity,
+            id,
+            deduction.bar_ingredient_id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'a, E>(executor: E, bar: &BarModel) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                s.serve_id AS id,
+                s.bar_id,
+                s.recipe_id,
+                s.servings,
+                s.created_at,
+                s.undone_at,
+                COALESCE(
+                    array_agg(
+                        json_build_object(
+                            'bar_ingredient_id', d.bar_ingredient_id,
+                            'ingredient_id', i.ingredient_id,
+                            'name', i.name,
+                            'quantity', d.quantity,
+                            'unit', json_build_object(
+                                'id', u.unit_id,
+                                'name', u.name,
+                                'abbreviation', u.abbreviation,
+                                'system', us.name,
+                                'dimension', u.dimension,
+                                'base_quantity', u.base_quantity
+                            )
+                        )
+                        ORDER BY i.name
+                    ) FILTER (WHERE d.serve_deduction_id IS NOT NULL),
+                    '{}'
+                ) AS "deductions!: Vec<DeductionModel>"
+            FROM serves s
+            JOIN bars b ON b.bar_id = s.bar_id
+            LEFT JOIN serve_deductions d ON d.serve_id = s.serve_id
+            LEFT JOIN bar_ingredients bi ON bi.bar_ingredient_id = d.bar_ingredient_id
+            LEFT JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND s.bar_id = $2
+            GROUP BY s.serve_id
+            ORDER BY s.created_at DESC, s.serve_id
+            "#,
+            bar.owner,
+            bar.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, bar: &BarModel, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                s.serve_id AS id,
+                s.bar_id,
+                s.recipe_id,
+                s.servings,
+                s.created_at,
+                s.undone_at,
+                COALESCE(
+                    array_agg(
+                        json_build_object(
+                            'bar_ingredient_id', d.bar_ingredient_id,
+                            'ingredient_id', i.ingredient_id,
+                            'name', i.name,
+                            'quantity', d.quantity,
+                            'unit', json_build_object(
+                                'id', u.unit_id,
+                                'name', u.name,
+                                'abbreviation', u.abbreviation,
+                                'system', us.name,
+                                'dimension', u.dimension,
+                                'base_quantity', u.base_quantity
+                            )
+                        )
+                        ORDER BY i.name
+                    ) FILTER (WHERE d.serve_deduction_id IS NOT NULL),
+                    '{}'
+                ) AS "deductions!: Vec<DeductionModel>"
+            FROM serves s
+            JOIN bars b ON b.bar_id = s.bar_id
+            LEFT JOIN serve_deductions d ON d.serve_id = s.serve_id
+            LEFT JOIN bar_ingredients bi ON bi.bar_ingredient_id = d.bar_ingredient_id
+            LEFT JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND s.bar_id = $2
+                AND s.serve_id = $3
+            GROUP BY s.serve_id
+            "#,
+            bar.owner,
+            bar.id,
+            id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn mark_undone<'a, E>(&self, executor: E) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            UPDATE serves
+            SET undone_at = now()
+            WHERE serve_id = $1
+                AND undone_at IS NULL
+            RETURNING serve_id
+            "#,
+            self.id,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn restore<'a, E>(&self, executor: E) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            UPDATE bar_ingredients bi
+            SET quantity = bi.quantity + d.quantity
+            FROM serve_deductions d
+            WHERE d.serve_id = $1
+                AND bi.bar_ingredient_id = d.bar_ingredient_id
+            "#,
+            self.id,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateServeSchema {
+    #[serde(rename = "recipeId")]
+    pub recipe_id: Uuid,
+    pub servings: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub(crate) struct DeductionModel {
+    pub bar_ingredient_id: Uuid,
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub quantity: f32,
+    pub unit: UnitModel,
+}
+
+super::json_type!(DeductionModel);
+
+#[derive(Debug)]
+pub(crate) struct CreateDeductionSchema {
+    pub bar_ingredient_id: Uuid,
+    pub quantity: f32,
+}
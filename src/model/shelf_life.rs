@@ -0,0 +1,116 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ShelfLifeModel {
+    pub id: Uuid,
+    pub name: String,
+    pub days: i32,
+    pub owner: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+}
+
+impl ShelfLifeModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(
+        executor: E,
+        owner: Uuid,
+        shelf_life: CreateShelfLifeSchema,
+    ) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            INSERT INTO shelf_lives (
+                name,
+                days,
+                user_id
+            ) VALUES (
+                lower($1),
+                $2,
+                $3
+            ) RETURNING
+                shelf_life_id AS id,
+                name,
+                days,
+                user_id AS owner,
+                created_at
+            "#,
+            shelf_life.name,
+            shelf_life.days,
+            owner,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'a, E>(executor: E, owner: Uuid) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                shelf_life_id AS id,
+                name,
+                days,
+                user_id AS owner,
+                created_at
+            FROM shelf_lives
+            WHERE user_id = $1
+                OR user_id IS NULL
+            ORDER BY name
+            "#,
+            owner,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, owner: Uuid, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                shelf_life_id AS id,
+                name,
+                days,
+                user_id AS owner,
+                created_at
+            FROM shelf_lives
+            WHERE (user_id = $1 OR user_id IS NULL)
+                AND shelf_life_id = $2
+            "#,
+            owner,
+            id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateShelfLifeSchema {
+    pub name: String,
+    pub days: i32,
+}
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct IngredientShelfLifeSchema {
+    #[serde(rename = "shelfLifeId")]
+    pub shelf_life_id: Option<Uuid>,
+}
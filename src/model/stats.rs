@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+
+#[derive(Debug, FromRow, Deserialize, Serialize)]
+pub(crate) struct StatsModel {
+    pub bars: i64,
+    pub ingredients: i64,
+    pub media: i64,
+    pub users: i64,
+}
+
+impl StatsModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM bars) AS "bars!",
+                (SELECT COUNT(*) FROM ingredients) AS "ingredients!",
+                (SELECT COUNT(*) FROM media) AS "media!",
+                (SELECT COUNT(*) FROM users) AS "users!"
+            "#
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+}
@@ -0,0 +1,394 @@
+use axum::http::StatusCode;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::{BarModel, UnitModel};
+use crate::error::Error;
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct StockTakeModel {
+    pub id: Uuid,
+    pub bar_id: Uuid,
+    pub created_at: NaiveDateTime,
+    pub closed_at: Option<NaiveDateTime>,
+    pub applied_at: Option<NaiveDateTime>,
+    pub counts: Vec<StockCountModel>,
+}
+
+impl StockTakeModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn create<'a, E>(executor: E, bar: &BarModel) -> crate::Result<Uuid>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO stock_takes (bar_id)
+            VALUES ($1)
+            RETURNING stock_take_id
+            "#,
+            bar.id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn all<'a, E>(executor: E, bar: &BarModel) -> crate::Result<Vec<Self>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                t.stock_take_id AS id,
+                t.bar_id,
+                t.created_at,
+                t.closed_at,
+                t.applied_at,
+                COALESCE(
+                    array_agg(
+                        json_build_object(
+                            'id', c.stock_count_id,
+                            'bar_ingredient_id', c.bar_ingredient_id,
+                            'ingredient_id', i.ingredient_id,
+                            'name', i.name,
+                            'quantity', c.quantity,
+                            'expected', c.expected,
+                            'unit', json_build_object(
+                                'id', u.unit_id,
+                                'name', u.name,
+                                'abbreviation', u.abbreviation,
+                                'system', us.name,
+                                'dimension', u.dimension,
+                                'base_quantity', u.base_quantity
+                            ),
+                            'counted_at', c.counted_at
+                        )
+                        ORDER BY i.name
+                    ) FILTER (WHERE c.stock_count_id IS NOT NULL),
+                    '{}'
+                ) AS "counts!: Vec<StockCountModel>"
+            FROM stock_takes t
+            JOIN bars b ON b.bar_id = t.bar_id
+            LEFT JOIN stock_counts c ON c.stock_take_id = t.stock_take_id
+            LEFT JOIN bar_ingredients bi ON bi.bar_ingredient_id = c.bar_ingredient_id
+            LEFT JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND t.bar_id = $2
+            GROUP BY t.stock_take_id
+            ORDER BY t.created_at DESC, t.stock_take_id
+            "#,
+            bar.owner,
+            bar.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(executor: E, bar: &BarModel, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                t.stock_take_id AS id,
+                t.bar_id,
+                t.created_at,
+                t.closed_at,
+                t.applied_at,
+                COALESCE(
+                    array_agg(
+                        json_build_object(
+                            'id', c.stock_count_id,
+                            'bar_ingredient_id', c.bar_ingredient_id,
+                            'ingredient_id', i.ingredient_id,
+                            'name', i.name,
+                            'quantity', c.quantity,
+                            'expected', c.expected,
+                            'unit', json_build_object(
+                                'id', u.unit_id,
+                                'name', u.name,
+                                'abbreviation', u.abbreviation,
+                                'system', us.name,
+                                'dimension', u.dimension,
+                                'base_quantity', u.base_quantity
+                            ),
+                            'counted_at', c.counted_at
+                        )
+                        ORDER BY i.name
+                    ) FILTER (WHERE c.stock_count_id IS NOT NULL),
+                    '{}'
+                ) AS "counts!: Vec<StockCountModel>"
+            FROM stock_takes t
+            JOIN bars b ON b.bar_id = t.bar_id
+            LEFT JOIN stock_counts c ON c.stock_take_id = t.stock_take_id
+            LEFT JOIN bar_ingredients bi ON bi.bar_ingredient_id = c.bar_ingredient_id
+            LEFT JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = $1
+                AND t.bar_id = $2
+                AND t.stock_take_id = $3
+            GROUP BY t.stock_take_id
+            "#,
+            bar.owner,
+            bar.id,
+            id,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn count<'a, E>(
+        &self,
+        executor: E,
+        bar_ingredient_id: Uuid,
+        quantity: f32,
+    ) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        // The stock take is locked against closing until the count is in, so
+        // that closing takes what is expected of every count.
+        sqlx::query_scalar!(
+            r#"
+            INSERT INTO stock_counts (
+                quantity,
+                stock_take_id,
+                bar_ingredient_id
+            )
+            SELECT $1, t.stock_take_id, $3
+            FROM stock_takes t
+            WHERE t.stock_take_id = $2
+                AND t.closed_at IS NULL
+            FOR SHARE
+            ON CONFLICT (stock_take_id, bar_ingredient_id) DO UPDATE
+            SET quantity = excluded.quantity,
+                counted_at = now()
+            RETURNING stock_count_id
+            "#,
+            quantity,
+            self.id,
+            bar_ingredient_id,
+        )
+        .fetch_optional(executor)
+        .await?
+        .ok_or_else(|| Error::new(StatusCode::CONFLICT, "stock take has been closed"))?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn lock<'a, E>(&self, executor: E) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            SELECT stock_take_id
+            FROM stock_takes
+            WHERE stock_take_id = $1
+            FOR UPDATE
+            "#,
+            self.id,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn close<'a, E>(&self, executor: E) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_scalar!(
+            r#"
+            WITH closed_stock_takes AS (
+                UPDATE stock_takes
+                SET closed_at = now()
+                WHERE stock_take_id = $1
+                    AND closed_at IS NULL
+                RETURNING stock_take_id
+            ), expected_stock_counts AS (
+                UPDATE stock_counts c
+                SET expected = bi.quantity
+                FROM closed_stock_takes t, bar_ingredients bi
+                WHERE c.stock_take_id = t.stock_take_id
+                    AND bi.bar_ingredient_id = c.bar_ingredient_id
+            )
+            SELECT stock_take_id FROM closed_stock_takes
+            "#,
+            self.id,
+        )
+        .fetch_one(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    /// `None` unless the stock take is closed and neither it nor a later one
+    /// has been applied. Stock is clamped at zero and the clamped lines are
+    /// returned.
+    #[tracing::instrument(skip(executor))]
+    pub async fn apply<'a, E>(&self, executor: E) -> crate::Result<Option<Vec<ClampedCountModel>>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        // Locking the bar ingredients reads their latest quantities, so serves
+        // made while the stock take is applied are not lost.
+        let rows = sqlx::query!(
+            r#"
+            WITH applied_stock_takes AS (
+                UPDATE stock_takes
+                SET applied_at = now()
+                WHERE stock_take_id = $1
+                    AND closed_at IS NOT NULL
+                    AND applied_at IS NULL
+                    AND NOT EXISTS (
+                        SELECT 1
+                        FROM stock_takes later
+                        WHERE later.bar_id = stock_takes.bar_id
+                            AND later.created_at > stock_takes.created_at
+                            AND later.applied_at IS NOT NULL
+                    )
+                RETURNING stock_take_id
+            ), corrections AS (
+                SELECT
+                    bi.bar_ingredient_id,
+                    bi.quantity + c.quantity - c.expected AS quantity
+                FROM applied_stock_takes t
+                JOIN stock_counts c ON c.stock_take_id = t.stock_take_id
+                JOIN bar_ingredients bi ON bi.bar_ingredient_id = c.bar_ingredient_id
+                FOR UPDATE OF bi
+            ), corrected_bar_ingredients AS (
+                UPDATE bar_ingredients bi
+                SET quantity = GREATEST(x.quantity, 0)
+                FROM corrections x
+                WHERE bi.bar_ingredient_id = x.bar_ingredient_id
+            )
+            SELECT
+                x.bar_ingredient_id AS "bar_ingredient_id?",
+                -x.quantity AS "shortfall?"
+            FROM applied_stock_takes t
+            LEFT JOIN corrections x ON x.quantity < 0
+            "#,
+            self.id,
+        )
+        .fetch_all(executor)
+        .await?;
+
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(
+            rows.into_iter()
+                .filter_map(|row| {
+                    Some(ClampedCountModel {
+                        bar_ingredient_id: row.bar_ingredient_id?,
+                        shortfall: row.shortfall?,
+                    })
+                })
+                .collect(),
+        ))
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn usage<'a, E>(&self, executor: E) -> crate::Result<Vec<StockUsageModel>>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            StockUsageModel,
+            r#"
+            SELECT
+                d.bar_ingredient_id,
+                SUM(d.quantity)::DOUBLE PRECISION AS "quantity!"
+            FROM serve_deductions d
+            JOIN serves s ON s.serve_id = d.serve_id
+            JOIN stock_takes t ON t.bar_id = s.bar_id
+            WHERE t.stock_take_id = $1
+                AND s.undone_at IS NULL
+                AND s.created_at <= COALESCE(t.closed_at, now())
+                AND s.created_at > COALESCE(
+                    (
+                        SELECT MAX(p.closed_at)
+                        FROM stock_takes p
+                        WHERE p.bar_id = t.bar_id
+                            AND p.applied_at IS NOT NULL
+                            AND p.closed_at <= t.created_at
+                    ),
+                    '-infinity'
+                )
+            GROUP BY d.bar_ingredient_id
+            "#,
+            self.id,
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub(crate) struct AppliedStockTakeModel {
+    #[serde(flatten)]
+    pub stock_take: StockTakeModel,
+    pub clamped: Vec<ClampedCountModel>,
+}
+
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct ClampedCountModel {
+    pub bar_ingredient_id: Uuid,
+    pub shortfall: f32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub(crate) struct StockCountModel {
+    pub id: Uuid,
+    pub bar_ingredient_id: Uuid,
+    pub ingredient_id: Uuid,
+    pub name: String,
+    pub quantity: f32,
+    pub expected: Option<f32>,
+    pub unit: UnitModel,
+    pub counted_at: NaiveDateTime,
+}
+
+super::json_type!(StockCountModel);
+
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub(crate) struct CreateStockCountSchema {
+    #[serde(rename = "barIngredientId")]
+    pub bar_ingredient_id: Uuid,
+    pub bottles: Option<f32>,
+    pub quantity: Option<f32>,
+    #[serde(rename = "unitId")]
+    pub unit_id: Option<Uuid>,
+    pub weight: Option<f32>,
+    #[serde(rename = "tareWeight")]
+    pub tare_weight: Option<f32>,
+    pub density: Option<f32>,
+}
+
+#[derive(Debug, FromRow)]
+pub(crate) struct StockUsageModel {
+    pub bar_ingredient_id: Uuid,
+    pub quantity: f64,
+}
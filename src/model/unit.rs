@@ -3,16 +3,21 @@ use sqlx::{Executor, FromRow, Postgres};
 use utoipa::ToSchema;
 use uuid::Uuid;
 
-#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+#[derive(Debug, Clone, FromRow, Deserialize, Serialize, ToSchema)]
 pub(crate) struct UnitModel {
-    id: Uuid,
-    name: String,
-    abbreviation: String,
+    pub id: Uuid,
+    pub name: String,
+    pub abbreviation: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    system: Option<String>,
+    pub system: Option<String>,
+    pub dimension: String,
+    pub base_quantity: f64,
 }
 
+super::json_type!(UnitModel);
+
 impl UnitModel {
+    #[tracing::instrument(skip(executor))]
     pub async fn all<'a, E>(executor: E) -> crate::Result<Vec<Self>>
     where
         E: Executor<'a, Database = Postgres>,
@@ -24,7 +29,9 @@ impl UnitModel {
                 u.unit_id AS id,
                 u.name,
                 u.abbreviation,
-                us.name AS system
+                us.name AS system,
+                u.dimension,
+                u.base_quantity
             FROM units u
             LEFT JOIN unit_systems us USING (unit_system_id)
             "#,
@@ -0,0 +1,105 @@
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::{Executor, FromRow, Postgres};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{error::Error, Limits};
+
+#[derive(Debug, FromRow, Deserialize, Serialize, ToSchema)]
+pub(crate) struct UsageModel {
+    pub used: i64,
+    pub quota: i64,
+    pub remaining: i64,
+    pub media_count: i64,
+    pub recent_uploads: i64,
+}
+
+impl UsageModel {
+    #[tracing::instrument(skip(executor))]
+    pub async fn retrieve<'a, E>(
+        executor: E,
+        owner: Uuid,
+        default_quota: i64,
+    ) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                COALESCE(SUM(m.size), 0)::BIGINT AS "used!",
+                COALESCE(u.storage_quota, $2) AS "quota!",
+                GREATEST(
+                    COALESCE(u.storage_quota, $2) - COALESCE(SUM(m.size), 0),
+                    0
+                )::BIGINT AS "remaining!",
+                COUNT(m.media_id) AS "media_count!",
+                (
+                    SELECT COUNT(*)
+                    FROM uploads up
+                    WHERE up.user_id = u.user_id
+                        AND up.created_at > now() - INTERVAL '1 hour'
+                ) AS "recent_uploads!"
+            FROM users u
+            LEFT JOIN media m USING (user_id)
+            WHERE u.user_id = $1
+            GROUP BY u.user_id
+            "#,
+            owner,
+            default_quota,
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(executor))]
+    pub async fn record_upload<'a, E>(executor: E, owner: Uuid, size: i64) -> crate::Result<()>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query!(
+            r#"
+            INSERT INTO uploads (
+                size,
+                user_id
+            ) VALUES (
+                $1,
+                $2
+            )
+            "#,
+            size,
+            owner,
+        )
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+
+    pub fn check_upload(&self, size: i64, limits: &Limits) -> crate::Result<()> {
+        if self.recent_uploads >= limits.uploads_per_hour {
+            return Err(Error::with_details(
+                StatusCode::TOO_MANY_REQUESTS,
+                "upload rate limit exceeded",
+                json!({ "limit": limits.uploads_per_hour, "window": 3600 }),
+            ));
+        }
+        if size > self.remaining {
+            return Err(Error::with_details(
+                StatusCode::INSUFFICIENT_STORAGE,
+                "storage quota exceeded",
+                json!({
+                    "quota": self.quota,
+                    "used": self.used,
+                    "remaining": self.remaining,
+                }),
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@ pub(crate) struct UserModel {
 }
 
 impl UserModel {
+    #[tracing::instrument(skip(executor))]
     pub async fn create<'a, E>(executor: E) -> crate::Result<Self>
     where
         E: Executor<'a, Database = Postgres>,
@@ -31,6 +32,7 @@ impl UserModel {
         .map_err(|e| e.into())
     }
 
+    #[tracing::instrument(skip(executor))]
     pub async fn retrieve<'a, E>(executor: E, id: Uuid) -> crate::Result<Self>
     where
         E: Executor<'a, Database = Postgres>,
@@ -50,4 +52,27 @@ impl UserModel {
         .await
         .map_err(|e| e.into())
     }
+
+    /// Serializes the user's changes that depend on what they already have.
+    #[tracing::instrument(skip(executor))]
+    pub async fn lock<'a, E>(executor: E, id: Uuid) -> crate::Result<Self>
+    where
+        E: Executor<'a, Database = Postgres>,
+    {
+        sqlx::query_as!(
+            Self,
+            r#"
+            SELECT
+                user_id AS id,
+                created_at
+            FROM users
+            WHERE user_id = $1
+            FOR UPDATE
+            "#,
+            id
+        )
+        .fetch_one(executor)
+        .await
+        .map_err(|e| e.into())
+    }
 }
@@ -0,0 +1,167 @@
+use std::{
+    future::Future,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response,
+};
+use metrics::{
+    counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Unit,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tokio::sync::Mutex;
+
+use crate::repository::Repository;
+
+pub(crate) const HTTP_REQUESTS: &str = "tapster_http_requests_total";
+pub(crate) const HTTP_REQUEST_DURATION: &str = "tapster_http_request_duration_seconds";
+pub(crate) const ERRORS: &str = "tapster_errors_total";
+pub(crate) const S3_REQUEST_DURATION: &str = "tapster_s3_request_duration_seconds";
+pub(crate) const UPLOADS: &str = "tapster_uploads_total";
+pub(crate) const UPLOAD_BYTES: &str = "tapster_upload_bytes_total";
+pub(crate) const DB_POOL_CONNECTIONS: &str = "tapster_db_pool_connections";
+pub(crate) const DB_POOL_MAX_CONNECTIONS: &str = "tapster_db_pool_max_connections";
+pub(crate) const BARS: &str = "tapster_bars";
+pub(crate) const INGREDIENTS: &str = "tapster_ingredients";
+pub(crate) const MEDIA: &str = "tapster_media";
+pub(crate) const USERS: &str = "tapster_users";
+
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Clone)]
+pub(crate) struct Metrics(PrometheusHandle);
+
+impl Metrics {
+    pub fn install() -> Self {
+        static HANDLE: OnceLock<PrometheusHandle> = OnceLock::new();
+
+        let handle = HANDLE.get_or_init(|| {
+            let handle = PrometheusBuilder::new()
+                .set_buckets_for_metric(Matcher::Suffix("_seconds".to_string()), LATENCY_BUCKETS)
+                .expect("latency buckets are not empty")
+                .install_recorder()
+                .expect("failed to install metrics recorder");
+            describe();
+
+            handle
+        });
+
+        Self(handle.clone())
+    }
+
+    pub fn render(&self) -> String {
+        self.0.run_upkeep();
+        self.0.render()
+    }
+}
+
+pub(crate) struct StatsGauges {
+    interval: Duration,
+    counted_at: Mutex<Option<Instant>>,
+}
+
+impl StatsGauges {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            counted_at: Mutex::new(None),
+        }
+    }
+
+    /// Concurrent scrapes wait for one count rather than each starting their
+    /// own.
+    pub async fn refresh(&self, repo: &dyn Repository) {
+        let mut counted_at = self.counted_at.lock().await;
+        if counted_at.is_some_and(|at| at.elapsed() < self.interval) {
+            return;
+        }
+
+        // A database outage should not take the rest of the metrics with it.
+        match repo.stats().await {
+            Ok(stats) => {
+                gauge!(BARS).set(stats.bars as f64);
+                gauge!(INGREDIENTS).set(stats.ingredients as f64);
+                gauge!(MEDIA).set(stats.media as f64);
+                gauge!(USERS).set(stats.users as f64);
+                *counted_at = Some(Instant::now());
+            }
+            Err(e) => tracing::warn!(error = %e, "failed to collect domain metrics"),
+        }
+    }
+}
+
+fn describe() {
+    describe_counter!(HTTP_REQUESTS, "HTTP requests by method, route and status");
+    describe_histogram!(
+        HTTP_REQUEST_DURATION,
+        Unit::Seconds,
+        "Time taken to respond to HTTP requests, by method and route"
+    );
+    describe_counter!(ERRORS, "Error responses by status");
+    describe_histogram!(
+        S3_REQUEST_DURATION,
+        Unit::Seconds,
+        "Latency of object storage calls, by operation and outcome"
+    );
+    describe_counter!(
+        UPLOADS,
+        "Media uploads, by whether the bytes were already stored"
+    );
+    describe_counter!(UPLOAD_BYTES, Unit::Bytes, "Bytes received in media uploads");
+    describe_gauge!(
+        DB_POOL_CONNECTIONS,
+        "Open database connections, by whether they are idle or in use"
+    );
+    describe_gauge!(DB_POOL_MAX_CONNECTIONS, "Size limit of the database pool");
+    describe_gauge!(BARS, "Bars across all users");
+    describe_gauge!(INGREDIENTS, "Ingredients across all users");
+    describe_gauge!(MEDIA, "Media across all users");
+    describe_gauge!(USERS, "Registered users");
+}
+
+/// Labelled by matched route rather than raw path to bound the number of
+/// series.
+pub(crate) async fn track_requests(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    counter!(
+        HTTP_REQUESTS,
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => status,
+    )
+    .increment(1);
+    histogram!(HTTP_REQUEST_DURATION, "method" => method, "route" => route)
+        .record(start.elapsed().as_secs_f64());
+
+    response
+}
+
+pub(crate) async fn observe_s3<T, E>(
+    operation: &'static str,
+    request: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    let start = Instant::now();
+    let result = request.await;
+    let outcome = if result.is_ok() { "ok" } else { "error" };
+
+    histogram!(S3_REQUEST_DURATION, "operation" => operation, "outcome" => outcome)
+        .record(start.elapsed().as_secs_f64());
+
+    result
+}
@@ -0,0 +1,106 @@
+use std::{collections::HashMap, path::Path};
+
+use image::{imageops::FilterType, GenericImageView, Limits};
+
+const COMPONENTS_X: u32 = 4;
+const COMPONENTS_Y: u32 = 3;
+
+const SAMPLE_SIZE: u32 = 32;
+
+/// Caps on what decoding an upload may take, so a small, highly compressed
+/// image cannot claim gigabytes once decoded.
+const MAX_DIMENSION: u32 = 8192;
+const MAX_ALLOC: u64 = 128 * 1024 * 1024;
+
+#[derive(Debug, Clone)]
+pub(crate) struct Placeholder {
+    pub width: i32,
+    pub height: i32,
+    pub blurhash: String,
+    pub dominant_color: String,
+}
+
+impl Placeholder {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let mut reader = image::ImageReader::open(path)
+            .ok()?
+            .with_guessed_format()
+            .ok()?;
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(MAX_DIMENSION);
+        limits.max_image_height = Some(MAX_DIMENSION);
+        limits.max_alloc = Some(MAX_ALLOC);
+        reader.limits(limits);
+        let image = reader.decode().ok()?;
+        let (width, height) = image.dimensions();
+
+        let sample = image
+            .resize(SAMPLE_SIZE, SAMPLE_SIZE, FilterType::Triangle)
+            .to_rgba8();
+        let blurhash = blurhash::encode(
+            COMPONENTS_X,
+            COMPONENTS_Y,
+            sample.width(),
+            sample.height(),
+            sample.as_raw(),
+        )
+        .ok()?;
+
+        Some(Self {
+            width: width.try_into().ok()?,
+            height: height.try_into().ok()?,
+            blurhash,
+            dominant_color: dominant_color(sample.pixels().map(|p| p.0)),
+        })
+    }
+}
+
+fn dominant_color(pixels: impl Iterator<Item = [u8; 4]>) -> String {
+    let mut buckets: HashMap<[u8; 3], (u32, [u32; 3])> = HashMap::new();
+
+    for [r, g, b, a] in pixels {
+        if a < 128 {
+            continue;
+        }
+
+        let (count, sum) = buckets.entry([r >> 4, g >> 4, b >> 4]).or_default();
+        *count += 1;
+        sum[0] += r as u32;
+        sum[1] += g as u32;
+        sum[2] += b as u32;
+    }
+
+    // Ties are broken by the bucket itself, or hash order would pick one at
+    // random.
+    let [r, g, b] = buckets
+        .into_iter()
+        .max_by_key(|(bucket, (count, _))| (*count, *bucket))
+        .map(|(_, (count, sum))| sum.map(|c| c / count))
+        .unwrap_or_default();
+
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breaks_ties_between_buckets_the_same_way() {
+        for _ in 0..20 {
+            let pixels = [[255, 0, 0, 255], [0, 0, 255, 255]];
+            assert_eq!(dominant_color(pixels.into_iter()), "#ff0000");
+        }
+    }
+
+    #[test]
+    fn refuses_images_beyond_the_limits() {
+        let path = std::env::temp_dir().join(format!("{}.png", uuid::Uuid::new_v4()));
+        image::RgbaImage::new(MAX_DIMENSION + 1, 1)
+            .save(&path)
+            .unwrap();
+        let placeholder = Placeholder::from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(placeholder.is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{Auth, Error, Quota, RateLimitConfig};
+
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Client {
+    Ip(IpAddr),
+    User(Uuid),
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    quota: Quota,
+    buckets: Mutex<HashMap<Client, Bucket>>,
+    signing_key: Option<String>,
+    trust_forwarded_for: bool,
+    trusted_proxies: usize,
+}
+
+impl RateLimiter {
+    pub fn per_ip(quota: Quota, config: &RateLimitConfig) -> Arc<Self> {
+        Self::new(quota, config, None)
+    }
+
+    pub fn per_user(quota: Quota, config: &RateLimitConfig, signing_key: &str) -> Arc<Self> {
+        Self::new(quota, config, Some(signing_key.to_string()))
+    }
+
+    fn new(quota: Quota, config: &RateLimitConfig, signing_key: Option<String>) -> Arc<Self> {
+        Arc::new(Self {
+            quota,
+            buckets: Mutex::default(),
+            signing_key,
+            trust_forwarded_for: config.trust_forwarded_for,
+            trusted_proxies: config.trusted_proxies,
+        })
+    }
+
+    fn client(&self, request: &Request) -> Option<Client> {
+        if let Some(signing_key) = &self.signing_key
+            && let Ok(user) = Auth::decode_header(signing_key, request.headers().clone())
+        {
+            return Some(Client::User(user));
+        }
+
+        // Each proxy appends the address it got the request from, so only the
+        // entries on the right are ones our proxies vouch for.
+        let forwarded = self
+            .trust_forwarded_for
+            .then(|| request.headers().get("x-forwarded-for"))
+            .flatten()
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| {
+                let entries: Vec<&str> = value.split(',').collect();
+                entries
+                    .get(entries.len().saturating_sub(self.trusted_proxies))
+                    .and_then(|ip| ip.trim().parse().ok())
+            });
+        let peer = request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|info| info.0.ip());
+
+        forwarded.or(peer).map(Client::Ip)
+    }
+
+    fn acquire(&self, client: Client) -> Result<(), Duration> {
+        let now = Instant::now();
+        let rate = self.quota.per_minute as f64 / 60.0;
+        let burst = self.quota.burst as f64;
+        let refill = |bucket: &Bucket| {
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            (bucket.tokens + elapsed * rate).min(burst)
+        };
+
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+        if buckets.len() >= PRUNE_THRESHOLD {
+            buckets.retain(|_, bucket| refill(bucket) < burst);
+        }
+
+        let bucket = buckets.entry(client).or_insert(Bucket {
+            tokens: burst,
+            updated: now,
+        });
+        bucket.tokens = refill(bucket);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate))
+        }
+    }
+}
+
+pub(crate) async fn rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Counting requests without an address against one shared bucket would
+    // let anyone lock everyone else out.
+    let Some(client) = limiter.client(&request) else {
+        tracing::error!("rate limited request has no client address");
+        return Error::new(StatusCode::INTERNAL_SERVER_ERROR, "client address unknown")
+            .into_response();
+    };
+
+    match limiter.acquire(client) {
+        Ok(()) => next.run(request).await,
+        Err(wait) => {
+            let retry_after = wait.as_secs_f64().ceil() as u64;
+            tracing::debug!(?client, retry_after, "rate limited");
+
+            let mut response = Error::with_details(
+                StatusCode::TOO_MANY_REQUESTS,
+                "rate limit exceeded",
+                json!({
+                    "limit": limiter.quota.per_minute,
+                    "burst": limiter.quota.burst,
+                    "window": 60,
+                    "retryAfter": retry_after,
+                }),
+            )
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+
+            response
+        }
+    }
+}
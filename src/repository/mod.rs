@@ -0,0 +1,391 @@
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc};
+
+use async_trait::async_trait;
+use sqlx::migrate::{MigrateError, Migrator};
+use uuid::Uuid;
+
+use crate::{
+    model::{
+        AppliedStockTakeModel, BarIngredientLevelsSchema, BarIngredientModel, BarModel, BlobModel,
+        BottleModel, CategoryModel, CreateBarIngredientSchema, CreateBarSchema, CreateBottleSchema,
+        CreateCategorySchema, CreateDeductionSchema, CreateIngredientSchema, CreateMediaSchema,
+        CreateProductSchema, CreatePurchaseSchema, CreateRecipeIngredientSchema,
+        CreateRecipeSchema, CreateServeSchema, CreateShelfLifeSchema, CreateSubIngredientSchema,
+        IngredientAttributesSchema, IngredientModel, IngredientPartModel, MediaLibraryModel,
+        MediaModel, ProductModel, PurchaseModel, RecipeIngredientModel, RecipeModel, RestockSchema,
+        ScanModel, ServeModel, ShelfLifeModel, StatsModel, StockTakeModel, StockUsageModel,
+        SubIngredientModel, UnitModel, UpdateBottleSchema, UsageModel, UserModel,
+    },
+    DatabaseConfig, ImportError, Limits, ProductRecord,
+};
+
+pub(crate) use postgres::*;
+pub(crate) use sqlite::*;
+
+mod postgres;
+mod sqlite;
+
+pub(crate) type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Runs inside the transaction that records the blob, so a failed write leaves
+/// nothing behind.
+pub(crate) type StoreBlob<'a> =
+    Box<dyn FnOnce(String) -> BoxFuture<'a, crate::Result<()>> + Send + 'a>;
+
+/// Runs while the blob's row is locked, so an upload of the same bytes waits
+/// rather than losing its object.
+pub(crate) type RemoveBlob<'a> =
+    Box<dyn FnOnce(String) -> BoxFuture<'a, crate::Result<()>> + Send + 'a>;
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PoolStatus {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+#[derive(Debug)]
+pub(crate) struct DeletedMediaModel {
+    pub media: Vec<MediaModel>,
+    pub orphaned: Vec<Uuid>,
+}
+
+/// Every query that takes an `owner` only sees that user's rows.
+#[async_trait]
+pub(crate) trait Repository: Send + Sync {
+    fn migrator(&self) -> &'static Migrator;
+
+    async fn run_migrations(&self) -> Result<(), MigrateError>;
+
+    async fn undo_migrations(&self, target: i64) -> Result<(), MigrateError>;
+
+    async fn applied_migrations(&self) -> Result<HashMap<i64, Vec<u8>>, MigrateError>;
+
+    async fn ping(&self) -> crate::Result<()>;
+
+    fn pool_status(&self) -> PoolStatus;
+
+    async fn close(&self);
+
+    async fn create_user(&self) -> crate::Result<UserModel>;
+
+    async fn retrieve_user(&self, id: Uuid) -> crate::Result<UserModel>;
+
+    async fn usage(&self, owner: Uuid, default_quota: i64) -> crate::Result<UsageModel>;
+
+    async fn stats(&self) -> crate::Result<StatsModel>;
+
+    async fn list_units(&self) -> crate::Result<Vec<UnitModel>>;
+
+    async fn create_bar(&self, owner: Uuid, bar: CreateBarSchema) -> crate::Result<BarModel>;
+
+    async fn list_bars(&self, owner: Uuid) -> crate::Result<Vec<BarModel>>;
+
+    async fn retrieve_bar(&self, owner: Uuid, id: Uuid) -> crate::Result<BarModel>;
+
+    async fn add_bar_ingredient(
+        &self,
+        bar: &BarModel,
+        stock: CreateBarIngredientSchema,
+    ) -> crate::Result<BarIngredientModel>;
+
+    async fn bar_ingredients(&self, bar: &BarModel) -> crate::Result<Vec<BarIngredientModel>>;
+
+    async fn retrieve_bar_ingredient(
+        &self,
+        bar: &BarModel,
+        id: Uuid,
+    ) -> crate::Result<BarIngredientModel>;
+
+    async fn set_bar_ingredient_levels(
+        &self,
+        bar: &BarModel,
+        id: Uuid,
+        levels: BarIngredientLevelsSchema,
+    ) -> crate::Result<BarIngredientModel>;
+
+    async fn create_purchase(
+        &self,
+        stock: &BarIngredientModel,
+        purchase: CreatePurchaseSchema,
+    ) -> crate::Result<PurchaseModel>;
+
+    async fn list_purchases(&self, stock: &BarIngredientModel)
+        -> crate::Result<Vec<PurchaseModel>>;
+
+    async fn bar_purchases(&self, bar: &BarModel) -> crate::Result<Vec<PurchaseModel>>;
+
+    async fn create_serve(
+        &self,
+        bar: &BarModel,
+        serve: CreateServeSchema,
+        deductions: Vec<CreateDeductionSchema>,
+    ) -> crate::Result<ServeModel>;
+
+    async fn list_serves(&self, bar: &BarModel) -> crate::Result<Vec<ServeModel>>;
+
+    async fn retrieve_serve(&self, bar: &BarModel, id: Uuid) -> crate::Result<ServeModel>;
+
+    async fn undo_serve(&self, bar: &BarModel, serve: &ServeModel) -> crate::Result<ServeModel>;
+
+    async fn create_stock_take(&self, bar: &BarModel) -> crate::Result<StockTakeModel>;
+
+    async fn list_stock_takes(&self, bar: &BarModel) -> crate::Result<Vec<StockTakeModel>>;
+
+    async fn retrieve_stock_take(&self, bar: &BarModel, id: Uuid) -> crate::Result<StockTakeModel>;
+
+    async fn count_stock(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+        bar_ingredient_id: Uuid,
+        quantity: f32,
+    ) -> crate::Result<StockTakeModel>;
+
+    async fn close_stock_take(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<StockTakeModel>;
+
+    async fn apply_stock_take(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<Option<AppliedStockTakeModel>>;
+
+    async fn stock_take_usage(
+        &self,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<Vec<StockUsageModel>>;
+
+    async fn create_bottle(
+        &self,
+        bar: &BarModel,
+        bottle: CreateBottleSchema,
+    ) -> crate::Result<BottleModel>;
+
+    async fn list_bottles(&self, bar: &BarModel) -> crate::Result<Vec<BottleModel>>;
+
+    async fn retrieve_bottle(&self, bar: &BarModel, id: Uuid) -> crate::Result<BottleModel>;
+
+    async fn expiring_bottles(&self, bar: &BarModel, days: i32) -> crate::Result<Vec<BottleModel>>;
+
+    async fn update_bottle(
+        &self,
+        bar: &BarModel,
+        bottle: &BottleModel,
+        update: UpdateBottleSchema,
+    ) -> crate::Result<BottleModel>;
+
+    async fn delete_bottle(&self, bottle: &BottleModel) -> crate::Result<()>;
+
+    /// `None` when a concurrent scan created what `restock` would, so it can be
+    /// worked out again.
+    async fn scan_product(
+        &self,
+        bar: &BarModel,
+        restock: RestockSchema,
+    ) -> crate::Result<Option<ScanModel>>;
+
+    async fn create_ingredient(
+        &self,
+        owner: Uuid,
+        ingredient: CreateIngredientSchema,
+    ) -> crate::Result<IngredientModel>;
+
+    async fn list_ingredients(&self, owner: Uuid) -> crate::Result<Vec<IngredientModel>>;
+
+    async fn retrieve_ingredient(&self, owner: Uuid, id: Uuid) -> crate::Result<IngredientModel>;
+
+    async fn sub_ingredients(
+        &self,
+        ingredient: IngredientModel,
+    ) -> crate::Result<Vec<SubIngredientModel>>;
+
+    async fn fork_ingredient(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+    ) -> crate::Result<IngredientModel>;
+
+    async fn add_sub_ingredient(
+        &self,
+        ingredient: &IngredientModel,
+        part: CreateSubIngredientSchema,
+    ) -> crate::Result<SubIngredientModel>;
+
+    async fn ingredient_parts(&self, owner: Uuid) -> crate::Result<Vec<IngredientPartModel>>;
+
+    async fn create_shelf_life(
+        &self,
+        owner: Uuid,
+        shelf_life: CreateShelfLifeSchema,
+    ) -> crate::Result<ShelfLifeModel>;
+
+    async fn list_shelf_lives(&self, owner: Uuid) -> crate::Result<Vec<ShelfLifeModel>>;
+
+    async fn retrieve_shelf_life(&self, owner: Uuid, id: Uuid) -> crate::Result<ShelfLifeModel>;
+
+    async fn set_ingredient_shelf_life(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+        shelf_life_id: Option<Uuid>,
+    ) -> crate::Result<IngredientModel>;
+
+    async fn set_ingredient_attributes(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+        attributes: IngredientAttributesSchema,
+    ) -> crate::Result<IngredientModel>;
+
+    async fn create_category(
+        &self,
+        owner: Uuid,
+        category: CreateCategorySchema,
+    ) -> crate::Result<CategoryModel>;
+
+    async fn list_categories(&self, owner: Uuid) -> crate::Result<Vec<CategoryModel>>;
+
+    async fn retrieve_category(&self, owner: Uuid, id: Uuid) -> crate::Result<CategoryModel>;
+
+    async fn retrieve_product(&self, barcode: &str) -> crate::Result<ProductModel>;
+
+    async fn import_products(&self, products: Vec<CreateProductSchema>) -> crate::Result<()>;
+
+    async fn create_recipe(
+        &self,
+        owner: Uuid,
+        recipe: CreateRecipeSchema,
+    ) -> crate::Result<RecipeModel>;
+
+    async fn list_recipes(&self, owner: Uuid) -> crate::Result<Vec<RecipeModel>>;
+
+    async fn retrieve_recipe(&self, owner: Uuid, id: Uuid) -> crate::Result<RecipeModel>;
+
+    async fn add_recipe_ingredient(
+        &self,
+        recipe: &RecipeModel,
+        line: CreateRecipeIngredientSchema,
+    ) -> crate::Result<RecipeIngredientModel>;
+
+    async fn recipe_ingredients(
+        &self,
+        recipe: &RecipeModel,
+    ) -> crate::Result<Vec<RecipeIngredientModel>>;
+
+    async fn create_media<'a>(
+        &self,
+        owner: Uuid,
+        hash: &str,
+        media: CreateMediaSchema,
+        limits: &Limits,
+        store: StoreBlob<'a>,
+    ) -> crate::Result<MediaModel>;
+
+    async fn retrieve_media(&self, owner: Uuid, id: Uuid) -> crate::Result<MediaModel>;
+
+    async fn retrieve_blob(&self, id: Uuid) -> crate::Result<BlobModel>;
+
+    async fn media_library(
+        &self,
+        owner: Uuid,
+        content_type: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> crate::Result<Vec<MediaLibraryModel>>;
+
+    async fn count_media(&self, owner: Uuid, content_type: Option<&str>) -> crate::Result<i64>;
+
+    async fn delete_media(&self, owner: Uuid, id: Uuid) -> crate::Result<Vec<Uuid>>;
+
+    async fn delete_media_list(
+        &self,
+        owner: Uuid,
+        ids: &[Uuid],
+    ) -> crate::Result<DeletedMediaModel>;
+
+    async fn delete_unused_media(&self, owner: Uuid) -> crate::Result<DeletedMediaModel>;
+
+    async fn remove_blob<'a>(&self, id: Uuid, remove: RemoveBlob<'a>) -> crate::Result<()>;
+}
+
+#[derive(Clone)]
+pub struct Database {
+    pub(crate) repo: Arc<dyn Repository>,
+}
+
+impl Database {
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let repo: Arc<dyn Repository> = if config.is_sqlite() {
+            Arc::new(SqliteRepository::connect(config).await?)
+        } else {
+            Arc::new(PostgresRepository::connect(config).await?)
+        };
+
+        Ok(Self { repo })
+    }
+
+    pub fn migrator(&self) -> &'static Migrator {
+        self.repo.migrator()
+    }
+
+    pub async fn run_migrations(&self) -> Result<(), MigrateError> {
+        self.repo.run_migrations().await
+    }
+
+    pub async fn undo_migrations(&self, target: i64) -> Result<(), MigrateError> {
+        self.repo.undo_migrations(target).await
+    }
+
+    pub async fn applied_migrations(&self) -> Result<HashMap<i64, Vec<u8>>, MigrateError> {
+        self.repo.applied_migrations().await
+    }
+
+    pub async fn close(&self) {
+        self.repo.close().await
+    }
+
+    pub async fn import_products(
+        &self,
+        products: Vec<ProductRecord>,
+    ) -> Result<usize, ImportError> {
+        let units = self
+            .repo
+            .list_units()
+            .await
+            .map_err(|e| ImportError::Database(e.to_string()))?;
+        let count = products.len();
+        let products = products
+            .into_iter()
+            .enumerate()
+            .map(|(i, product)| {
+                let unit = units
+                    .iter()
+                    .find(|u| u.abbreviation == product.unit)
+                    .ok_or_else(|| ImportError::Record {
+                        record: i + 1,
+                        message: format!("unknown unit {}", product.unit),
+                    })?;
+
+                Ok(CreateProductSchema {
+                    barcode: product.barcode,
+                    name: product.name,
+                    ingredient: product.ingredient,
+                    size: product.size,
+                    abv: product.abv,
+                    unit_id: unit.id,
+                })
+            })
+            .collect::<Result<Vec<_>, ImportError>>()?;
+
+        self.repo
+            .import_products(products)
+            .await
+            .map_err(|e| ImportError::Database(e.to_string()))?;
+
+        Ok(count)
+    }
+}
@@ -0,0 +1,618 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    postgres::PgPoolOptions,
+    PgConnection, Pool, Postgres,
+};
+use uuid::Uuid;
+
+use super::{DeletedMediaModel, PoolStatus, RemoveBlob, Repository, StoreBlob};
+use crate::{
+    model::{
+        AppliedStockTakeModel, BarIngredientLevelsSchema, BarIngredientModel, BarModel, BlobModel,
+        BottleModel, CategoryModel, CreateBarIngredientSchema, CreateBarSchema, CreateBottleSchema,
+        CreateCategorySchema, CreateDeductionSchema, CreateIngredientSchema, CreateMediaSchema,
+        CreateProductSchema, CreatePurchaseSchema, CreateRecipeIngredientSchema,
+        CreateRecipeSchema, CreateServeSchema, CreateShelfLifeSchema, CreateSubIngredientSchema,
+        IngredientAttributesSchema, IngredientModel, IngredientPartModel, MediaLibraryModel,
+        MediaModel, ProductModel, PurchaseModel, RecipeIngredientModel, RecipeModel, RestockSchema,
+        ScanModel, ServeModel, ShelfLifeModel, StatsModel, StockTakeModel, StockUsageModel,
+        SubIngredientModel, UnitModel, UpdateBottleSchema, UsageModel, UserModel,
+    },
+    DatabaseConfig, Limits, MIGRATOR,
+};
+
+pub(crate) struct PostgresRepository {
+    pool: Pool<Postgres>,
+}
+
+impl PostgresRepository {
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let pool = PgPoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect(&config.url)
+            .await?;
+
+        Ok(Self::new(pool))
+    }
+
+    pub fn new(pool: Pool<Postgres>) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl Repository for PostgresRepository {
+    fn migrator(&self) -> &'static Migrator {
+        &MIGRATOR
+    }
+
+    async fn run_migrations(&self) -> Result<(), MigrateError> {
+        MIGRATOR.run(&self.pool).await
+    }
+
+    async fn undo_migrations(&self, target: i64) -> Result<(), MigrateError> {
+        MIGRATOR.undo(&self.pool, target).await
+    }
+
+    async fn applied_migrations(&self) -> Result<HashMap<i64, Vec<u8>>, MigrateError> {
+        let mut conn = self.pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+
+        Ok(conn
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|m| (m.version, m.checksum.into_owned()))
+            .collect())
+    }
+
+    async fn ping(&self) -> crate::Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    fn pool_status(&self) -> PoolStatus {
+        PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max: self.pool.options().get_max_connections(),
+        }
+    }
+
+    async fn close(&self) {
+        self.pool.close().await
+    }
+
+    async fn create_user(&self) -> crate::Result<UserModel> {
+        UserModel::create(&self.pool).await
+    }
+
+    async fn retrieve_user(&self, id: Uuid) -> crate::Result<UserModel> {
+        UserModel::retrieve(&self.pool, id).await
+    }
+
+    async fn usage(&self, owner: Uuid, default_quota: i64) -> crate::Result<UsageModel> {
+        UsageModel::retrieve(&self.pool, owner, default_quota).await
+    }
+
+    async fn stats(&self) -> crate::Result<StatsModel> {
+        StatsModel::retrieve(&self.pool).await
+    }
+
+    async fn list_units(&self) -> crate::Result<Vec<UnitModel>> {
+        UnitModel::all(&self.pool).await
+    }
+
+    async fn create_bar(&self, owner: Uuid, bar: CreateBarSchema) -> crate::Result<BarModel> {
+        BarModel::create(&self.pool, owner, bar).await
+    }
+
+    async fn list_bars(&self, owner: Uuid) -> crate::Result<Vec<BarModel>> {
+        BarModel::all(&self.pool, owner).await
+    }
+
+    async fn retrieve_bar(&self, owner: Uuid, id: Uuid) -> crate::Result<BarModel> {
+        BarModel::retrieve(&self.pool, owner, id).await
+    }
+
+    async fn add_bar_ingredient(
+        &self,
+        bar: &BarModel,
+        stock: CreateBarIngredientSchema,
+    ) -> crate::Result<BarIngredientModel> {
+        bar.add_ingredient(&self.pool, stock).await
+    }
+
+    async fn bar_ingredients(&self, bar: &BarModel) -> crate::Result<Vec<BarIngredientModel>> {
+        bar.ingredients(&self.pool).await
+    }
+
+    async fn retrieve_bar_ingredient(
+        &self,
+        bar: &BarModel,
+        id: Uuid,
+    ) -> crate::Result<BarIngredientModel> {
+        bar.ingredient(&self.pool, id).await
+    }
+
+    async fn set_bar_ingredient_levels(
+        &self,
+        bar: &BarModel,
+        id: Uuid,
+        levels: BarIngredientLevelsSchema,
+    ) -> crate::Result<BarIngredientModel> {
+        bar.set_ingredient_levels(&self.pool, id, &levels).await?;
+        bar.ingredient(&self.pool, id).await
+    }
+
+    async fn create_purchase(
+        &self,
+        stock: &BarIngredientModel,
+        purchase: CreatePurchaseSchema,
+    ) -> crate::Result<PurchaseModel> {
+        PurchaseModel::create(&self.pool, stock, purchase).await
+    }
+
+    async fn list_purchases(
+        &self,
+        stock: &BarIngredientModel,
+    ) -> crate::Result<Vec<PurchaseModel>> {
+        PurchaseModel::all(&self.pool, stock).await
+    }
+
+    async fn bar_purchases(&self, bar: &BarModel) -> crate::Result<Vec<PurchaseModel>> {
+        PurchaseModel::for_bar(&self.pool, bar).await
+    }
+
+    async fn create_serve(
+        &self,
+        bar: &BarModel,
+        serve: CreateServeSchema,
+        deductions: Vec<CreateDeductionSchema>,
+    ) -> crate::Result<ServeModel> {
+        let mut tx = self.pool.begin().await?;
+        let id = ServeModel::create(&mut *tx, bar, &serve).await?;
+        for deduction in &deductions {
+            ServeModel::deduct(&mut *tx, id, deduction).await?;
+        }
+        let serve = ServeModel::retrieve(&mut *tx, bar, id).await?;
+        tx.commit().await?;
+
+        Ok(serve)
+    }
+
+    async fn list_serves(&self, bar: &BarModel) -> crate::Result<Vec<ServeModel>> {
+        ServeModel::all(&self.pool, bar).await
+    }
+
+    async fn retrieve_serve(&self, bar: &BarModel, id: Uuid) -> crate::Result<ServeModel> {
+        ServeModel::retrieve(&self.pool, bar, id).await
+    }
+
+    async fn undo_serve(&self, bar: &BarModel, serve: &ServeModel) -> crate::Result<ServeModel> {
+        let mut tx = self.pool.begin().await?;
+        serve.mark_undone(&mut *tx).await?;
+        serve.restore(&mut *tx).await?;
+        let serve = ServeModel::retrieve(&mut *tx, bar, serve.id).await?;
+        tx.commit().await?;
+
+        Ok(serve)
+    }
+
+    async fn create_stock_take(&self, bar: &BarModel) -> crate::Result<StockTakeModel> {
+        let mut tx = self.pool.begin().await?;
+        let id = StockTakeModel::create(&mut *tx, bar).await?;
+        let stock_take = StockTakeModel::retrieve(&mut *tx, bar, id).await?;
+        tx.commit().await?;
+
+        Ok(stock_take)
+    }
+
+    async fn list_stock_takes(&self, bar: &BarModel) -> crate::Result<Vec<StockTakeModel>> {
+        StockTakeModel::all(&self.pool, bar).await
+    }
+
+    async fn retrieve_stock_take(&self, bar: &BarModel, id: Uuid) -> crate::Result<StockTakeModel> {
+        StockTakeModel::retrieve(&self.pool, bar, id).await
+    }
+
+    async fn count_stock(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+        bar_ingredient_id: Uuid,
+        quantity: f32,
+    ) -> crate::Result<StockTakeModel> {
+        stock_take
+            .count(&self.pool, bar_ingredient_id, quantity)
+            .await?;
+        StockTakeModel::retrieve(&self.pool, bar, stock_take.id).await
+    }
+
+    async fn close_stock_take(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<StockTakeModel> {
+        let mut tx = self.pool.begin().await?;
+        stock_take.lock(&mut *tx).await?;
+        stock_take.close(&mut *tx).await?;
+        let stock_take = StockTakeModel::retrieve(&mut *tx, bar, stock_take.id).await?;
+        tx.commit().await?;
+
+        Ok(stock_take)
+    }
+
+    async fn apply_stock_take(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<Option<AppliedStockTakeModel>> {
+        let mut tx = self.pool.begin().await?;
+        bar.lock(&mut *tx).await?;
+        let Some(clamped) = stock_take.apply(&mut *tx).await? else {
+            return Ok(None);
+        };
+        let stock_take = StockTakeModel::retrieve(&mut *tx, bar, stock_take.id).await?;
+        tx.commit().await?;
+
+        Ok(Some(AppliedStockTakeModel {
+            stock_take,
+            clamped,
+        }))
+    }
+
+    async fn stock_take_usage(
+        &self,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<Vec<StockUsageModel>> {
+        stock_take.usage(&self.pool).await
+    }
+
+    async fn create_bottle(
+        &self,
+        bar: &BarModel,
+        bottle: CreateBottleSchema,
+    ) -> crate::Result<BottleModel> {
+        let mut tx = self.pool.begin().await?;
+        let id = BottleModel::create(&mut *tx, &bottle).await?;
+        let bottle = BottleModel::retrieve(&mut *tx, bar, id).await?;
+        tx.commit().await?;
+
+        Ok(bottle)
+    }
+
+    async fn list_bottles(&self, bar: &BarModel) -> crate::Result<Vec<BottleModel>> {
+        BottleModel::all(&self.pool, bar).await
+    }
+
+    async fn retrieve_bottle(&self, bar: &BarModel, id: Uuid) -> crate::Result<BottleModel> {
+        BottleModel::retrieve(&self.pool, bar, id).await
+    }
+
+    async fn expiring_bottles(&self, bar: &BarModel, days: i32) -> crate::Result<Vec<BottleModel>> {
+        BottleModel::expiring(&self.pool, bar, days).await
+    }
+
+    async fn update_bottle(
+        &self,
+        bar: &BarModel,
+        bottle: &BottleModel,
+        update: UpdateBottleSchema,
+    ) -> crate::Result<BottleModel> {
+        bottle.update(&self.pool, &update).await?;
+        BottleModel::retrieve(&self.pool, bar, bottle.id).await
+    }
+
+    async fn delete_bottle(&self, bottle: &BottleModel) -> crate::Result<()> {
+        bottle.delete(&self.pool).await
+    }
+
+    async fn scan_product(
+        &self,
+        bar: &BarModel,
+        restock: RestockSchema,
+    ) -> crate::Result<Option<ScanModel>> {
+        let mut tx = self.pool.begin().await?;
+        UserModel::lock(&mut *tx, bar.owner).await?;
+        if !restock.is_current(&mut *tx, bar).await? {
+            return Ok(None);
+        }
+
+        let ingredient_id = match restock.ingredient_id {
+            Some(id) => id,
+            None => {
+                IngredientModel::create(&mut *tx, bar.owner, restock.ingredient())
+                    .await?
+                    .id
+            }
+        };
+        let bar_ingredient_id = match restock.bar_ingredient_id {
+            Some(id) => {
+                bar.restock_ingredient(&mut *tx, id, restock.quantity())
+                    .await?;
+                id
+            }
+            None => {
+                bar.add_ingredient(&mut *tx, restock.stock(ingredient_id))
+                    .await?
+                    .id
+            }
+        };
+        let mut ids = Vec::new();
+        for _ in 0..restock.bottles {
+            ids.push(BottleModel::create(&mut *tx, &restock.bottle(bar_ingredient_id)).await?);
+        }
+
+        let bar_ingredient = bar.ingredient(&mut *tx, bar_ingredient_id).await?;
+        let mut bottles = Vec::new();
+        for id in ids {
+            bottles.push(BottleModel::retrieve(&mut *tx, bar, id).await?);
+        }
+        tx.commit().await?;
+
+        Ok(Some(ScanModel {
+            product: restock.product,
+            ingredient_created: restock.ingredient_id.is_none(),
+            bar_ingredient,
+            bottles,
+        }))
+    }
+
+    async fn create_ingredient(
+        &self,
+        owner: Uuid,
+        ingredient: CreateIngredientSchema,
+    ) -> crate::Result<IngredientModel> {
+        IngredientModel::create(&self.pool, owner, ingredient).await
+    }
+
+    async fn list_ingredients(&self, owner: Uuid) -> crate::Result<Vec<IngredientModel>> {
+        IngredientModel::all(&self.pool, owner).await
+    }
+
+    async fn retrieve_ingredient(&self, owner: Uuid, id: Uuid) -> crate::Result<IngredientModel> {
+        IngredientModel::retrieve(&self.pool, owner, id).await
+    }
+
+    async fn sub_ingredients(
+        &self,
+        ingredient: IngredientModel,
+    ) -> crate::Result<Vec<SubIngredientModel>> {
+        ingredient.ingredients(&self.pool).await
+    }
+
+    async fn fork_ingredient(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+    ) -> crate::Result<IngredientModel> {
+        let mut tx = self.pool.begin().await?;
+        let fork = IngredientModel::create(&mut *tx, owner, ingredient.into()).await?;
+        ingredient.copy_parts(&mut *tx, fork.id).await?;
+        tx.commit().await?;
+
+        Ok(fork)
+    }
+
+    async fn add_sub_ingredient(
+        &self,
+        ingredient: &IngredientModel,
+        part: CreateSubIngredientSchema,
+    ) -> crate::Result<SubIngredientModel> {
+        ingredient.add_ingredient(&self.pool, part).await
+    }
+
+    async fn ingredient_parts(&self, owner: Uuid) -> crate::Result<Vec<IngredientPartModel>> {
+        IngredientModel::parts(&self.pool, owner).await
+    }
+
+    async fn create_shelf_life(
+        &self,
+        owner: Uuid,
+        shelf_life: CreateShelfLifeSchema,
+    ) -> crate::Result<ShelfLifeModel> {
+        ShelfLifeModel::create(&self.pool, owner, shelf_life).await
+    }
+
+    async fn list_shelf_lives(&self, owner: Uuid) -> crate::Result<Vec<ShelfLifeModel>> {
+        ShelfLifeModel::all(&self.pool, owner).await
+    }
+
+    async fn retrieve_shelf_life(&self, owner: Uuid, id: Uuid) -> crate::Result<ShelfLifeModel> {
+        ShelfLifeModel::retrieve(&self.pool, owner, id).await
+    }
+
+    async fn set_ingredient_shelf_life(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+        shelf_life_id: Option<Uuid>,
+    ) -> crate::Result<IngredientModel> {
+        ingredient.set_shelf_life(&self.pool, shelf_life_id).await?;
+        IngredientModel::retrieve(&self.pool, owner, ingredient.id).await
+    }
+
+    async fn set_ingredient_attributes(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+        attributes: IngredientAttributesSchema,
+    ) -> crate::Result<IngredientModel> {
+        ingredient.set_attributes(&self.pool, &attributes).await?;
+        IngredientModel::retrieve(&self.pool, owner, ingredient.id).await
+    }
+
+    async fn create_category(
+        &self,
+        owner: Uuid,
+        category: CreateCategorySchema,
+    ) -> crate::Result<CategoryModel> {
+        CategoryModel::create(&self.pool, owner, category).await
+    }
+
+    async fn list_categories(&self, owner: Uuid) -> crate::Result<Vec<CategoryModel>> {
+        CategoryModel::all(&self.pool, owner).await
+    }
+
+    async fn retrieve_category(&self, owner: Uuid, id: Uuid) -> crate::Result<CategoryModel> {
+        CategoryModel::retrieve(&self.pool, owner, id).await
+    }
+
+    async fn retrieve_product(&self, barcode: &str) -> crate::Result<ProductModel> {
+        ProductModel::retrieve(&self.pool, barcode).await
+    }
+
+    async fn import_products(&self, products: Vec<CreateProductSchema>) -> crate::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for product in &products {
+            ProductModel::upsert(&mut *tx, product).await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn create_recipe(
+        &self,
+        owner: Uuid,
+        recipe: CreateRecipeSchema,
+    ) -> crate::Result<RecipeModel> {
+        RecipeModel::create(&self.pool, owner, recipe).await
+    }
+
+    async fn list_recipes(&self, owner: Uuid) -> crate::Result<Vec<RecipeModel>> {
+        RecipeModel::all(&self.pool, owner).await
+    }
+
+    async fn retrieve_recipe(&self, owner: Uuid, id: Uuid) -> crate::Result<RecipeModel> {
+        RecipeModel::retrieve(&self.pool, owner, id).await
+    }
+
+    async fn add_recipe_ingredient(
+        &self,
+        recipe: &RecipeModel,
+        line: CreateRecipeIngredientSchema,
+    ) -> crate::Result<RecipeIngredientModel> {
+        recipe.add_ingredient(&self.pool, line).await
+    }
+
+    async fn recipe_ingredients(
+        &self,
+        recipe: &RecipeModel,
+    ) -> crate::Result<Vec<RecipeIngredientModel>> {
+        recipe.ingredients(&self.pool).await
+    }
+
+    async fn create_media<'a>(
+        &self,
+        owner: Uuid,
+        hash: &str,
+        media: CreateMediaSchema,
+        limits: &Limits,
+        store: StoreBlob<'a>,
+    ) -> crate::Result<MediaModel> {
+        let mut tx = self.pool.begin().await?;
+        UserModel::lock(&mut *tx, owner).await?;
+        UsageModel::retrieve(&mut *tx, owner, limits.storage_quota)
+            .await?
+            .check_upload(media.size, limits)?;
+        UsageModel::record_upload(&mut *tx, owner, media.size).await?;
+
+        let blob = BlobModel::acquire(&mut *tx, hash, media.size).await?;
+
+        if blob.reference_count == 1 {
+            store(blob.key).await?;
+        }
+
+        let media = MediaModel::create(&mut *tx, owner, blob.id, media).await?;
+        tx.commit().await?;
+
+        Ok(media)
+    }
+
+    async fn retrieve_media(&self, owner: Uuid, id: Uuid) -> crate::Result<MediaModel> {
+        MediaModel::retrieve(&self.pool, owner, id).await
+    }
+
+    async fn retrieve_blob(&self, id: Uuid) -> crate::Result<BlobModel> {
+        BlobModel::retrieve(&self.pool, id).await
+    }
+
+    async fn media_library(
+        &self,
+        owner: Uuid,
+        content_type: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> crate::Result<Vec<MediaLibraryModel>> {
+        MediaModel::library(&self.pool, owner, content_type, limit, offset).await
+    }
+
+    async fn count_media(&self, owner: Uuid, content_type: Option<&str>) -> crate::Result<i64> {
+        MediaModel::count(&self.pool, owner, content_type).await
+    }
+
+    async fn delete_media(&self, owner: Uuid, id: Uuid) -> crate::Result<Vec<Uuid>> {
+        let mut tx = self.pool.begin().await?;
+        let media = MediaModel::retrieve(&mut *tx, owner, id)
+            .await?
+            .delete(&mut *tx)
+            .await?;
+        let orphaned = release_blobs(&mut tx, &[media]).await?;
+        tx.commit().await?;
+
+        Ok(orphaned)
+    }
+
+    async fn delete_media_list(
+        &self,
+        owner: Uuid,
+        ids: &[Uuid],
+    ) -> crate::Result<DeletedMediaModel> {
+        let mut tx = self.pool.begin().await?;
+        let media = MediaModel::delete_many(&mut *tx, owner, ids).await?;
+        if media.len() != ids.len() {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+        let orphaned = release_blobs(&mut tx, &media).await?;
+        tx.commit().await?;
+
+        Ok(DeletedMediaModel { media, orphaned })
+    }
+
+    async fn delete_unused_media(&self, owner: Uuid) -> crate::Result<DeletedMediaModel> {
+        let mut tx = self.pool.begin().await?;
+        let media = MediaModel::delete_unused(&mut *tx, owner).await?;
+        let orphaned = release_blobs(&mut tx, &media).await?;
+        tx.commit().await?;
+
+        Ok(DeletedMediaModel { media, orphaned })
+    }
+
+    async fn remove_blob<'a>(&self, id: Uuid, remove: RemoveBlob<'a>) -> crate::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        if let Some(blob) = BlobModel::delete_unreferenced(&mut *tx, id).await? {
+            remove(blob.key).await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+async fn release_blobs(conn: &mut PgConnection, media: &[MediaModel]) -> crate::Result<Vec<Uuid>> {
+    let mut orphaned = Vec::new();
+
+    for m in media {
+        let blob = BlobModel::release(&mut *conn, m.blob_id).await?;
+        if blob.reference_count == 0 {
+            orphaned.push(blob.id);
+        }
+    }
+
+    Ok(orphaned)
+}
@@ -0,0 +1,3010 @@
+use std::{collections::HashMap, str::FromStr, time::Duration};
+
+use async_trait::async_trait;
+use axum::http::StatusCode;
+use chrono::{NaiveDate, NaiveDateTime};
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    types::Json,
+    Executor, FromRow, Pool, Sqlite, SqliteConnection, Transaction,
+};
+use uuid::Uuid;
+
+use super::{DeletedMediaModel, PoolStatus, RemoveBlob, Repository, StoreBlob};
+use crate::{
+    error::Error,
+    model::{
+        AppliedStockTakeModel, BarIngredientLevelsSchema, BarIngredientModel, BarModel, BlobModel,
+        BottleModel, CategoryModel, ClampedCountModel, CreateBarIngredientSchema, CreateBarSchema,
+        CreateBottleSchema, CreateCategorySchema, CreateDeductionSchema, CreateIngredientSchema,
+        CreateMediaSchema, CreateProductSchema, CreatePurchaseSchema, CreateRecipeIngredientSchema,
+        CreateRecipeSchema, CreateServeSchema, CreateShelfLifeSchema, CreateSubIngredientSchema,
+        DeductionModel, IngredientAttributesSchema, IngredientModel, IngredientPartModel,
+        MediaLibraryModel, MediaModel, ProductModel, PurchaseModel, RecipeIngredientModel,
+        RecipeModel, RestockSchema, ScanModel, ServeModel, ShelfLifeModel, StatsModel,
+        StockCountModel, StockTakeModel, StockUsageModel, SubIngredientModel, UnitModel,
+        UpdateBottleSchema, UsageModel, UserModel,
+    },
+    DatabaseConfig, Limits, SQLITE_MIGRATOR,
+};
+
+const MEDIA_COLUMNS: &str = r#"
+    m.media_id AS id,
+    m.size,
+    m.mime_type AS content_type,
+    m.user_id AS owner,
+    m.created_at,
+    m.width,
+    m.height,
+    m.blurhash,
+    m.dominant_color,
+    m.blob_id
+"#;
+
+const THUMBNAIL_COLUMNS: &str = r#"
+    m.media_id AS thumbnail_id,
+    m.size AS thumbnail_size,
+    m.mime_type AS thumbnail_content_type,
+    m.user_id AS thumbnail_owner,
+    m.created_at AS thumbnail_created_at,
+    m.width AS thumbnail_width,
+    m.height AS thumbnail_height,
+    m.blurhash AS thumbnail_blurhash,
+    m.dominant_color AS thumbnail_dominant_color,
+    m.blob_id AS thumbnail_blob_id
+"#;
+
+const UNIT_COLUMNS: &str = r#"
+    u.unit_id,
+    u.name AS unit_name,
+    u.abbreviation AS unit_abbreviation,
+    us.name AS unit_system,
+    u.dimension AS unit_dimension,
+    u.base_quantity AS unit_base_quantity
+"#;
+
+const BLOB_COLUMNS: &str = r#"
+    blob_id AS id,
+    hash,
+    object_key AS key,
+    size,
+    reference_count,
+    created_at
+"#;
+
+const UNUSED_MEDIA: &str = r#"
+    NOT EXISTS (SELECT 1 FROM bars b WHERE b.media_id = media.media_id)
+    AND NOT EXISTS (SELECT 1 FROM ingredients i WHERE i.media_id = media.media_id)
+    AND NOT EXISTS (SELECT 1 FROM recipes r WHERE r.media_id = media.media_id)
+    AND NOT EXISTS (SELECT 1 FROM profiles p WHERE p.media_id = media.media_id)
+"#;
+
+/// SQLite has no JSON aggregation, so nested models are read as flat rows and
+/// assembled here.
+pub(crate) struct SqliteRepository {
+    pool: Pool<Sqlite>,
+}
+
+impl SqliteRepository {
+    pub async fn connect(config: &DatabaseConfig) -> Result<Self, sqlx::Error> {
+        let options = SqliteConnectOptions::from_str(&config.url)?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .busy_timeout(Duration::from_secs(5));
+        let pool = SqlitePoolOptions::new()
+            .max_connections(config.max_connections)
+            .connect_with(options)
+            .await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Foreign keys are off while migrating, as migrations rebuild tables and
+    /// dropping the old table would cascade. The pragma cannot change inside a
+    /// transaction, so migrations get a connection of their own.
+    async fn migrate(&self, undo_to: Option<i64>) -> Result<(), MigrateError> {
+        let options = (*self.pool.connect_options()).clone().foreign_keys(false);
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        let migrated = match undo_to {
+            Some(target) => SQLITE_MIGRATOR.undo(&pool, target).await,
+            None => SQLITE_MIGRATOR.run(&pool).await,
+        };
+        let dangling: Vec<(String,)> =
+            sqlx::query_as(r#"SELECT "table" FROM pragma_foreign_key_check"#)
+                .fetch_all(&pool)
+                .await?;
+        pool.close().await;
+        migrated?;
+
+        match dangling.first() {
+            Some((table,)) => Err(MigrateError::Execute(sqlx::Error::Protocol(format!(
+                "migrations left rows of {table} referring to rows that do not exist"
+            )))),
+            None => Ok(()),
+        }
+    }
+
+    /// A deferred transaction that reads before it writes fails with
+    /// `SQLITE_BUSY` instead of waiting out the busy timeout, so take the write
+    /// lock up front.
+    async fn begin(&self) -> Result<Transaction<'static, Sqlite>, sqlx::Error> {
+        self.pool.begin_with("BEGIN IMMEDIATE").await
+    }
+
+    async fn stock_takes(
+        &self,
+        bar: &BarModel,
+        id: Option<Uuid>,
+    ) -> crate::Result<Vec<StockTakeModel>> {
+        let stock_takes: Vec<StockTakeRow> = sqlx::query_as(
+            r#"
+            SELECT
+                t.stock_take_id AS id,
+                t.bar_id,
+                t.created_at,
+                t.closed_at,
+                t.applied_at
+            FROM stock_takes t
+            JOIN bars b ON b.bar_id = t.bar_id
+            WHERE b.user_id = ?1
+                AND t.bar_id = ?2
+                AND (?3 IS NULL OR t.stock_take_id = ?3)
+            ORDER BY t.created_at DESC, t.stock_take_id
+            "#,
+        )
+        .bind(bar.owner)
+        .bind(bar.id)
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let counts: Vec<StockCountRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                c.stock_count_id AS id,
+                c.stock_take_id,
+                c.bar_ingredient_id,
+                i.ingredient_id,
+                i.name,
+                c.quantity,
+                c.expected,
+                c.counted_at,
+                {UNIT_COLUMNS}
+            FROM stock_counts c
+            JOIN stock_takes t ON t.stock_take_id = c.stock_take_id
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = c.bar_ingredient_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE t.bar_id = ?1
+                AND (?2 IS NULL OR t.stock_take_id = ?2)
+            ORDER BY i.name
+            "#
+        ))
+        .bind(bar.id)
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut counts_of: HashMap<Uuid, Vec<StockCountModel>> = HashMap::new();
+        for row in counts {
+            counts_of
+                .entry(row.stock_take_id)
+                .or_default()
+                .push(row.into());
+        }
+
+        Ok(stock_takes
+            .into_iter()
+            .map(|row| StockTakeModel {
+                counts: counts_of.remove(&row.id).unwrap_or_default(),
+                id: row.id,
+                bar_id: row.bar_id,
+                created_at: row.created_at,
+                closed_at: row.closed_at,
+                applied_at: row.applied_at,
+            })
+            .collect())
+    }
+
+    async fn bottles(&self, bar: &BarModel, id: Option<Uuid>) -> crate::Result<Vec<BottleModel>> {
+        let rows: Vec<BottleRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                bo.bottle_id AS id,
+                bi.bar_id,
+                bo.bar_ingredient_id,
+                i.ingredient_id,
+                i.name,
+                bo.size,
+                bo.fill,
+                bo.location,
+                bo.opened_at,
+                strftime('%Y-%m-%dT%H:%M:%f', bo.opened_at, '+' || s.days || ' days') AS expires_at,
+                bo.created_at,
+                {UNIT_COLUMNS}
+            FROM bottles bo
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = bo.bar_ingredient_id
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN shelf_lives s ON s.shelf_life_id = i.shelf_life_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = ?1
+                AND bi.bar_id = ?2
+                AND (?3 IS NULL OR bo.bottle_id = ?3)
+            ORDER BY i.name, bo.created_at, bo.bottle_id
+            "#
+        ))
+        .bind(bar.owner)
+        .bind(bar.id)
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(BottleModel::from).collect())
+    }
+
+    async fn serves(&self, bar: &BarModel, id: Option<Uuid>) -> crate::Result<Vec<ServeModel>> {
+        let serves: Vec<ServeRow> = sqlx::query_as(
+            r#"
+            SELECT
+                s.serve_id AS id,
+                s.bar_id,
+                s.recipe_id,
+                s.servings,
+                s.created_at,
+                s.undone_at
+            FROM serves s
+            JOIN bars b ON b.bar_id = s.bar_id
+            WHERE b.user_id = ?1
+                AND s.bar_id = ?2
+                AND (?3 IS NULL OR s.serve_id = ?3)
+            ORDER BY s.created_at DESC, s.serve_id
+            "#,
+        )
+        .bind(bar.owner)
+        .bind(bar.id)
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let deductions: Vec<DeductionRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                d.serve_id,
+                d.bar_ingredient_id,
+                i.ingredient_id,
+                i.name,
+                d.quantity,
+                {UNIT_COLUMNS}
+            FROM serve_deductions d
+            JOIN serves s ON s.serve_id = d.serve_id
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = d.bar_ingredient_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE s.bar_id = ?1
+                AND (?2 IS NULL OR s.serve_id = ?2)
+            ORDER BY i.name
+            "#
+        ))
+        .bind(bar.id)
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut deductions_of: HashMap<Uuid, Vec<DeductionModel>> = HashMap::new();
+        for row in deductions {
+            deductions_of
+                .entry(row.serve_id)
+                .or_default()
+                .push(row.into());
+        }
+
+        Ok(serves
+            .into_iter()
+            .map(|row| ServeModel {
+                deductions: deductions_of.remove(&row.id).unwrap_or_default(),
+                id: row.id,
+                bar_id: row.bar_id,
+                recipe_id: row.recipe_id,
+                servings: row.servings,
+                created_at: row.created_at,
+                undone_at: row.undone_at,
+            })
+            .collect())
+    }
+
+    async fn retrieve_media_on(
+        conn: &mut SqliteConnection,
+        owner: Uuid,
+        id: Uuid,
+    ) -> crate::Result<MediaModel> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {MEDIA_COLUMNS}
+            FROM media m
+            WHERE m.user_id = ?1
+                AND m.media_id = ?2
+            "#
+        ))
+        .bind(owner)
+        .bind(id)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| e.into())
+    }
+}
+
+#[derive(FromRow)]
+struct ThumbnailRow {
+    thumbnail_id: Option<Uuid>,
+    thumbnail_size: Option<i64>,
+    thumbnail_content_type: Option<String>,
+    thumbnail_owner: Option<Uuid>,
+    thumbnail_created_at: Option<NaiveDateTime>,
+    thumbnail_width: Option<i32>,
+    thumbnail_height: Option<i32>,
+    thumbnail_blurhash: Option<String>,
+    thumbnail_dominant_color: Option<String>,
+    thumbnail_blob_id: Option<Uuid>,
+}
+
+impl ThumbnailRow {
+    fn into_model(self) -> Option<MediaModel> {
+        Some(MediaModel {
+            id: self.thumbnail_id?,
+            size: self.thumbnail_size?,
+            content_type: self.thumbnail_content_type?,
+            owner: self.thumbnail_owner?,
+            created_at: self.thumbnail_created_at?,
+            width: self.thumbnail_width,
+            height: self.thumbnail_height,
+            blurhash: self.thumbnail_blurhash,
+            dominant_color: self.thumbnail_dominant_color,
+            blob_id: self.thumbnail_blob_id?,
+        })
+    }
+}
+
+#[derive(FromRow)]
+struct BarRow {
+    id: Uuid,
+    name: String,
+    owner: Uuid,
+    created_at: NaiveDateTime,
+    #[sqlx(flatten)]
+    thumbnail: ThumbnailRow,
+}
+
+impl From<BarRow> for BarModel {
+    fn from(row: BarRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            owner: row.owner,
+            created_at: row.created_at,
+            thumbnail: row.thumbnail.into_model(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct IngredientRow {
+    id: Uuid,
+    name: String,
+    description: String,
+    abv: Option<f32>,
+    sugar: Option<f32>,
+    acid: Option<f32>,
+    shelf_life_id: Option<Uuid>,
+    category_id: Option<Uuid>,
+    origin: Option<String>,
+    producer: Option<String>,
+    tags: Json<Vec<String>>,
+    flavours: Json<Vec<String>>,
+    owner: Option<Uuid>,
+    created_at: NaiveDateTime,
+    #[sqlx(flatten)]
+    thumbnail: ThumbnailRow,
+}
+
+impl From<IngredientRow> for IngredientModel {
+    fn from(row: IngredientRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            abv: row.abv,
+            sugar: row.sugar,
+            acid: row.acid,
+            shelf_life_id: row.shelf_life_id,
+            category_id: row.category_id,
+            origin: row.origin,
+            producer: row.producer,
+            tags: row.tags.0,
+            flavours: row.flavours.0,
+            owner: row.owner,
+            created_at: row.created_at,
+            thumbnail: row.thumbnail.into_model(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct SubIngredientRow {
+    sub_ingredient_id: Uuid,
+    parts: i16,
+    #[sqlx(flatten)]
+    ingredient: IngredientRow,
+}
+
+#[derive(FromRow)]
+struct UnitRow {
+    unit_id: Uuid,
+    unit_name: String,
+    unit_abbreviation: String,
+    unit_system: Option<String>,
+    unit_dimension: String,
+    unit_base_quantity: f64,
+}
+
+impl From<UnitRow> for UnitModel {
+    fn from(row: UnitRow) -> Self {
+        Self {
+            id: row.unit_id,
+            name: row.unit_name,
+            abbreviation: row.unit_abbreviation,
+            system: row.unit_system,
+            dimension: row.unit_dimension,
+            base_quantity: row.unit_base_quantity,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct RecipeRow {
+    id: Uuid,
+    name: String,
+    description: String,
+    method: Option<String>,
+    owner: Uuid,
+    created_at: NaiveDateTime,
+    #[sqlx(flatten)]
+    thumbnail: ThumbnailRow,
+}
+
+impl From<RecipeRow> for RecipeModel {
+    fn from(row: RecipeRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            method: row.method,
+            owner: row.owner,
+            created_at: row.created_at,
+            thumbnail: row.thumbnail.into_model(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct RecipeIngredientRow {
+    recipe_ingredient_id: Uuid,
+    quantity: f32,
+    #[sqlx(flatten)]
+    ingredient: IngredientRow,
+    line_category_id: Option<Uuid>,
+    #[sqlx(flatten)]
+    unit: UnitRow,
+}
+
+impl From<RecipeIngredientRow> for RecipeIngredientModel {
+    fn from(row: RecipeIngredientRow) -> Self {
+        Self {
+            id: row.recipe_ingredient_id,
+            quantity: row.quantity,
+            ingredient: row.ingredient.into(),
+            category_id: row.line_category_id,
+            unit: row.unit.into(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct BarIngredientRow {
+    bar_ingredient_id: Uuid,
+    bar_id: Uuid,
+    quantity: f32,
+    par: Option<f32>,
+    minimum: Option<f32>,
+    bottle_size: Option<f32>,
+    #[sqlx(flatten)]
+    ingredient: IngredientRow,
+    #[sqlx(flatten)]
+    unit: UnitRow,
+}
+
+impl From<BarIngredientRow> for BarIngredientModel {
+    fn from(row: BarIngredientRow) -> Self {
+        Self {
+            id: row.bar_ingredient_id,
+            bar_id: row.bar_id,
+            quantity: row.quantity,
+            par: row.par,
+            minimum: row.minimum,
+            bottle_size: row.bottle_size,
+            ingredient: row.ingredient.into(),
+            unit: row.unit.into(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct PurchaseRow {
+    id: Uuid,
+    bar_ingredient_id: Uuid,
+    price: f64,
+    quantity: f32,
+    store: Option<String>,
+    purchased_at: NaiveDate,
+    created_at: NaiveDateTime,
+    #[sqlx(flatten)]
+    unit: UnitRow,
+}
+
+impl From<PurchaseRow> for PurchaseModel {
+    fn from(row: PurchaseRow) -> Self {
+        Self {
+            id: row.id,
+            bar_ingredient_id: row.bar_ingredient_id,
+            price: row.price,
+            quantity: row.quantity,
+            unit: row.unit.into(),
+            store: row.store,
+            purchased_at: row.purchased_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct ServeRow {
+    id: Uuid,
+    bar_id: Uuid,
+    recipe_id: Uuid,
+    servings: f32,
+    created_at: NaiveDateTime,
+    undone_at: Option<NaiveDateTime>,
+}
+
+#[derive(FromRow)]
+struct DeductionRow {
+    serve_id: Uuid,
+    bar_ingredient_id: Uuid,
+    ingredient_id: Uuid,
+    name: String,
+    quantity: f32,
+    #[sqlx(flatten)]
+    unit: UnitRow,
+}
+
+impl From<DeductionRow> for DeductionModel {
+    fn from(row: DeductionRow) -> Self {
+        Self {
+            bar_ingredient_id: row.bar_ingredient_id,
+            ingredient_id: row.ingredient_id,
+            name: row.name,
+            quantity: row.quantity,
+            unit: row.unit.into(),
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct StockTakeRow {
+    id: Uuid,
+    bar_id: Uuid,
+    created_at: NaiveDateTime,
+    closed_at: Option<NaiveDateTime>,
+    applied_at: Option<NaiveDateTime>,
+}
+
+#[derive(FromRow)]
+struct StockCountRow {
+    id: Uuid,
+    stock_take_id: Uuid,
+    bar_ingredient_id: Uuid,
+    ingredient_id: Uuid,
+    name: String,
+    quantity: f32,
+    expected: Option<f32>,
+    counted_at: NaiveDateTime,
+    #[sqlx(flatten)]
+    unit: UnitRow,
+}
+
+impl From<StockCountRow> for StockCountModel {
+    fn from(row: StockCountRow) -> Self {
+        Self {
+            id: row.id,
+            bar_ingredient_id: row.bar_ingredient_id,
+            ingredient_id: row.ingredient_id,
+            name: row.name,
+            quantity: row.quantity,
+            expected: row.expected,
+            unit: row.unit.into(),
+            counted_at: row.counted_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct BottleRow {
+    id: Uuid,
+    bar_id: Uuid,
+    bar_ingredient_id: Uuid,
+    ingredient_id: Uuid,
+    name: String,
+    size: f32,
+    fill: f32,
+    location: Option<String>,
+    opened_at: Option<NaiveDateTime>,
+    expires_at: Option<NaiveDateTime>,
+    created_at: NaiveDateTime,
+    #[sqlx(flatten)]
+    unit: UnitRow,
+}
+
+impl From<BottleRow> for BottleModel {
+    fn from(row: BottleRow) -> Self {
+        Self {
+            id: row.id,
+            bar_id: row.bar_id,
+            bar_ingredient_id: row.bar_ingredient_id,
+            ingredient_id: row.ingredient_id,
+            name: row.name,
+            size: row.size,
+            fill: row.fill,
+            unit: row.unit.into(),
+            location: row.location,
+            opened_at: row.opened_at,
+            expires_at: row.expires_at,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct ProductRow {
+    barcode: String,
+    name: String,
+    ingredient: String,
+    size: f32,
+    abv: Option<f32>,
+    created_at: NaiveDateTime,
+    #[sqlx(flatten)]
+    unit: UnitRow,
+}
+
+impl From<ProductRow> for ProductModel {
+    fn from(row: ProductRow) -> Self {
+        Self {
+            barcode: row.barcode,
+            name: row.name,
+            ingredient: row.ingredient,
+            size: row.size,
+            unit: row.unit.into(),
+            abv: row.abv,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct MediaLibraryRow {
+    #[sqlx(flatten)]
+    media: MediaModel,
+    bars: Option<String>,
+    ingredients: Option<String>,
+    recipes: Option<String>,
+    profiles: Option<String>,
+}
+
+impl From<MediaLibraryRow> for MediaLibraryModel {
+    fn from(row: MediaLibraryRow) -> Self {
+        let ids = |list: Option<String>| -> Vec<Uuid> {
+            list.iter()
+                .flat_map(|list| list.split(','))
+                .filter_map(|id| Uuid::try_parse(id).ok())
+                .collect()
+        };
+
+        Self {
+            id: row.media.id,
+            size: row.media.size,
+            content_type: row.media.content_type,
+            owner: row.media.owner,
+            created_at: row.media.created_at,
+            width: row.media.width,
+            height: row.media.height,
+            blurhash: row.media.blurhash,
+            dominant_color: row.media.dominant_color,
+            bars: ids(row.bars),
+            ingredients: ids(row.ingredients),
+            recipes: ids(row.recipes),
+            profiles: ids(row.profiles),
+        }
+    }
+}
+
+#[async_trait]
+impl Repository for SqliteRepository {
+    fn migrator(&self) -> &'static Migrator {
+        &SQLITE_MIGRATOR
+    }
+
+    async fn run_migrations(&self) -> Result<(), MigrateError> {
+        self.migrate(None).await
+    }
+
+    async fn undo_migrations(&self, target: i64) -> Result<(), MigrateError> {
+        self.migrate(Some(target)).await
+    }
+
+    async fn applied_migrations(&self) -> Result<HashMap<i64, Vec<u8>>, MigrateError> {
+        let mut conn = self.pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+
+        Ok(conn
+            .list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|m| (m.version, m.checksum.into_owned()))
+            .collect())
+    }
+
+    async fn ping(&self) -> crate::Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    fn pool_status(&self) -> PoolStatus {
+        PoolStatus {
+            size: self.pool.size(),
+            idle: self.pool.num_idle(),
+            max: self.pool.options().get_max_connections(),
+        }
+    }
+
+    async fn close(&self) {
+        self.pool.close().await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_user(&self) -> crate::Result<UserModel> {
+        sqlx::query_as(
+            r#"
+            INSERT INTO users (user_id)
+            VALUES (?1)
+            RETURNING
+                user_id AS id,
+                created_at
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_user(&self, id: Uuid) -> crate::Result<UserModel> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                user_id AS id,
+                created_at
+            FROM users
+            WHERE user_id = ?1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn usage(&self, owner: Uuid, default_quota: i64) -> crate::Result<UsageModel> {
+        usage(&self.pool, owner, default_quota).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn stats(&self) -> crate::Result<StatsModel> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM bars) AS bars,
+                (SELECT COUNT(*) FROM ingredients) AS ingredients,
+                (SELECT COUNT(*) FROM media) AS media,
+                (SELECT COUNT(*) FROM users) AS users
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_units(&self) -> crate::Result<Vec<UnitModel>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                u.unit_id AS id,
+                u.name,
+                u.abbreviation,
+                us.name AS system,
+                u.dimension,
+                u.base_quantity
+            FROM units u
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_bar(&self, owner: Uuid, bar: CreateBarSchema) -> crate::Result<BarModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO bars (
+                bar_id,
+                name,
+                media_id,
+                user_id
+            ) VALUES (
+                ?1,
+                lower(?2),
+                ?3,
+                ?4
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(bar.name)
+        .bind(bar.thumbnail_id)
+        .bind(owner)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_bar(owner, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_bars(&self, owner: Uuid) -> crate::Result<Vec<BarModel>> {
+        let rows: Vec<BarRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                b.bar_id AS id,
+                b.name,
+                b.user_id AS owner,
+                b.created_at,
+                {THUMBNAIL_COLUMNS}
+            FROM bars b
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE b.user_id = ?1
+            "#
+        ))
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(BarModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_bar(&self, owner: Uuid, id: Uuid) -> crate::Result<BarModel> {
+        let row: BarRow = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                b.bar_id AS id,
+                b.name,
+                b.user_id AS owner,
+                b.created_at,
+                {THUMBNAIL_COLUMNS}
+            FROM bars b
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE b.user_id = ?1
+                AND b.bar_id = ?2
+            "#
+        ))
+        .bind(owner)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn add_bar_ingredient(
+        &self,
+        bar: &BarModel,
+        stock: CreateBarIngredientSchema,
+    ) -> crate::Result<BarIngredientModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO bar_ingredients (
+                bar_ingredient_id,
+                quantity,
+                par,
+                minimum,
+                bottle_size,
+                bar_id,
+                ingredient_id,
+                unit_id
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4,
+                ?5,
+                ?6,
+                ?7,
+                ?8
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(stock.quantity)
+        .bind(stock.par)
+        .bind(stock.minimum)
+        .bind(stock.bottle_size)
+        .bind(bar.id)
+        .bind(stock.ingredient_id)
+        .bind(stock.unit_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_bar_ingredient(bar, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn bar_ingredients(&self, bar: &BarModel) -> crate::Result<Vec<BarIngredientModel>> {
+        let rows: Vec<BarIngredientRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                bi.bar_ingredient_id,
+                bi.bar_id,
+                bi.quantity,
+                bi.par,
+                bi.minimum,
+                bi.bottle_size,
+                i.ingredient_id AS id,
+                i.name,
+                i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags,
+                i.flavours,
+                i.user_id AS owner,
+                i.created_at,
+                {THUMBNAIL_COLUMNS},
+                {UNIT_COLUMNS}
+            FROM bar_ingredients bi
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = ?1
+                AND bi.bar_id = ?2
+            ORDER BY i.name
+            "#
+        ))
+        .bind(bar.owner)
+        .bind(bar.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(BarIngredientModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_bar_ingredient(
+        &self,
+        bar: &BarModel,
+        id: Uuid,
+    ) -> crate::Result<BarIngredientModel> {
+        let row: BarIngredientRow = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                bi.bar_ingredient_id,
+                bi.bar_id,
+                bi.quantity,
+                bi.par,
+                bi.minimum,
+                bi.bottle_size,
+                i.ingredient_id AS id,
+                i.name,
+                i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags,
+                i.flavours,
+                i.user_id AS owner,
+                i.created_at,
+                {THUMBNAIL_COLUMNS},
+                {UNIT_COLUMNS}
+            FROM bar_ingredients bi
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = ?1
+                AND bi.bar_id = ?2
+                AND bi.bar_ingredient_id = ?3
+            "#
+        ))
+        .bind(bar.owner)
+        .bind(bar.id)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn set_bar_ingredient_levels(
+        &self,
+        bar: &BarModel,
+        id: Uuid,
+        levels: BarIngredientLevelsSchema,
+    ) -> crate::Result<BarIngredientModel> {
+        let result = sqlx::query(
+            r#"
+            UPDATE bar_ingredients
+            SET par = ?1,
+                minimum = ?2,
+                bottle_size = ?3
+            WHERE bar_id = ?4
+                AND bar_ingredient_id = ?5
+            "#,
+        )
+        .bind(levels.par)
+        .bind(levels.minimum)
+        .bind(levels.bottle_size)
+        .bind(bar.id)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound.into());
+        }
+
+        self.retrieve_bar_ingredient(bar, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_purchase(
+        &self,
+        stock: &BarIngredientModel,
+        purchase: CreatePurchaseSchema,
+    ) -> crate::Result<PurchaseModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO purchases (
+                purchase_id,
+                price,
+                quantity,
+                store,
+                purchased_at,
+                bar_ingredient_id,
+                unit_id
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4,
+                COALESCE(?5, date('now')),
+                ?6,
+                ?7
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(purchase.price)
+        .bind(purchase.quantity)
+        .bind(purchase.store)
+        .bind(purchase.purchased_at)
+        .bind(stock.id)
+        .bind(purchase.unit_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.list_purchases(stock)
+            .await?
+            .into_iter()
+            .find(|purchase| purchase.id == id)
+            .ok_or(sqlx::Error::RowNotFound.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_purchases(
+        &self,
+        stock: &BarIngredientModel,
+    ) -> crate::Result<Vec<PurchaseModel>> {
+        let rows: Vec<PurchaseRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                p.purchase_id AS id,
+                p.bar_ingredient_id,
+                p.price,
+                p.quantity,
+                p.store,
+                p.purchased_at,
+                p.created_at,
+                {UNIT_COLUMNS}
+            FROM purchases p
+            JOIN units u ON u.unit_id = p.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE p.bar_ingredient_id = ?1
+            ORDER BY p.purchased_at DESC, p.created_at DESC
+            "#
+        ))
+        .bind(stock.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(PurchaseModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn bar_purchases(&self, bar: &BarModel) -> crate::Result<Vec<PurchaseModel>> {
+        let rows: Vec<PurchaseRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                p.purchase_id AS id,
+                p.bar_ingredient_id,
+                p.price,
+                p.quantity,
+                p.store,
+                p.purchased_at,
+                p.created_at,
+                {UNIT_COLUMNS}
+            FROM purchases p
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = p.bar_ingredient_id
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN units u ON u.unit_id = p.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = ?1
+                AND bi.bar_id = ?2
+            ORDER BY p.purchased_at DESC, p.created_at DESC
+            "#
+        ))
+        .bind(bar.owner)
+        .bind(bar.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(PurchaseModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_serve(
+        &self,
+        bar: &BarModel,
+        serve: CreateServeSchema,
+        deductions: Vec<CreateDeductionSchema>,
+    ) -> crate::Result<ServeModel> {
+        let id = Uuid::new_v4();
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO serves (
+                serve_id,
+                servings,
+                bar_id,
+                recipe_id
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(serve.servings)
+        .bind(bar.id)
+        .bind(serve.recipe_id)
+        .execute(&mut *tx)
+        .await?;
+
+        for deduction in &deductions {
+            sqlx::query(
+                r#"
+                INSERT INTO serve_deductions (
+                    serve_deduction_id,
+                    quantity,
+                    serve_id,
+                    bar_ingredient_id
+                ) VALUES (
+                    ?1,
+                    ?2,
+                    ?3,
+                    ?4
+                )
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(deduction.quantity)
+            .bind(id)
+            .bind(deduction.bar_ingredient_id)
+            .execute(&mut *tx)
+            .await?;
+
+            sqlx::query(
+                r#"
+                UPDATE bar_ingredients
+                SET quantity = quantity - ?1
+                WHERE bar_ingredient_id = ?2
+                "#,
+            )
+            .bind(deduction.quantity)
+            .bind(deduction.bar_ingredient_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        self.retrieve_serve(bar, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_serves(&self, bar: &BarModel) -> crate::Result<Vec<ServeModel>> {
+        self.serves(bar, None).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_serve(&self, bar: &BarModel, id: Uuid) -> crate::Result<ServeModel> {
+        self.serves(bar, Some(id))
+            .await?
+            .pop()
+            .ok_or(sqlx::Error::RowNotFound.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn undo_serve(&self, bar: &BarModel, serve: &ServeModel) -> crate::Result<ServeModel> {
+        let mut tx = self.begin().await?;
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE serves
+            SET undone_at = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+            WHERE serve_id = ?1
+                AND undone_at IS NULL
+            RETURNING serve_id
+            "#,
+        )
+        .bind(serve.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE bar_ingredients
+            SET quantity = quantity + (
+                SELECT SUM(d.quantity)
+                FROM serve_deductions d
+                WHERE d.serve_id = ?1
+                    AND d.bar_ingredient_id = bar_ingredients.bar_ingredient_id
+            )
+            WHERE bar_ingredient_id IN (
+                SELECT bar_ingredient_id
+                FROM serve_deductions
+                WHERE serve_id = ?1
+            )
+            "#,
+        )
+        .bind(serve.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.retrieve_serve(bar, serve.id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_stock_take(&self, bar: &BarModel) -> crate::Result<StockTakeModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO stock_takes (
+                stock_take_id,
+                bar_id
+            ) VALUES (
+                ?1,
+                ?2
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(bar.id)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_stock_take(bar, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_stock_takes(&self, bar: &BarModel) -> crate::Result<Vec<StockTakeModel>> {
+        self.stock_takes(bar, None).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_stock_take(&self, bar: &BarModel, id: Uuid) -> crate::Result<StockTakeModel> {
+        self.stock_takes(bar, Some(id))
+            .await?
+            .pop()
+            .ok_or(sqlx::Error::RowNotFound.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn count_stock(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+        bar_ingredient_id: Uuid,
+        quantity: f32,
+    ) -> crate::Result<StockTakeModel> {
+        let counted = sqlx::query(
+            r#"
+            INSERT INTO stock_counts (
+                stock_count_id,
+                quantity,
+                stock_take_id,
+                bar_ingredient_id
+            )
+            SELECT ?1, ?2, t.stock_take_id, ?4
+            FROM stock_takes t
+            WHERE t.stock_take_id = ?3
+                AND t.closed_at IS NULL
+            ON CONFLICT (stock_take_id, bar_ingredient_id) DO UPDATE
+            SET quantity = excluded.quantity,
+                counted_at = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(quantity)
+        .bind(stock_take.id)
+        .bind(bar_ingredient_id)
+        .execute(&self.pool)
+        .await?;
+        if counted.rows_affected() == 0 {
+            return Err(Error::new(
+                StatusCode::CONFLICT,
+                "stock take has been closed",
+            ));
+        }
+
+        self.retrieve_stock_take(bar, stock_take.id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn close_stock_take(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<StockTakeModel> {
+        let mut tx = self.begin().await?;
+        sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE stock_takes
+            SET closed_at = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+            WHERE stock_take_id = ?1
+                AND closed_at IS NULL
+            RETURNING stock_take_id
+            "#,
+        )
+        .bind(stock_take.id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE stock_counts
+            SET expected = (
+                SELECT bi.quantity
+                FROM bar_ingredients bi
+                WHERE bi.bar_ingredient_id = stock_counts.bar_ingredient_id
+            )
+            WHERE stock_take_id = ?1
+            "#,
+        )
+        .bind(stock_take.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        self.retrieve_stock_take(bar, stock_take.id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn apply_stock_take(
+        &self,
+        bar: &BarModel,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<Option<AppliedStockTakeModel>> {
+        let mut tx = self.begin().await?;
+        let applied = sqlx::query_scalar::<_, Uuid>(
+            r#"
+            UPDATE stock_takes
+            SET applied_at = strftime('%Y-%m-%dT%H:%M:%f', 'now')
+            WHERE stock_take_id = ?1
+                AND closed_at IS NOT NULL
+                AND applied_at IS NULL
+                AND NOT EXISTS (
+                    SELECT 1
+                    FROM stock_takes later
+                    WHERE later.bar_id = stock_takes.bar_id
+                        AND later.created_at > stock_takes.created_at
+                        AND later.applied_at IS NOT NULL
+                )
+            RETURNING stock_take_id
+            "#,
+        )
+        .bind(stock_take.id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if applied.is_none() {
+            return Ok(None);
+        }
+
+        let clamped = sqlx::query_as::<_, ClampedCountModel>(
+            r#"
+            SELECT
+                bi.bar_ingredient_id,
+                c.expected - c.quantity - bi.quantity AS shortfall
+            FROM stock_counts c
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = c.bar_ingredient_id
+            WHERE c.stock_take_id = ?1
+                AND bi.quantity + c.quantity - c.expected < 0
+            "#,
+        )
+        .bind(stock_take.id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            r#"
+            UPDATE bar_ingredients
+            SET quantity = MAX(quantity + (
+                SELECT c.quantity - c.expected
+                FROM stock_counts c
+                WHERE c.stock_take_id = ?1
+                    AND c.bar_ingredient_id = bar_ingredients.bar_ingredient_id
+            ), 0)
+            WHERE bar_ingredient_id IN (
+                SELECT bar_ingredient_id
+                FROM stock_counts
+                WHERE stock_take_id = ?1
+            )
+            "#,
+        )
+        .bind(stock_take.id)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(Some(AppliedStockTakeModel {
+            stock_take: self.retrieve_stock_take(bar, stock_take.id).await?,
+            clamped,
+        }))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn stock_take_usage(
+        &self,
+        stock_take: &StockTakeModel,
+    ) -> crate::Result<Vec<StockUsageModel>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                d.bar_ingredient_id,
+                SUM(d.quantity) AS quantity
+            FROM serve_deductions d
+            JOIN serves s ON s.serve_id = d.serve_id
+            JOIN stock_takes t ON t.bar_id = s.bar_id
+            WHERE t.stock_take_id = ?1
+                AND s.undone_at IS NULL
+                AND s.created_at <= COALESCE(t.closed_at, strftime('%Y-%m-%dT%H:%M:%f', 'now'))
+                AND s.created_at > COALESCE(
+                    (
+                        SELECT MAX(p.closed_at)
+                        FROM stock_takes p
+                        WHERE p.bar_id = t.bar_id
+                            AND p.applied_at IS NOT NULL
+                            AND p.closed_at <= t.created_at
+                    ),
+                    ''
+                )
+            GROUP BY d.bar_ingredient_id
+            "#,
+        )
+        .bind(stock_take.id)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_bottle(
+        &self,
+        bar: &BarModel,
+        bottle: CreateBottleSchema,
+    ) -> crate::Result<BottleModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO bottles (
+                bottle_id,
+                size,
+                fill,
+                location,
+                opened_at,
+                bar_ingredient_id
+            ) VALUES (
+                ?1,
+                ?2,
+                COALESCE(?3, 1.0),
+                ?4,
+                strftime('%Y-%m-%dT%H:%M:%f', ?5),
+                ?6
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(bottle.size)
+        .bind(bottle.fill)
+        .bind(bottle.location)
+        .bind(bottle.opened_at)
+        .bind(bottle.bar_ingredient_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_bottle(bar, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_bottles(&self, bar: &BarModel) -> crate::Result<Vec<BottleModel>> {
+        self.bottles(bar, None).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_bottle(&self, bar: &BarModel, id: Uuid) -> crate::Result<BottleModel> {
+        self.bottles(bar, Some(id))
+            .await?
+            .pop()
+            .ok_or(sqlx::Error::RowNotFound.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn expiring_bottles(&self, bar: &BarModel, days: i32) -> crate::Result<Vec<BottleModel>> {
+        let rows: Vec<BottleRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                bo.bottle_id AS id,
+                bi.bar_id,
+                bo.bar_ingredient_id,
+                i.ingredient_id,
+                i.name,
+                bo.size,
+                bo.fill,
+                bo.location,
+                bo.opened_at,
+                strftime('%Y-%m-%dT%H:%M:%f', bo.opened_at, '+' || s.days || ' days') AS expires_at,
+                bo.created_at,
+                {UNIT_COLUMNS}
+            FROM bottles bo
+            JOIN bar_ingredients bi ON bi.bar_ingredient_id = bo.bar_ingredient_id
+            JOIN bars b ON b.bar_id = bi.bar_id
+            JOIN ingredients i ON i.ingredient_id = bi.ingredient_id
+            JOIN shelf_lives s ON s.shelf_life_id = i.shelf_life_id
+            JOIN units u ON u.unit_id = bi.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE b.user_id = ?1
+                AND bi.bar_id = ?2
+                AND bo.fill > 0
+                AND expires_at <= strftime('%Y-%m-%dT%H:%M:%f', 'now', '+' || ?3 || ' days')
+            ORDER BY expires_at, i.name, bo.bottle_id
+            "#
+        ))
+        .bind(bar.owner)
+        .bind(bar.id)
+        .bind(days)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(BottleModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn update_bottle(
+        &self,
+        bar: &BarModel,
+        bottle: &BottleModel,
+        update: UpdateBottleSchema,
+    ) -> crate::Result<BottleModel> {
+        sqlx::query(
+            r#"
+            UPDATE bottles
+            SET fill = ?1,
+                location = ?2,
+                opened_at = strftime('%Y-%m-%dT%H:%M:%f', ?3)
+            WHERE bottle_id = ?4
+            "#,
+        )
+        .bind(update.fill)
+        .bind(update.location)
+        .bind(update.opened_at)
+        .bind(bottle.id)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_bottle(bar, bottle.id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_bottle(&self, bottle: &BottleModel) -> crate::Result<()> {
+        sqlx::query(
+            r#"
+            DELETE FROM bottles
+            WHERE bottle_id = ?1
+            "#,
+        )
+        .bind(bottle.id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn scan_product(
+        &self,
+        bar: &BarModel,
+        restock: RestockSchema,
+    ) -> crate::Result<Option<ScanModel>> {
+        // The transaction holds the write lock, so no other scan can create
+        // the ingredient or bar ingredient between this check and the writes.
+        let mut tx = self.begin().await?;
+        let current: bool = sqlx::query_scalar(
+            r#"
+            SELECT NOT EXISTS (
+                SELECT 1
+                FROM ingredients i
+                WHERE ?1 IS NULL
+                    AND i.user_id = ?2
+                    AND i.name = lower(?3)
+            ) AND NOT EXISTS (
+                SELECT 1
+                FROM bar_ingredients bi
+                WHERE ?4 IS NULL
+                    AND bi.bar_id = ?5
+                    AND bi.ingredient_id = ?1
+            )
+            "#,
+        )
+        .bind(restock.ingredient_id)
+        .bind(bar.owner)
+        .bind(&restock.product.ingredient)
+        .bind(restock.bar_ingredient_id)
+        .bind(bar.id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if !current {
+            return Ok(None);
+        }
+
+        let ingredient_id = match restock.ingredient_id {
+            Some(id) => id,
+            None => {
+                let ingredient = restock.ingredient();
+                let id = Uuid::new_v4();
+                sqlx::query(
+                    r#"
+                    INSERT INTO ingredients (
+                        ingredient_id,
+                        name,
+                        description,
+                        abv,
+                        user_id
+                    ) VALUES (
+                        ?1,
+                        lower(?2),
+                        ?3,
+                        ?4,
+                        ?5
+                    )
+                    "#,
+                )
+                .bind(id)
+                .bind(ingredient.name)
+                .bind(ingredient.description)
+                .bind(ingredient.abv)
+                .bind(bar.owner)
+                .execute(&mut *tx)
+                .await?;
+                id
+            }
+        };
+        let bar_ingredient_id = match restock.bar_ingredient_id {
+            Some(id) => {
+                sqlx::query_scalar::<_, Uuid>(
+                    r#"
+                    UPDATE bar_ingredients
+                    SET quantity = quantity + ?1
+                    WHERE bar_id = ?2
+                        AND bar_ingredient_id = ?3
+                    RETURNING bar_ingredient_id
+                    "#,
+                )
+                .bind(restock.quantity())
+                .bind(bar.id)
+                .bind(id)
+                .fetch_one(&mut *tx)
+                .await?
+            }
+            None => {
+                let stock = restock.stock(ingredient_id);
+                let id = Uuid::new_v4();
+                sqlx::query(
+                    r#"
+                    INSERT INTO bar_ingredients (
+                        bar_ingredient_id,
+                        quantity,
+                        bottle_size,
+                        bar_id,
+                        ingredient_id,
+                        unit_id
+                    ) VALUES (
+                        ?1,
+                        ?2,
+                        ?3,
+                        ?4,
+                        ?5,
+                        ?6
+                    )
+                    "#,
+                )
+                .bind(id)
+                .bind(stock.quantity)
+                .bind(stock.bottle_size)
+                .bind(bar.id)
+                .bind(stock.ingredient_id)
+                .bind(stock.unit_id)
+                .execute(&mut *tx)
+                .await?;
+                id
+            }
+        };
+        let mut ids = Vec::new();
+        for _ in 0..restock.bottles {
+            let bottle = restock.bottle(bar_ingredient_id);
+            let id = Uuid::new_v4();
+            sqlx::query(
+                r#"
+                INSERT INTO bottles (
+                    bottle_id,
+                    size,
+                    bar_ingredient_id
+                ) VALUES (
+                    ?1,
+                    ?2,
+                    ?3
+                )
+                "#,
+            )
+            .bind(id)
+            .bind(bottle.size)
+            .bind(bottle.bar_ingredient_id)
+            .execute(&mut *tx)
+            .await?;
+            ids.push(id);
+        }
+        tx.commit().await?;
+
+        let bar_ingredient = self.retrieve_bar_ingredient(bar, bar_ingredient_id).await?;
+        let mut bottles = Vec::new();
+        for id in ids {
+            bottles.push(self.retrieve_bottle(bar, id).await?);
+        }
+
+        Ok(Some(ScanModel {
+            product: restock.product,
+            ingredient_created: restock.ingredient_id.is_none(),
+            bar_ingredient,
+            bottles,
+        }))
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_ingredient(
+        &self,
+        owner: Uuid,
+        ingredient: CreateIngredientSchema,
+    ) -> crate::Result<IngredientModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO ingredients (
+                ingredient_id,
+                name,
+                description,
+                abv,
+                sugar,
+                acid,
+                shelf_life_id,
+                media_id,
+                user_id,
+                category_id,
+                origin,
+                producer,
+                tags,
+                flavours
+            ) VALUES (
+                ?1,
+                lower(?2),
+                ?3,
+                ?4,
+                ?5,
+                ?6,
+                ?7,
+                ?8,
+                ?9,
+                ?10,
+                ?11,
+                ?12,
+                ?13,
+                ?14
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(ingredient.name)
+        .bind(ingredient.description)
+        .bind(ingredient.abv)
+        .bind(ingredient.sugar)
+        .bind(ingredient.acid)
+        .bind(ingredient.shelf_life_id)
+        .bind(ingredient.thumbnail_id)
+        .bind(owner)
+        .bind(ingredient.category_id)
+        .bind(ingredient.origin)
+        .bind(ingredient.producer)
+        .bind(Json(ingredient.tags))
+        .bind(Json(ingredient.flavours))
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_ingredient(owner, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn fork_ingredient(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+    ) -> crate::Result<IngredientModel> {
+        let fork = CreateIngredientSchema::from(ingredient);
+        let id = Uuid::new_v4();
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            r#"
+            INSERT INTO ingredients (
+                ingredient_id,
+                name,
+                description,
+                abv,
+                sugar,
+                acid,
+                shelf_life_id,
+                media_id,
+                user_id,
+                category_id,
+                origin,
+                producer,
+                tags,
+                flavours
+            ) VALUES (
+                ?1,
+                lower(?2),
+                ?3,
+                ?4,
+                ?5,
+                ?6,
+                ?7,
+                ?8,
+                ?9,
+                ?10,
+                ?11,
+                ?12,
+                ?13,
+                ?14
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(fork.name)
+        .bind(fork.description)
+        .bind(fork.abv)
+        .bind(fork.sugar)
+        .bind(fork.acid)
+        .bind(fork.shelf_life_id)
+        .bind(fork.thumbnail_id)
+        .bind(owner)
+        .bind(fork.category_id)
+        .bind(fork.origin)
+        .bind(fork.producer)
+        .bind(Json(fork.tags))
+        .bind(Json(fork.flavours))
+        .execute(&mut *tx)
+        .await?;
+
+        let parts: Vec<(i16, Uuid)> = sqlx::query_as(
+            r#"
+            SELECT
+                parts,
+                ingredient_id
+            FROM ingredient_ingredients
+            WHERE compound_ingredient_id = ?1
+            "#,
+        )
+        .bind(ingredient.id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (parts, ingredient_id) in parts {
+            sqlx::query(
+                r#"
+                INSERT INTO ingredient_ingredients (
+                    ingredient_ingredient_id,
+                    parts,
+                    ingredient_id,
+                    compound_ingredient_id
+                ) VALUES (
+                    ?1,
+                    ?2,
+                    ?3,
+                    ?4
+                )
+                "#,
+            )
+            .bind(Uuid::new_v4())
+            .bind(parts)
+            .bind(ingredient_id)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        self.retrieve_ingredient(owner, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_ingredients(&self, owner: Uuid) -> crate::Result<Vec<IngredientModel>> {
+        let rows: Vec<IngredientRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                i.ingredient_id AS id,
+                i.name,
+                i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags,
+                i.flavours,
+                i.user_id AS owner,
+                i.created_at,
+                {THUMBNAIL_COLUMNS}
+            FROM ingredients i
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE i.user_id = ?1
+                OR i.user_id IS NULL
+            "#
+        ))
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(IngredientModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_ingredient(&self, owner: Uuid, id: Uuid) -> crate::Result<IngredientModel> {
+        let row: IngredientRow = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                i.ingredient_id AS id,
+                i.name,
+                i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags,
+                i.flavours,
+                i.user_id AS owner,
+                i.created_at,
+                {THUMBNAIL_COLUMNS}
+            FROM ingredients i
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE (i.user_id = ?1 OR i.user_id IS NULL)
+                AND i.ingredient_id = ?2
+            "#
+        ))
+        .bind(owner)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn sub_ingredients(
+        &self,
+        ingredient: IngredientModel,
+    ) -> crate::Result<Vec<SubIngredientModel>> {
+        let rows: Vec<SubIngredientRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                ii.ingredient_ingredient_id AS sub_ingredient_id,
+                ii.parts,
+                i.ingredient_id AS id,
+                i.name,
+                i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags,
+                i.flavours,
+                i.user_id AS owner,
+                i.created_at,
+                {THUMBNAIL_COLUMNS}
+            FROM ingredient_ingredients ii
+            JOIN ingredients i USING (ingredient_id)
+            LEFT JOIN media m USING (media_id)
+            WHERE (i.user_id = ?1 OR i.user_id IS NULL)
+                AND ii.compound_ingredient_id = ?2
+            "#
+        ))
+        .bind(ingredient.owner)
+        .bind(ingredient.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| SubIngredientModel {
+                id: row.sub_ingredient_id,
+                parts: row.parts,
+                ingredient: Some(row.ingredient.into()),
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn add_sub_ingredient(
+        &self,
+        ingredient: &IngredientModel,
+        part: CreateSubIngredientSchema,
+    ) -> crate::Result<SubIngredientModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO ingredient_ingredients (
+                ingredient_ingredient_id,
+                parts,
+                ingredient_id,
+                compound_ingredient_id
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(part.parts)
+        .bind(part.ingredient_id)
+        .bind(ingredient.id)
+        .execute(&self.pool)
+        .await?;
+
+        self.sub_ingredients(ingredient.clone())
+            .await?
+            .into_iter()
+            .find(|part| part.id == id)
+            .ok_or(sqlx::Error::RowNotFound.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn ingredient_parts(&self, owner: Uuid) -> crate::Result<Vec<IngredientPartModel>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                ii.compound_ingredient_id AS compound_id,
+                ii.ingredient_id,
+                ii.parts
+            FROM ingredient_ingredients ii
+            JOIN ingredients c ON c.ingredient_id = ii.compound_ingredient_id
+            WHERE c.user_id = ?1
+                OR c.user_id IS NULL
+            "#,
+        )
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_shelf_life(
+        &self,
+        owner: Uuid,
+        shelf_life: CreateShelfLifeSchema,
+    ) -> crate::Result<ShelfLifeModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO shelf_lives (
+                shelf_life_id,
+                name,
+                days,
+                user_id
+            ) VALUES (
+                ?1,
+                lower(?2),
+                ?3,
+                ?4
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(shelf_life.name)
+        .bind(shelf_life.days)
+        .bind(owner)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_shelf_life(owner, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_shelf_lives(&self, owner: Uuid) -> crate::Result<Vec<ShelfLifeModel>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                shelf_life_id AS id,
+                name,
+                days,
+                user_id AS owner,
+                created_at
+            FROM shelf_lives
+            WHERE user_id = ?1
+                OR user_id IS NULL
+            ORDER BY name
+            "#,
+        )
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_shelf_life(&self, owner: Uuid, id: Uuid) -> crate::Result<ShelfLifeModel> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                shelf_life_id AS id,
+                name,
+                days,
+                user_id AS owner,
+                created_at
+            FROM shelf_lives
+            WHERE (user_id = ?1 OR user_id IS NULL)
+                AND shelf_life_id = ?2
+            "#,
+        )
+        .bind(owner)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn set_ingredient_shelf_life(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+        shelf_life_id: Option<Uuid>,
+    ) -> crate::Result<IngredientModel> {
+        sqlx::query(
+            r#"
+            UPDATE ingredients
+            SET shelf_life_id = ?1
+            WHERE ingredient_id = ?2
+            "#,
+        )
+        .bind(shelf_life_id)
+        .bind(ingredient.id)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_ingredient(owner, ingredient.id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn set_ingredient_attributes(
+        &self,
+        owner: Uuid,
+        ingredient: &IngredientModel,
+        attributes: IngredientAttributesSchema,
+    ) -> crate::Result<IngredientModel> {
+        sqlx::query(
+            r#"
+            UPDATE ingredients
+            SET category_id = ?1,
+                origin = ?2,
+                producer = ?3,
+                tags = ?4,
+                flavours = ?5
+            WHERE ingredient_id = ?6
+            "#,
+        )
+        .bind(attributes.category_id)
+        .bind(attributes.origin)
+        .bind(attributes.producer)
+        .bind(Json(attributes.tags))
+        .bind(Json(attributes.flavours))
+        .bind(ingredient.id)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_ingredient(owner, ingredient.id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_category(
+        &self,
+        owner: Uuid,
+        category: CreateCategorySchema,
+    ) -> crate::Result<CategoryModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO categories (
+                category_id,
+                name,
+                parent_id,
+                user_id
+            ) VALUES (
+                ?1,
+                lower(?2),
+                ?3,
+                ?4
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(category.name)
+        .bind(category.parent_id)
+        .bind(owner)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_category(owner, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_categories(&self, owner: Uuid) -> crate::Result<Vec<CategoryModel>> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                category_id AS id,
+                name,
+                parent_id,
+                user_id AS owner,
+                created_at
+            FROM categories
+            WHERE user_id = ?1
+                OR user_id IS NULL
+            ORDER BY name
+            "#,
+        )
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_category(&self, owner: Uuid, id: Uuid) -> crate::Result<CategoryModel> {
+        sqlx::query_as(
+            r#"
+            SELECT
+                category_id AS id,
+                name,
+                parent_id,
+                user_id AS owner,
+                created_at
+            FROM categories
+            WHERE (user_id = ?1 OR user_id IS NULL)
+                AND category_id = ?2
+            "#,
+        )
+        .bind(owner)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_product(&self, barcode: &str) -> crate::Result<ProductModel> {
+        sqlx::query_as::<_, ProductRow>(&format!(
+            r#"
+            SELECT
+                p.barcode,
+                p.name,
+                p.ingredient,
+                p.size,
+                p.abv,
+                p.created_at,
+                {UNIT_COLUMNS}
+            FROM products p
+            JOIN units u ON u.unit_id = p.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE p.barcode = ?1
+            "#
+        ))
+        .bind(barcode)
+        .fetch_one(&self.pool)
+        .await
+        .map(ProductModel::from)
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self, products))]
+    async fn import_products(&self, products: Vec<CreateProductSchema>) -> crate::Result<()> {
+        let mut tx = self.begin().await?;
+        for product in products {
+            sqlx::query(
+                r#"
+                INSERT INTO products (
+                    barcode,
+                    name,
+                    ingredient,
+                    size,
+                    abv,
+                    unit_id
+                ) VALUES (
+                    ?1,
+                    ?2,
+                    ?3,
+                    ?4,
+                    ?5,
+                    ?6
+                ) ON CONFLICT (barcode) DO UPDATE
+                SET name = excluded.name,
+                    ingredient = excluded.ingredient,
+                    size = excluded.size,
+                    abv = excluded.abv,
+                    unit_id = excluded.unit_id
+                "#,
+            )
+            .bind(product.barcode)
+            .bind(product.name)
+            .bind(product.ingredient)
+            .bind(product.size)
+            .bind(product.abv)
+            .bind(product.unit_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn create_recipe(
+        &self,
+        owner: Uuid,
+        recipe: CreateRecipeSchema,
+    ) -> crate::Result<RecipeModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO recipes (
+                recipe_id,
+                name,
+                description,
+                method,
+                media_id,
+                user_id
+            ) VALUES (
+                ?1,
+                lower(?2),
+                ?3,
+                ?4,
+                ?5,
+                ?6
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(recipe.name)
+        .bind(recipe.description)
+        .bind(recipe.method)
+        .bind(recipe.thumbnail_id)
+        .bind(owner)
+        .execute(&self.pool)
+        .await?;
+
+        self.retrieve_recipe(owner, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn list_recipes(&self, owner: Uuid) -> crate::Result<Vec<RecipeModel>> {
+        let rows: Vec<RecipeRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                r.recipe_id AS id,
+                r.name,
+                r.description,
+                r.method,
+                r.user_id AS owner,
+                r.created_at,
+                {THUMBNAIL_COLUMNS}
+            FROM recipes r
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE r.user_id = ?1
+            "#
+        ))
+        .bind(owner)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(RecipeModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_recipe(&self, owner: Uuid, id: Uuid) -> crate::Result<RecipeModel> {
+        let row: RecipeRow = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                r.recipe_id AS id,
+                r.name,
+                r.description,
+                r.method,
+                r.user_id AS owner,
+                r.created_at,
+                {THUMBNAIL_COLUMNS}
+            FROM recipes r
+            LEFT JOIN media m USING (user_id, media_id)
+            WHERE r.user_id = ?1
+                AND r.recipe_id = ?2
+            "#
+        ))
+        .bind(owner)
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn add_recipe_ingredient(
+        &self,
+        recipe: &RecipeModel,
+        line: CreateRecipeIngredientSchema,
+    ) -> crate::Result<RecipeIngredientModel> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO recipe_ingredients (
+                recipe_ingredient_id,
+                quantity,
+                recipe_id,
+                ingredient_id,
+                unit_id,
+                category_id
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4,
+                ?5,
+                ?6
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(line.quantity)
+        .bind(recipe.id)
+        .bind(line.ingredient_id)
+        .bind(line.unit_id)
+        .bind(line.category_id)
+        .execute(&self.pool)
+        .await?;
+
+        self.recipe_ingredients(recipe)
+            .await?
+            .into_iter()
+            .find(|line| line.id == id)
+            .ok_or(sqlx::Error::RowNotFound.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn recipe_ingredients(
+        &self,
+        recipe: &RecipeModel,
+    ) -> crate::Result<Vec<RecipeIngredientModel>> {
+        let rows: Vec<RecipeIngredientRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                ri.recipe_ingredient_id,
+                ri.quantity,
+                ri.category_id AS line_category_id,
+                i.ingredient_id AS id,
+                i.name,
+                i.description,
+                i.abv,
+                i.sugar,
+                i.acid,
+                i.shelf_life_id,
+                i.category_id,
+                i.origin,
+                i.producer,
+                i.tags,
+                i.flavours,
+                i.user_id AS owner,
+                i.created_at,
+                {THUMBNAIL_COLUMNS},
+                {UNIT_COLUMNS}
+            FROM recipe_ingredients ri
+            JOIN recipes r ON r.recipe_id = ri.recipe_id
+            JOIN ingredients i ON i.ingredient_id = ri.ingredient_id
+            LEFT JOIN media m ON m.media_id = i.media_id
+            JOIN units u ON u.unit_id = ri.unit_id
+            LEFT JOIN unit_systems us USING (unit_system_id)
+            WHERE r.user_id = ?1
+                AND ri.recipe_id = ?2
+            ORDER BY ri.created_at, ri.recipe_ingredient_id
+            "#
+        ))
+        .bind(recipe.owner)
+        .bind(recipe.id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(RecipeIngredientModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self, store))]
+    async fn create_media<'a>(
+        &self,
+        owner: Uuid,
+        hash: &str,
+        media: CreateMediaSchema,
+        limits: &Limits,
+        store: StoreBlob<'a>,
+    ) -> crate::Result<MediaModel> {
+        // The transaction holds the write lock, so concurrent uploads are
+        // checked against their limits one at a time.
+        let mut tx = self.begin().await?;
+        usage(&mut *tx, owner, limits.storage_quota)
+            .await?
+            .check_upload(media.size, limits)?;
+        sqlx::query(
+            r#"
+            INSERT INTO uploads (
+                upload_id,
+                size,
+                user_id
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3
+            )
+            "#,
+        )
+        .bind(Uuid::new_v4())
+        .bind(media.size)
+        .bind(owner)
+        .execute(&mut *tx)
+        .await?;
+
+        let blob: BlobModel = sqlx::query_as(&format!(
+            r#"
+            INSERT INTO blobs (
+                blob_id,
+                hash,
+                object_key,
+                size,
+                reference_count
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4,
+                1
+            )
+            ON CONFLICT (hash) DO UPDATE
+            SET reference_count = reference_count + 1
+            RETURNING {BLOB_COLUMNS}
+            "#
+        ))
+        .bind(Uuid::new_v4())
+        .bind(hash)
+        .bind(BlobModel::key_for_hash(hash))
+        .bind(media.size)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if blob.reference_count == 1 {
+            store(blob.key).await?;
+        }
+
+        let id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO media (
+                media_id,
+                size,
+                mime_type,
+                width,
+                height,
+                blurhash,
+                dominant_color,
+                blob_id,
+                user_id
+            ) VALUES (
+                ?1,
+                ?2,
+                ?3,
+                ?4,
+                ?5,
+                ?6,
+                ?7,
+                ?8,
+                ?9
+            )
+            "#,
+        )
+        .bind(id)
+        .bind(media.size)
+        .bind(media.content_type)
+        .bind(media.width)
+        .bind(media.height)
+        .bind(media.blurhash)
+        .bind(media.dominant_color)
+        .bind(blob.id)
+        .bind(owner)
+        .execute(&mut *tx)
+        .await?;
+
+        let media = Self::retrieve_media_on(&mut tx, owner, id).await?;
+        tx.commit().await?;
+
+        Ok(media)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_media(&self, owner: Uuid, id: Uuid) -> crate::Result<MediaModel> {
+        let mut conn = self.pool.acquire().await?;
+        Self::retrieve_media_on(&mut conn, owner, id).await
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn retrieve_blob(&self, id: Uuid) -> crate::Result<BlobModel> {
+        sqlx::query_as(&format!(
+            r#"
+            SELECT {BLOB_COLUMNS}
+            FROM blobs
+            WHERE blob_id = ?1
+            "#
+        ))
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn media_library(
+        &self,
+        owner: Uuid,
+        content_type: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> crate::Result<Vec<MediaLibraryModel>> {
+        let rows: Vec<MediaLibraryRow> = sqlx::query_as(&format!(
+            r#"
+            SELECT
+                {MEDIA_COLUMNS},
+                (
+                    SELECT group_concat(hex(b.bar_id))
+                    FROM bars b
+                    WHERE b.user_id = m.user_id
+                        AND b.media_id = m.media_id
+                ) AS bars,
+                (
+                    SELECT group_concat(hex(i.ingredient_id))
+                    FROM ingredients i
+                    WHERE i.user_id = m.user_id
+                        AND i.media_id = m.media_id
+                ) AS ingredients,
+                (
+                    SELECT group_concat(hex(r.recipe_id))
+                    FROM recipes r
+                    WHERE r.user_id = m.user_id
+                        AND r.media_id = m.media_id
+                ) AS recipes,
+                (
+                    SELECT group_concat(hex(p.profile_id))
+                    FROM profiles p
+                    WHERE p.user_id = m.user_id
+                        AND p.media_id = m.media_id
+                ) AS profiles
+            FROM media m
+            WHERE m.user_id = ?1
+                AND (?2 IS NULL OR m.mime_type LIKE ?2 ESCAPE '\')
+            ORDER BY m.created_at DESC, m.media_id
+            LIMIT ?3
+            OFFSET ?4
+            "#
+        ))
+        .bind(owner)
+        .bind(content_type)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(MediaLibraryModel::from).collect())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn count_media(&self, owner: Uuid, content_type: Option<&str>) -> crate::Result<i64> {
+        sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*)
+            FROM media m
+            WHERE m.user_id = ?1
+                AND (?2 IS NULL OR m.mime_type LIKE ?2 ESCAPE '\')
+            "#,
+        )
+        .bind(owner)
+        .bind(content_type)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| e.into())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_media(&self, owner: Uuid, id: Uuid) -> crate::Result<Vec<Uuid>> {
+        let mut tx = self.begin().await?;
+        let blob_id: Uuid = sqlx::query_scalar(
+            r#"
+            DELETE FROM media
+            WHERE user_id = ?1
+                AND media_id = ?2
+            RETURNING blob_id
+            "#,
+        )
+        .bind(owner)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let orphaned = release_blobs(&mut tx, &[blob_id]).await?;
+        tx.commit().await?;
+
+        Ok(orphaned)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_media_list(
+        &self,
+        owner: Uuid,
+        ids: &[Uuid],
+    ) -> crate::Result<DeletedMediaModel> {
+        // Any media that is not the user's fails the whole deletion.
+        let mut tx = self.begin().await?;
+        let mut media = Vec::new();
+        for id in ids {
+            let deleted: MediaModel = sqlx::query_as(
+                r#"
+                DELETE FROM media
+                WHERE user_id = ?1
+                    AND media_id = ?2
+                RETURNING
+                    media_id AS id,
+                    size,
+                    mime_type AS content_type,
+                    user_id AS owner,
+                    created_at,
+                    width,
+                    height,
+                    blurhash,
+                    dominant_color,
+                    blob_id
+                "#,
+            )
+            .bind(owner)
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+            media.push(deleted);
+        }
+
+        let blob_ids: Vec<Uuid> = media.iter().map(|m| m.blob_id).collect();
+        let orphaned = release_blobs(&mut tx, &blob_ids).await?;
+        tx.commit().await?;
+
+        Ok(DeletedMediaModel { media, orphaned })
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn delete_unused_media(&self, owner: Uuid) -> crate::Result<DeletedMediaModel> {
+        let mut tx = self.begin().await?;
+        let media: Vec<MediaModel> = sqlx::query_as(&format!(
+            r#"
+            DELETE FROM media
+            WHERE user_id = ?1
+                AND {UNUSED_MEDIA}
+            RETURNING
+                media_id AS id,
+                size,
+                mime_type AS content_type,
+                user_id AS owner,
+                created_at,
+                width,
+                height,
+                blurhash,
+                dominant_color,
+                blob_id
+            "#
+        ))
+        .bind(owner)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        let blob_ids: Vec<Uuid> = media.iter().map(|m| m.blob_id).collect();
+        let orphaned = release_blobs(&mut tx, &blob_ids).await?;
+        tx.commit().await?;
+
+        Ok(DeletedMediaModel { media, orphaned })
+    }
+
+    #[tracing::instrument(skip(self, remove))]
+    async fn remove_blob<'a>(&self, id: Uuid, remove: RemoveBlob<'a>) -> crate::Result<()> {
+        // The write lock is held from the start, so no upload can take the
+        // blob up again until the object is gone.
+        let mut tx = self.begin().await?;
+        let key: Option<String> = sqlx::query_scalar(
+            r#"
+            DELETE FROM blobs
+            WHERE blob_id = ?1
+                AND reference_count = 0
+            RETURNING object_key
+            "#,
+        )
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(key) = key {
+            remove(key).await?;
+        }
+        tx.commit().await?;
+
+        Ok(())
+    }
+}
+
+async fn usage<'a, E>(executor: E, owner: Uuid, default_quota: i64) -> crate::Result<UsageModel>
+where
+    E: Executor<'a, Database = Sqlite>,
+{
+    sqlx::query_as(
+        r#"
+        SELECT
+            COALESCE(SUM(m.size), 0) AS used,
+            COALESCE(u.storage_quota, ?2) AS quota,
+            MAX(COALESCE(u.storage_quota, ?2) - COALESCE(SUM(m.size), 0), 0) AS remaining,
+            COUNT(m.media_id) AS media_count,
+            (
+                SELECT COUNT(*)
+                FROM uploads up
+                WHERE up.user_id = u.user_id
+                    AND up.created_at > strftime('%Y-%m-%dT%H:%M:%f', 'now', '-1 hour')
+            ) AS recent_uploads
+        FROM users u
+        LEFT JOIN media m USING (user_id)
+        WHERE u.user_id = ?1
+        GROUP BY u.user_id
+        "#,
+    )
+    .bind(owner)
+    .bind(default_quota)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| e.into())
+}
+
+async fn release_blobs(conn: &mut SqliteConnection, blob_ids: &[Uuid]) -> crate::Result<Vec<Uuid>> {
+    let mut orphaned = Vec::new();
+
+    for blob_id in blob_ids {
+        let blob: BlobModel = sqlx::query_as(&format!(
+            r#"
+            UPDATE blobs
+            SET reference_count = reference_count - 1
+            WHERE blob_id = ?1
+            RETURNING {BLOB_COLUMNS}
+            "#
+        ))
+        .bind(blob_id)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        if blob.reference_count == 0 {
+            orphaned.push(blob.id);
+        }
+    }
+
+    Ok(orphaned)
+}
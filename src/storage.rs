@@ -0,0 +1,214 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_s3::{config::Credentials, primitives::ByteStream};
+use axum::{body::Body, http::StatusCode};
+use tokio::{fs::File, io::AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::{monitoring::observe_s3, Error, S3Config};
+
+#[async_trait]
+pub(crate) trait ObjectStore: Send + Sync {
+    async fn put(&self, key: &str, path: &Path, content_type: &str) -> crate::Result<()>;
+
+    async fn get(&self, key: &str) -> crate::Result<Body>;
+
+    async fn delete(&self, key: &str) -> crate::Result<()>;
+
+    async fn ping(&self) -> crate::Result<()>;
+}
+
+pub(crate) struct S3Store {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+#[async_trait]
+impl ObjectStore for S3Store {
+    async fn put(&self, key: &str, path: &Path, content_type: &str) -> crate::Result<()> {
+        let body = ByteStream::from_path(path)
+            .await
+            .map_err(|e| Error::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        observe_s3(
+            "put_object",
+            self.client
+                .put_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .content_type(content_type)
+                .body(body)
+                .send(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> crate::Result<Body> {
+        let reader = observe_s3(
+            "get_object",
+            self.client
+                .get_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .send(),
+        )
+        .await?
+        .body
+        .into_async_read();
+
+        Ok(Body::from_stream(ReaderStream::new(reader)))
+    }
+
+    async fn delete(&self, key: &str) -> crate::Result<()> {
+        observe_s3(
+            "delete_object",
+            self.client
+                .delete_object()
+                .bucket(&self.bucket)
+                .key(key)
+                .send(),
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn ping(&self) -> crate::Result<()> {
+        observe_s3(
+            "head_bucket",
+            self.client.head_bucket().bucket(&self.bucket).send(),
+        )
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MemoryStore {
+    objects: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+#[async_trait]
+impl ObjectStore for MemoryStore {
+    async fn put(&self, key: &str, path: &Path, _content_type: &str) -> crate::Result<()> {
+        let bytes = tokio::fs::read(path).await?;
+        self.objects
+            .lock()
+            .expect("memory store lock poisoned")
+            .insert(key.to_string(), bytes);
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> crate::Result<Body> {
+        self.objects
+            .lock()
+            .expect("memory store lock poisoned")
+            .get(key)
+            .map(|bytes| Body::from(bytes.clone()))
+            .ok_or_else(|| Error::new(StatusCode::NOT_FOUND, "object not found"))
+    }
+
+    async fn delete(&self, key: &str) -> crate::Result<()> {
+        self.objects
+            .lock()
+            .expect("memory store lock poisoned")
+            .remove(key);
+
+        Ok(())
+    }
+
+    async fn ping(&self) -> crate::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct Storage {
+    pub(crate) store: Arc<dyn ObjectStore>,
+}
+
+impl Storage {
+    pub async fn s3(config: &S3Config) -> Result<Self, aws_sdk_s3::Error> {
+        let cred = Credentials::new(
+            &config.access_key_id,
+            &config.secret_access_key,
+            None,
+            None,
+            "loaded-from-custom-env",
+        );
+        let s3_config = aws_sdk_s3::config::Builder::new()
+            .behavior_version(BehaviorVersion::latest())
+            .endpoint_url(&config.url)
+            .credentials_provider(cred)
+            .region(Region::new(config.region.clone()))
+            .force_path_style(true)
+            .build();
+        let client = aws_sdk_s3::Client::from_conf(s3_config);
+
+        if let Err(e) = client.head_bucket().bucket(&config.bucket).send().await
+            && e.into_service_error().is_not_found()
+        {
+            client.create_bucket().bucket(&config.bucket).send().await?;
+        }
+
+        Ok(Self {
+            store: Arc::new(S3Store {
+                client,
+                bucket: config.bucket.clone(),
+            }),
+        })
+    }
+
+    pub fn memory() -> Self {
+        Self {
+            store: Arc::new(MemoryStore::default()),
+        }
+    }
+}
+
+/// Spooled to a temporary file so large uploads are never held in memory.
+pub(crate) struct Spool {
+    path: PathBuf,
+    file: File,
+}
+
+impl Spool {
+    pub async fn create() -> crate::Result<Self> {
+        let path = std::env::temp_dir().join(format!("tapster-upload-{}", Uuid::new_v4()));
+        let file = File::create(&path).await?;
+
+        Ok(Self { path, file })
+    }
+
+    pub async fn write(&mut self, chunk: &[u8]) -> crate::Result<()> {
+        self.file.write_all(chunk).await?;
+
+        Ok(())
+    }
+
+    pub async fn finish(&mut self) -> crate::Result<&Path> {
+        self.file.flush().await?;
+
+        Ok(&self.path)
+    }
+}
+
+impl Drop for Spool {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::warn!(path = %self.path.display(), error = %e, "failed to remove spooled upload");
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+use crate::{LogConfig, LogFormat};
+
+/// Call [`Telemetry::shutdown`] before exiting so buffered spans are flushed.
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+impl Telemetry {
+    pub fn init(config: &LogConfig) -> Self {
+        let filter = EnvFilter::new(&config.level);
+        let json = matches!(config.format, LogFormat::Json);
+
+        #[cfg(feature = "otlp")]
+        let (provider, otlp) = match &config.otlp_endpoint {
+            Some(endpoint) => {
+                let (provider, layer) = otlp_layer(endpoint);
+                (Some(provider), Some(layer))
+            }
+            None => (None, None),
+        };
+
+        let registry = tracing_subscriber::registry()
+            .with(filter)
+            .with(json.then(|| {
+                fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_span_list(false)
+            }))
+            .with((!json).then(fmt::layer));
+
+        #[cfg(feature = "otlp")]
+        let registry = registry.with(otlp);
+
+        registry.init();
+
+        Self {
+            #[cfg(feature = "otlp")]
+            provider,
+        }
+    }
+
+    pub fn shutdown(self) {
+        #[cfg(feature = "otlp")]
+        if let Some(provider) = self.provider
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("failed to flush traces: {e}");
+        }
+    }
+}
+
+#[cfg(feature = "otlp")]
+fn otlp_layer<S>(
+    endpoint: &str,
+) -> (
+    opentelemetry_sdk::trace::SdkTracerProvider,
+    tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>,
+)
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry_otlp::WithExportConfig;
+
+    let exporter = opentelemetry_otlp::SpanExporter::builder()
+        .with_tonic()
+        .with_endpoint(endpoint)
+        .build()
+        .expect("failed to build otlp span exporter");
+    let provider = opentelemetry_sdk::trace::SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(
+            opentelemetry_sdk::Resource::builder()
+                .with_service_name("tapster-api")
+                .build(),
+        )
+        .build();
+    let tracer = provider.tracer("tapster-api");
+
+    (provider, tracing_opentelemetry::layer().with_tracer(tracer))
+}
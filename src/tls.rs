@@ -0,0 +1,78 @@
+use std::{
+    io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use axum::{
+    http::{header, uri::Authority, HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use axum_server::tls_rustls::RustlsConfig;
+
+use crate::TlsConfig;
+
+const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+pub async fn load_certificate(config: &TlsConfig) -> io::Result<RustlsConfig> {
+    RustlsConfig::from_pem_file(&config.cert_path, &config.key_path).await
+}
+
+/// Handshakes keep the previous certificate if a changed one fails to load.
+pub async fn watch_certificate(rustls: RustlsConfig, config: TlsConfig) {
+    let files = [config.cert_path.clone(), config.key_path.clone()];
+    let mut loaded = modified(&files);
+    let mut interval = tokio::time::interval(RELOAD_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let current = modified(&files);
+        if current == loaded {
+            continue;
+        }
+
+        match rustls
+            .reload_from_pem_file(&config.cert_path, &config.key_path)
+            .await
+        {
+            Ok(()) => {
+                tracing::info!("reloaded tls certificate");
+                loaded = current;
+            }
+            Err(e) => tracing::warn!(error = %e, "failed to reload tls certificate"),
+        }
+    }
+}
+
+fn modified(files: &[PathBuf; 2]) -> [Option<SystemTime>; 2] {
+    files
+        .each_ref()
+        .map(|path| path.metadata().and_then(|m| m.modified()).ok())
+}
+
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(move |headers: HeaderMap, uri: Uri| async move {
+        redirect_to_https(&headers, &uri, https_port)
+    })
+}
+
+fn redirect_to_https(headers: &HeaderMap, uri: &Uri, https_port: u16) -> Response {
+    let host = headers
+        .get(header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.parse::<Authority>().ok())
+        .or_else(|| uri.authority().cloned());
+    let Some(host) = host else {
+        return (StatusCode::BAD_REQUEST, "missing host header").into_response();
+    };
+
+    let authority = match https_port {
+        443 => host.host().to_string(),
+        port => format!("{}:{port}", host.host()),
+    };
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+
+    Redirect::permanent(&format!("https://{authority}{path}")).into_response()
+}
@@ -0,0 +1,79 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+use common::TestApp;
+
+mod common;
+
+#[tokio::test]
+async fn registering_returns_a_working_token() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let response = app.get("/me/usage", Some(&user.token)).await;
+
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["media_count"], 0);
+}
+
+#[tokio::test]
+async fn signing_in_returns_a_token_for_the_same_user() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    app.post("/bars", Some(&user.token), json!({ "name": "home bar" }))
+        .await;
+
+    let response = app.post("/sign-in", None, json!({ "id": user.id })).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let token = response.json()["accessKey"].as_str().unwrap().to_string();
+    let bars = app.get("/bars", Some(&token)).await.json();
+    assert_eq!(bars.as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn signing_in_as_an_unknown_user_is_not_found() {
+    let app = TestApp::spawn().await;
+
+    let response = app
+        .post("/sign-in", None, json!({ "id": Uuid::new_v4() }))
+        .await;
+
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn requests_without_a_token_are_unauthorized() {
+    let app = TestApp::spawn().await;
+
+    assert_eq!(
+        app.get("/bars", None).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        app.get("/ingredients", None).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        app.get("/media", None).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(
+        app.get("/me/usage", None).await.status,
+        StatusCode::UNAUTHORIZED
+    );
+}
+
+#[tokio::test]
+async fn invalid_tokens_are_unauthorized() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (payload, _) = user.token.rsplit_once('.').unwrap();
+    let forged = format!("{payload}.c2lnbmF0dXJl");
+
+    for token in ["not-a-token", "a.b.c", forged.as_str()] {
+        let response = app.get("/bars", Some(token)).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "token {token}");
+    }
+}
@@ -0,0 +1,162 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+use common::{png, TestApp};
+
+mod common;
+
+#[tokio::test]
+async fn creates_and_retrieves_a_bar() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let created = app
+        .post("/bars", Some(&user.token), json!({ "name": "Home Bar" }))
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+
+    let bar = created.json();
+    assert_eq!(bar["name"], "home bar");
+    assert_eq!(bar["owner"], user.id.to_string());
+    assert!(bar["thumbnail"].is_null());
+
+    let retrieved = app
+        .get(
+            &format!("/bars/{}", bar["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(retrieved.status, StatusCode::OK);
+    assert_eq!(retrieved.json(), bar);
+}
+
+#[tokio::test]
+async fn creates_a_bar_with_a_thumbnail() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let media = app
+        .upload(&user.token, "image/png", &png(8, 8, [200, 30, 30]))
+        .await
+        .json();
+
+    let bar = app
+        .post(
+            "/bars",
+            Some(&user.token),
+            json!({ "name": "home bar", "thumbnailId": media["id"] }),
+        )
+        .await;
+
+    assert_eq!(bar.status, StatusCode::CREATED);
+    assert_eq!(bar.json()["thumbnail"], media);
+}
+
+#[tokio::test]
+async fn rejects_invalid_bars() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let too_short = app
+        .post("/bars", Some(&user.token), json!({ "name": "x" }))
+        .await;
+    assert_eq!(too_short.status, StatusCode::BAD_REQUEST);
+
+    let missing_thumbnail = app
+        .post(
+            "/bars",
+            Some(&user.token),
+            json!({ "name": "home bar", "thumbnailId": Uuid::new_v4() }),
+        )
+        .await;
+    assert_eq!(missing_thumbnail.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn unknown_bar_is_not_found() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let response = app
+        .get(&format!("/bars/{}", Uuid::new_v4()), Some(&user.token))
+        .await;
+
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn bars_of_another_user_are_hidden() {
+    let app = TestApp::spawn().await;
+    let owner = app.register().await;
+    let other = app.register().await;
+
+    let bar = app
+        .post("/bars", Some(&owner.token), json!({ "name": "home bar" }))
+        .await
+        .json();
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    let response = app.get(&uri, Some(&other.token)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let listed = app.get("/bars", Some(&other.token)).await.json();
+    assert_eq!(listed, json!([]));
+
+    let listed = app.get("/bars", Some(&owner.token)).await.json();
+    assert_eq!(listed, json!([bar]));
+}
+
+#[tokio::test]
+async fn stocks_ingredients() {
+    let app = TestApp::spawn().await;
+    let owner = app.register().await;
+    let other = app.register().await;
+
+    let bar = app
+        .post("/bars", Some(&owner.token), json!({ "name": "home bar" }))
+        .await
+        .json();
+    let uri = format!("/bars/{}/ingredients", bar["id"].as_str().unwrap());
+    let gin = app
+        .post(
+            "/ingredients",
+            Some(&owner.token),
+            json!({ "name": "gin", "description": "" }),
+        )
+        .await
+        .json();
+    let stock =
+        json!({ "ingredientId": gin["id"], "quantity": 700, "unitId": app.unit_id("ml").await });
+
+    let created = app.post(&uri, Some(&owner.token), stock.clone()).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+
+    let created = created.json();
+    assert_eq!(created["bar_id"], bar["id"]);
+    assert_eq!(created["quantity"], 700.0);
+    assert_eq!(created["ingredient"], gin);
+    assert_eq!(created["unit"]["abbreviation"], "ml");
+    assert_eq!(
+        app.get(&uri, Some(&owner.token)).await.json(),
+        json!([created])
+    );
+
+    let again = app.post(&uri, Some(&owner.token), stock.clone()).await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+
+    let response = app.get(&uri, Some(&other.token)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let others_bar = app
+        .post("/bars", Some(&other.token), json!({ "name": "other bar" }))
+        .await
+        .json();
+    let response = app
+        .post(
+            &format!("/bars/{}/ingredients", others_bar["id"].as_str().unwrap()),
+            Some(&other.token),
+            stock,
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
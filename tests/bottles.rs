@@ -0,0 +1,354 @@
+use axum::http::StatusCode;
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde_json::{json, Value};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+async fn shelf_life(app: &TestApp, user: &TestUser, name: &str, days: i32) -> Value {
+    let shelf_life = app
+        .post(
+            "/shelf-lives",
+            Some(&user.token),
+            json!({ "name": name, "days": days }),
+        )
+        .await;
+    assert_eq!(shelf_life.status, StatusCode::CREATED);
+    shelf_life.json()
+}
+
+async fn stock(
+    app: &TestApp,
+    user: &TestUser,
+    bar: &Value,
+    name: &str,
+    shelf_life: Option<&Value>,
+    bottle_size: Option<f64>,
+) -> Value {
+    let ingredient = app
+        .create_ingredient(
+            user,
+            name,
+            json!({ "shelfLifeId": shelf_life.map(|s| s["id"].clone()) }),
+        )
+        .await;
+
+    app.stock_ingredient(
+        user,
+        bar,
+        &ingredient,
+        0.0,
+        "ml",
+        json!({ "bottleSize": bottle_size }),
+    )
+    .await
+}
+
+async fn bottle(
+    app: &TestApp,
+    user: &TestUser,
+    uri: &str,
+    stock: &Value,
+    days_ago: Option<i64>,
+) -> Value {
+    let opened_at = days_ago.map(|days| {
+        (Utc::now() - Duration::days(days))
+            .naive_utc()
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string()
+    });
+    let bottle = app
+        .post(
+            &format!("{uri}/bottles"),
+            Some(&user.token),
+            json!({ "barIngredientId": stock["id"], "openedAt": opened_at }),
+        )
+        .await;
+    assert_eq!(bottle.status, StatusCode::CREATED);
+    bottle.json()
+}
+
+#[tokio::test]
+async fn tracks_bottles() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+    let gin = stock(&app, &user, &bar, "gin", None, Some(700.0)).await;
+
+    let created = app
+        .post(
+            &format!("{uri}/bottles"),
+            Some(&user.token),
+            json!({
+                "barIngredientId": gin["id"],
+                "fill": 0.5,
+                "location": "back bar",
+                "openedAt": "2025-01-01T18:00:00",
+            }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+
+    // The size defaults to the bottle size of the bar ingredient.
+    let created = created.json();
+    assert_eq!(created["name"], "gin");
+    assert_eq!(created["size"], 700.0);
+    assert_eq!(created["fill"], 0.5);
+    assert_eq!(created["unit"]["abbreviation"], "ml");
+    assert_eq!(created["location"], "back bar");
+    assert_eq!(created["opened_at"], "2025-01-01T18:00:00");
+    assert!(created["expires_at"].is_null());
+
+    let sealed = app
+        .post(
+            &format!("{uri}/bottles"),
+            Some(&user.token),
+            json!({ "barIngredientId": gin["id"], "size": 1000 }),
+        )
+        .await
+        .json();
+    assert_eq!(sealed["size"], 1000.0);
+    assert_eq!(sealed["fill"], 1.0);
+    assert!(sealed["opened_at"].is_null());
+
+    let listed = app
+        .get(&format!("{uri}/bottles"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+    assert_eq!(listed[0]["id"], created["id"]);
+
+    let sealed_uri = format!("{uri}/bottles/{}", sealed["id"].as_str().unwrap());
+    let updated = app
+        .put(
+            &sealed_uri,
+            Some(&user.token),
+            json!({ "fill": 0.75, "location": "well", "openedAt": "2025-02-01T12:00:00" }),
+        )
+        .await;
+    assert_eq!(updated.status, StatusCode::OK);
+
+    let updated = updated.json();
+    assert_eq!(updated["fill"], 0.75);
+    assert_eq!(updated["location"], "well");
+    assert_eq!(updated["opened_at"], "2025-02-01T12:00:00");
+
+    let deleted = app.delete(&sealed_uri, Some(&user.token)).await;
+    assert_eq!(deleted.status, StatusCode::NO_CONTENT);
+    let response = app.get(&sealed_uri, Some(&user.token)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn lists_bottles_expiring_soon() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    // Vermouth, fresh juice and syrup are shared by every user.
+    let sherry = shelf_life(&app, &user, "sherry", 21).await;
+    let listed = app.get("/shelf-lives", Some(&user.token)).await.json();
+    let listed = listed.as_array().unwrap();
+    let names: Vec<&str> = listed.iter().map(|s| s["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["fresh juice", "sherry", "syrup", "vermouth"]);
+    let shared = |name: &str| {
+        let shelf_life = listed.iter().find(|s| s["name"] == name).unwrap();
+        assert!(shelf_life["owner"].is_null());
+        shelf_life.clone()
+    };
+    let (vermouth, juice, syrup) = (shared("vermouth"), shared("fresh juice"), shared("syrup"));
+    assert_eq!(vermouth["days"], 30);
+    assert!(!sherry["owner"].is_null());
+
+    let dry = stock(
+        &app,
+        &user,
+        &bar,
+        "dry vermouth",
+        Some(&vermouth),
+        Some(750.0),
+    )
+    .await;
+    let lime = stock(&app, &user, &bar, "lime juice", Some(&juice), Some(250.0)).await;
+    let simple = stock(&app, &user, &bar, "simple syrup", Some(&syrup), Some(500.0)).await;
+    let gin = stock(&app, &user, &bar, "gin", None, Some(700.0)).await;
+
+    // The juice went off yesterday and the vermouth goes off in five days.
+    // The syrup keeps for two weeks, sealed bottles and gin do not expire.
+    let old_dry = bottle(&app, &user, &uri, &dry, Some(25)).await;
+    bottle(&app, &user, &uri, &dry, None).await;
+    let old_lime = bottle(&app, &user, &uri, &lime, Some(2)).await;
+    bottle(&app, &user, &uri, &simple, Some(0)).await;
+    bottle(&app, &user, &uri, &gin, Some(365)).await;
+
+    let expiring = app
+        .get(&format!("{uri}/bottles/expiring"), Some(&user.token))
+        .await;
+    assert_eq!(expiring.status, StatusCode::OK);
+
+    let expiring = expiring.json();
+    let ids: Vec<&Value> = expiring
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| &b["id"])
+        .collect();
+    assert_eq!(ids, [&old_lime["id"], &old_dry["id"]]);
+    let opened_at: NaiveDateTime = serde_json::from_value(old_dry["opened_at"].clone()).unwrap();
+    let expires_at: NaiveDateTime =
+        serde_json::from_value(expiring[1]["expires_at"].clone()).unwrap();
+    assert_eq!(expires_at - opened_at, Duration::days(30));
+
+    let expiring = app
+        .get(
+            &format!("{uri}/bottles/expiring?days=14"),
+            Some(&user.token),
+        )
+        .await
+        .json();
+    assert_eq!(expiring.as_array().unwrap().len(), 3);
+    assert_eq!(expiring[2]["name"], "simple syrup");
+
+    // An empty bottle no longer expires.
+    let old_lime_uri = format!("{uri}/bottles/{}", old_lime["id"].as_str().unwrap());
+    app.put(
+        &old_lime_uri,
+        Some(&user.token),
+        json!({ "fill": 0, "openedAt": old_lime["opened_at"] }),
+    )
+    .await;
+    let expiring = app
+        .get(&format!("{uri}/bottles/expiring"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(expiring.as_array().unwrap().len(), 1);
+
+    // Taking the shelf life away from the vermouth stops it expiring too.
+    let cleared = app
+        .put(
+            &format!(
+                "/ingredients/{}/shelf-life",
+                dry["ingredient"]["id"].as_str().unwrap()
+            ),
+            Some(&user.token),
+            json!({ "shelfLifeId": null }),
+        )
+        .await;
+    assert_eq!(cleared.status, StatusCode::OK);
+    assert!(cleared.json()["shelf_life_id"].is_null());
+
+    let expiring = app
+        .get(&format!("{uri}/bottles/expiring"), Some(&user.token))
+        .await
+        .json();
+    assert!(expiring.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn expires_catalogue_ingredients() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    let catalogue = app
+        .get("/ingredients?catalogue=true", Some(&user.token))
+        .await
+        .json();
+    let dry = catalogue
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["name"] == "dry vermouth")
+        .unwrap();
+    assert!(!dry["shelf_life_id"].is_null());
+
+    let stocked = app
+        .stock_ingredient(&user, &bar, dry, 0.0, "ml", json!({ "bottleSize": 750 }))
+        .await;
+    let opened = bottle(&app, &user, &uri, &stocked, Some(25)).await;
+
+    let expiring = app
+        .get(&format!("{uri}/bottles/expiring"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(expiring.as_array().unwrap().len(), 1);
+    assert_eq!(expiring[0]["id"], opened["id"]);
+}
+
+#[tokio::test]
+async fn rejects_invalid_bottles() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+    let gin = stock(&app, &user, &bar, "gin", None, None).await;
+
+    for bottle in [
+        json!({ "barIngredientId": gin["id"] }),
+        json!({ "barIngredientId": gin["id"], "size": 0 }),
+        json!({ "barIngredientId": gin["id"], "size": 700, "fill": 1.5 }),
+        json!({ "barIngredientId": gin["id"], "size": 700, "location": "x".repeat(65) }),
+    ] {
+        let response = app
+            .post(&format!("{uri}/bottles"), Some(&user.token), bottle.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{bottle}");
+    }
+
+    let response = app
+        .post(
+            &format!("{uri}/bottles"),
+            Some(&other.token),
+            json!({ "barIngredientId": gin["id"], "size": 700 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    for days in [-1, 3651] {
+        let response = app
+            .get(
+                &format!("{uri}/bottles/expiring?days={days}"),
+                Some(&user.token),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{days}");
+    }
+
+    let response = app
+        .post(
+            "/shelf-lives",
+            Some(&user.token),
+            json!({ "name": "vermouth", "days": 0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    // Neither the user's own shelf lives nor the shared ones can be named
+    // twice.
+    let sherry = shelf_life(&app, &user, "sherry", 21).await;
+    for name in ["Sherry", "Vermouth"] {
+        let response = app
+            .post(
+                "/shelf-lives",
+                Some(&user.token),
+                json!({ "name": name, "days": 14 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::CONFLICT, "{name}");
+    }
+
+    // Another user's shelf life cannot be given to an ingredient.
+    let response = app
+        .post(
+            "/ingredients",
+            Some(&other.token),
+            json!({ "name": "sherry", "description": "", "shelfLifeId": sherry["id"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,263 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+async fn catalogue(app: &TestApp, user: &TestUser, name: &str) -> Value {
+    app.get("/ingredients?catalogue=true", Some(&user.token))
+        .await
+        .json()
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["name"] == name)
+        .unwrap_or_else(|| panic!("{name} is not in the catalogue"))
+        .clone()
+}
+
+#[tokio::test]
+async fn lists_the_catalogue_for_every_user() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+
+    app.post(
+        "/ingredients",
+        Some(&user.token),
+        json!({ "name": "house bitters", "description": "" }),
+    )
+    .await;
+
+    let listed = app.get("/ingredients", Some(&user.token)).await;
+    assert_eq!(listed.status, StatusCode::OK);
+    let listed = listed.json();
+    let listed = listed.as_array().unwrap();
+    assert!(listed.iter().any(|i| i["name"] == "house bitters"));
+    assert!(listed
+        .iter()
+        .any(|i| i["name"] == "gin" && i["owner"].is_null()));
+
+    let catalogue = app
+        .get("/ingredients?catalogue=true", Some(&user.token))
+        .await
+        .json();
+    let catalogue = catalogue.as_array().unwrap();
+    assert_eq!(catalogue.len(), listed.len() - 1);
+    assert!(catalogue.iter().all(|i| i["owner"].is_null()));
+
+    let own = app
+        .get("/ingredients?catalogue=false", Some(&user.token))
+        .await
+        .json();
+    assert_eq!(own.as_array().unwrap().len(), 1);
+    assert_eq!(own[0]["owner"], user.id.to_string());
+
+    let gin = catalogue.iter().find(|i| i["name"] == "gin").unwrap();
+    let gin = app
+        .get(
+            &format!("/ingredients/{}", gin["id"].as_str().unwrap()),
+            Some(&other.token),
+        )
+        .await;
+    assert_eq!(gin.status, StatusCode::OK);
+    assert_eq!(gin.json()["abv"], 40.0);
+}
+
+#[tokio::test]
+async fn stocks_and_mixes_catalogue_ingredients() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let ml = app.unit_id("ml").await;
+
+    let gin = catalogue(&app, &user, "gin").await;
+    let bar = app
+        .post("/bars", Some(&user.token), json!({ "name": "home bar" }))
+        .await
+        .json();
+    let stock = app
+        .post(
+            &format!("/bars/{}/ingredients", bar["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({ "ingredientId": gin["id"], "quantity": 700, "unitId": ml }),
+        )
+        .await;
+    assert_eq!(stock.status, StatusCode::CREATED);
+    assert_eq!(stock.json()["ingredient"]["id"], gin["id"]);
+
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Negroni", "description": "", "method": "stirred" }),
+        )
+        .await
+        .json();
+    let uri = format!("/recipes/{}", recipe["id"].as_str().unwrap());
+    for name in ["gin", "campari", "sweet vermouth"] {
+        let ingredient = catalogue(&app, &user, name).await;
+        let line = app
+            .post(
+                &format!("{uri}/ingredients"),
+                Some(&user.token),
+                json!({ "ingredientId": ingredient["id"], "quantity": 30, "unitId": ml }),
+            )
+            .await;
+        assert_eq!(line.status, StatusCode::CREATED, "{name}");
+    }
+
+    // The catalogue's compositions go into the analysis, including the acid
+    // of the vermouth.
+    let analysis = app.get(&format!("{uri}/analysis"), Some(&user.token)).await;
+    assert_eq!(analysis.status, StatusCode::OK);
+    let analysis = analysis.json();
+    assert_eq!(analysis["initial_volume"]["quantity"], 90.0);
+    assert!(analysis["abv"].as_f64().unwrap() > 19.0);
+    assert!(analysis["acid"].as_f64().unwrap() > 0.0);
+}
+
+#[tokio::test]
+async fn catalogue_ingredients_are_read_only() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let gin = catalogue(&app, &user, "gin").await;
+    let uri = format!("/ingredients/{}", gin["id"].as_str().unwrap());
+    let shelf_life = app
+        .post(
+            "/shelf-lives",
+            Some(&user.token),
+            json!({ "name": "spirits", "days": 365 }),
+        )
+        .await
+        .json();
+
+    let response = app
+        .put(
+            &format!("{uri}/shelf-life"),
+            Some(&user.token),
+            json!({ "shelfLifeId": shelf_life["id"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    let vermouth = catalogue(&app, &user, "dry vermouth").await;
+    let response = app
+        .post(
+            &format!("{uri}/ingredients"),
+            Some(&user.token),
+            json!({ "ingredientId": vermouth["id"], "parts": 1 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+
+    // Catalogue ingredients can still be parts of the user's own.
+    let martini = app
+        .post(
+            "/ingredients",
+            Some(&user.token),
+            json!({ "name": "batched martini", "description": "" }),
+        )
+        .await
+        .json();
+    let response = app
+        .post(
+            &format!(
+                "/ingredients/{}/ingredients",
+                martini["id"].as_str().unwrap()
+            ),
+            Some(&user.token),
+            json!({ "ingredientId": gin["id"], "parts": 5 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn forks_catalogue_ingredients() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let gin = catalogue(&app, &user, "gin").await;
+    let fork = app
+        .post(
+            &format!("/ingredients/{}/fork", gin["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(fork.status, StatusCode::CREATED);
+
+    let fork = fork.json();
+    assert_ne!(fork["id"], gin["id"]);
+    assert_eq!(fork["name"], "gin");
+    assert_eq!(fork["description"], gin["description"]);
+    assert_eq!(fork["abv"], 40.0);
+    assert_eq!(fork["owner"], user.id.to_string());
+
+    // The fork is the user's to change, and the catalogue is left alone.
+    let shelf_life = app
+        .post(
+            "/shelf-lives",
+            Some(&user.token),
+            json!({ "name": "spirits", "days": 365 }),
+        )
+        .await
+        .json();
+    let response = app
+        .put(
+            &format!("/ingredients/{}/shelf-life", fork["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({ "shelfLifeId": shelf_life["id"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.json()["shelf_life_id"], shelf_life["id"]);
+    assert!(catalogue(&app, &user, "gin").await["shelf_life_id"].is_null());
+
+    let own = app
+        .get("/ingredients?catalogue=false", Some(&user.token))
+        .await
+        .json();
+    assert_eq!(own.as_array().unwrap().len(), 1);
+
+    // Forking a compound ingredient copies its parts.
+    let martini = app
+        .post(
+            "/ingredients",
+            Some(&user.token),
+            json!({ "name": "batched martini", "description": "" }),
+        )
+        .await
+        .json();
+    let vermouth = catalogue(&app, &user, "dry vermouth").await;
+    let uri = format!("/ingredients/{}", martini["id"].as_str().unwrap());
+    for (ingredient, parts) in [(&gin, 5), (&vermouth, 1)] {
+        app.post(
+            &format!("{uri}/ingredients"),
+            Some(&user.token),
+            json!({ "ingredientId": ingredient["id"], "parts": parts }),
+        )
+        .await;
+    }
+    let copy = app
+        .post(&format!("{uri}/fork"), Some(&user.token), json!({}))
+        .await
+        .json();
+    let parts = app
+        .get(
+            &format!("/ingredients/{}/ingredients", copy["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await
+        .json();
+    let mut parts: Vec<i64> = parts
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["parts"].as_i64().unwrap())
+        .collect();
+    parts.sort();
+    assert_eq!(parts, [1, 5]);
+}
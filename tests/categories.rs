@@ -0,0 +1,334 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+async fn category(app: &TestApp, user: &TestUser, name: &str) -> Value {
+    app.get("/categories", Some(&user.token))
+        .await
+        .json()
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|c| c["name"] == name)
+        .unwrap_or_else(|| panic!("there is no {name} category"))
+        .clone()
+}
+
+async fn names(app: &TestApp, user: &TestUser, uri: &str) -> Vec<String> {
+    let listed = app.get(uri, Some(&user.token)).await;
+    assert_eq!(listed.status, StatusCode::OK, "{uri}");
+
+    let mut names: Vec<String> = listed
+        .json()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    names
+}
+
+#[tokio::test]
+async fn lists_the_taxonomy() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let rye = category(&app, &user, "rye whiskey").await;
+    let whiskey = category(&app, &user, "whiskey").await;
+    let spirit = category(&app, &user, "spirit").await;
+    assert_eq!(rye["parent_id"], whiskey["id"]);
+    assert_eq!(whiskey["parent_id"], spirit["id"]);
+    assert!(spirit["parent_id"].is_null());
+    assert!(rye["owner"].is_null());
+
+    // The catalogue is filed under the taxonomy.
+    let ingredients = app
+        .get("/ingredients?catalogue=true", Some(&user.token))
+        .await
+        .json();
+    let catalogue_rye = ingredients
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|i| i["name"] == "rye whiskey")
+        .unwrap();
+    assert_eq!(catalogue_rye["category_id"], rye["id"]);
+}
+
+#[tokio::test]
+async fn creates_categories_beneath_the_taxonomy() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+    let bourbon = category(&app, &user, "bourbon").await;
+
+    let created = app
+        .post(
+            "/categories",
+            Some(&user.token),
+            json!({ "name": "Bottled in Bond", "parentId": bourbon["id"] }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+
+    let created = created.json();
+    assert_eq!(created["name"], "bottled in bond");
+    assert_eq!(created["parent_id"], bourbon["id"]);
+    assert_eq!(created["owner"], user.id.to_string());
+
+    let listed = app.get("/categories", Some(&other.token)).await.json();
+    assert!(listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|c| c["id"] != created["id"]));
+
+    // Another user cannot file categories beneath it.
+    let response = app
+        .post(
+            "/categories",
+            Some(&other.token),
+            json!({ "name": "single barrel", "parentId": created["id"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .post("/categories", Some(&user.token), json!({ "name": "x" }))
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn filters_ingredients_by_attributes() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let rye = category(&app, &user, "rye whiskey").await;
+    let whiskey = category(&app, &user, "whiskey").await;
+
+    let ingredient = app
+        .post(
+            "/ingredients",
+            Some(&user.token),
+            json!({
+                "name": "Rittenhouse",
+                "description": "",
+                "abv": 50,
+                "categoryId": rye["id"],
+                "origin": " United States ",
+                "producer": "Heaven Hill",
+                "tags": ["House", "house", " overproof "],
+                "flavours": ["spice", "cocoa"],
+            }),
+        )
+        .await;
+    assert_eq!(ingredient.status, StatusCode::CREATED);
+
+    let ingredient = ingredient.json();
+    assert_eq!(ingredient["category_id"], rye["id"]);
+    assert_eq!(ingredient["origin"], "United States");
+    assert_eq!(ingredient["producer"], "Heaven Hill");
+    assert_eq!(ingredient["tags"], json!(["house", "overproof"]));
+    assert_eq!(ingredient["flavours"], json!(["cocoa", "spice"]));
+
+    let uri = |query: &str| format!("/ingredients?{query}");
+    let whiskey_uri = uri(&format!("category={}", whiskey["id"].as_str().unwrap()));
+    assert_eq!(
+        names(&app, &user, &whiskey_uri).await,
+        ["bourbon", "rittenhouse", "rye whiskey", "scotch whisky"]
+    );
+    assert_eq!(names(&app, &user, &uri("tag=HOUSE")).await, ["rittenhouse"]);
+    assert_eq!(
+        names(&app, &user, &uri("flavour=spice")).await,
+        ["green chartreuse", "rittenhouse", "rye whiskey"]
+    );
+    assert_eq!(
+        names(&app, &user, &uri("origin=united%20states")).await,
+        ["bourbon", "rittenhouse", "rye whiskey"]
+    );
+    assert_eq!(
+        names(&app, &user, &uri("producer=heaven%20hill")).await,
+        ["rittenhouse"]
+    );
+    assert_eq!(
+        names(&app, &user, &uri("minAbv=45&catalogue=false")).await,
+        ["rittenhouse"]
+    );
+    assert_eq!(
+        names(&app, &user, &uri("minAbv=45&maxAbv=46")).await,
+        ["bourbon", "rye whiskey"]
+    );
+
+    let response = app
+        .get(&uri(&format!("category={}", user.id)), Some(&user.token))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn sets_ingredient_attributes() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let gin = category(&app, &user, "gin").await;
+
+    let ingredient = app
+        .post(
+            "/ingredients",
+            Some(&user.token),
+            json!({ "name": "house gin", "description": "", "tags": ["house"] }),
+        )
+        .await
+        .json();
+    let uri = format!(
+        "/ingredients/{}/attributes",
+        ingredient["id"].as_str().unwrap()
+    );
+
+    let response = app
+        .put(
+            &uri,
+            Some(&user.token),
+            json!({
+                "categoryId": gin["id"],
+                "origin": "England",
+                "flavours": ["Juniper"],
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let updated = response.json();
+    assert_eq!(updated["category_id"], gin["id"]);
+    assert_eq!(updated["origin"], "England");
+    assert!(updated["producer"].is_null());
+    assert_eq!(updated["tags"], json!([]));
+    assert_eq!(updated["flavours"], json!(["juniper"]));
+
+    let tags: Vec<String> = (0..21).map(|i| format!("tag {i}")).collect();
+    for body in [
+        json!({ "tags": tags }),
+        json!({ "flavours": ["x".repeat(33)] }),
+        json!({ "origin": "x".repeat(65) }),
+    ] {
+        let response = app.put(&uri, Some(&user.token), body.clone()).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{body}");
+    }
+
+    let response = app
+        .put(
+            &uri,
+            Some(&user.token),
+            json!({ "categoryId": ingredient["id"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // The catalogue's attributes are not the user's to change.
+    let catalogue = app
+        .get("/ingredients?catalogue=true", Some(&user.token))
+        .await
+        .json();
+    let response = app
+        .put(
+            &format!(
+                "/ingredients/{}/attributes",
+                catalogue[0]["id"].as_str().unwrap()
+            ),
+            Some(&user.token),
+            json!({ "tags": ["mine"] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn makes_generic_recipe_lines_with_what_the_bar_stocks() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let ml = app.unit_id("ml").await;
+    let rye = category(&app, &user, "rye whiskey").await;
+    let whiskey = category(&app, &user, "whiskey").await;
+
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Manhattan", "description": "", "method": "stirred" }),
+        )
+        .await
+        .json();
+    let lines = format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap());
+
+    // Any rye whiskey, with the catalogue's standing in.
+    let line = app
+        .post(
+            &lines,
+            Some(&user.token),
+            json!({ "categoryId": rye["id"], "quantity": 60, "unitId": ml }),
+        )
+        .await;
+    assert_eq!(line.status, StatusCode::CREATED);
+    let line = line.json();
+    assert_eq!(line["category_id"], rye["id"]);
+    assert_eq!(line["ingredient"]["name"], "rye whiskey");
+    assert!(line["ingredient"]["owner"].is_null());
+
+    let gin = app
+        .post(
+            "/ingredients",
+            Some(&user.token),
+            json!({ "name": "gin", "description": "" }),
+        )
+        .await
+        .json();
+    for body in [
+        json!({ "ingredientId": gin["id"], "categoryId": whiskey["id"], "quantity": 30, "unitId": ml }),
+        json!({ "quantity": 30, "unitId": ml }),
+    ] {
+        let response = app.post(&lines, Some(&user.token), body.clone()).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{body}");
+    }
+
+    // The bar stocks a rye of the user's own, which serving the recipe takes.
+    let own_rye = app
+        .post(
+            "/ingredients",
+            Some(&user.token),
+            json!({ "name": "rittenhouse", "description": "", "categoryId": rye["id"] }),
+        )
+        .await
+        .json();
+    let bar = app
+        .post("/bars", Some(&user.token), json!({ "name": "home bar" }))
+        .await
+        .json();
+    let bar_uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+    app.post(
+        &format!("{bar_uri}/ingredients"),
+        Some(&user.token),
+        json!({ "ingredientId": own_rye["id"], "quantity": 700, "unitId": ml }),
+    )
+    .await;
+
+    let serve = app
+        .post(
+            &format!("{bar_uri}/serves"),
+            Some(&user.token),
+            json!({ "recipeId": recipe["id"], "servings": 1 }),
+        )
+        .await;
+    assert_eq!(serve.status, StatusCode::CREATED);
+    let serve = serve.json();
+    assert_eq!(serve["deductions"].as_array().unwrap().len(), 1);
+    assert_eq!(serve["deductions"][0]["name"], "rittenhouse");
+
+    let stock = app
+        .get(&format!("{bar_uri}/ingredients"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(stock[0]["quantity"], 640.0);
+}
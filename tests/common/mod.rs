@@ -0,0 +1,383 @@
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+use axum::{
+    body::{to_bytes, Body},
+    http::{header, HeaderMap, Method, Request, StatusCode},
+    Router,
+};
+use serde_json::{json, Value};
+use sqlx::{Connection, Executor, PgConnection};
+use tapster_api::{
+    read_products, AppState, Config, ConfigLayer, Database, DatasetFormat, ImportError, Readiness,
+    Storage,
+};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+pub const SIGNING_KEY: &str = "test-signing-key";
+const BOUNDARY: &str = "tapster-test-boundary";
+
+const SQLITE_TARGET: &str = "sqlite";
+
+pub struct TestApp {
+    router: Router,
+    db: Database,
+    pub readiness: Readiness,
+    backend: Option<Backend>,
+}
+
+enum Backend {
+    Postgres { admin_url: String, database: String },
+    Sqlite { path: PathBuf },
+}
+
+impl Backend {
+    async fn create() -> (Self, String) {
+        if env!("CARGO_CRATE_NAME") == SQLITE_TARGET {
+            let path = std::env::temp_dir().join(format!("tapster_test_{}.db", Uuid::new_v4()));
+            let url = format!("sqlite://{}", path.display());
+
+            return (Self::Sqlite { path }, url);
+        }
+
+        let admin_url =
+            std::env::var("DATABASE_URL").expect("DATABASE_URL must point at a Postgres server");
+        let database = format!("tapster_test_{}", Uuid::new_v4().simple());
+
+        let mut conn = PgConnection::connect(&admin_url)
+            .await
+            .expect("failed to connect to postgres");
+        conn.execute(format!(r#"CREATE DATABASE "{database}""#).as_str())
+            .await
+            .expect("failed to create test database");
+        conn.close().await.ok();
+
+        let url = with_database(&admin_url, &database);
+        (
+            Self::Postgres {
+                admin_url,
+                database,
+            },
+            url,
+        )
+    }
+
+    async fn drop(self) -> Result<(), sqlx::Error> {
+        match self {
+            Self::Postgres {
+                admin_url,
+                database,
+            } => {
+                let mut conn = PgConnection::connect(&admin_url).await?;
+                conn.execute(
+                    format!(r#"DROP DATABASE IF EXISTS "{database}" WITH (FORCE)"#).as_str(),
+                )
+                .await?;
+                conn.close().await
+            }
+            Self::Sqlite { path } => {
+                // The write-ahead log and its index sit beside the database.
+                for suffix in ["", "-wal", "-shm"] {
+                    let mut file = path.clone().into_os_string();
+                    file.push(suffix);
+                    std::fs::remove_file(file).ok();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+pub struct TestUser {
+    pub id: Uuid,
+    pub token: String,
+}
+
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).expect("response body is not json")
+    }
+}
+
+impl TestApp {
+    pub async fn spawn() -> Self {
+        Self::spawn_with(|_| {}).await
+    }
+
+    pub async fn spawn_with(configure: impl FnOnce(&mut ConfigLayer)) -> Self {
+        let (backend, url) = Backend::create().await;
+
+        let mut flags = ConfigLayer::default();
+        flags.database.url = Some(url);
+        flags.database.max_connections = Some(4);
+        flags.s3.url = Some("http://127.0.0.1:9".to_string());
+        flags.s3.access_key_id = Some("test".to_string());
+        flags.s3.secret_access_key = Some("test".to_string());
+        flags.auth.signing_key = Some(SIGNING_KEY.to_string());
+        flags.rate_limit.enabled = Some(false);
+        configure(&mut flags);
+
+        let config = Config::load(None, flags).unwrap_or_else(|e| panic!("{e}"));
+        let db = Database::connect(&config.database)
+            .await
+            .expect("failed to connect to test database");
+        db.run_migrations()
+            .await
+            .expect("failed to migrate test database");
+
+        let state = AppState::new(db.clone(), Storage::memory(), &config);
+
+        Self {
+            readiness: state.readiness(),
+            router: tapster_api::router(state),
+            db,
+            backend: Some(backend),
+        }
+    }
+
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        content_type: Option<&str>,
+        body: Body,
+    ) -> TestResponse {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        if let Some(content_type) = content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+
+        self.send(request.body(body).expect("invalid request"))
+            .await
+    }
+
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self
+            .router
+            .clone()
+            .oneshot(request)
+            .await
+            .expect("router is infallible");
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("failed to read response body")
+            .to_vec();
+
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    pub async fn get(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::GET, uri, token, None, Body::empty())
+            .await
+    }
+
+    pub async fn post(&self, uri: &str, token: Option<&str>, json: Value) -> TestResponse {
+        self.request(
+            Method::POST,
+            uri,
+            token,
+            Some("application/json"),
+            Body::from(json.to_string()),
+        )
+        .await
+    }
+
+    pub async fn put(&self, uri: &str, token: Option<&str>, json: Value) -> TestResponse {
+        self.request(
+            Method::PUT,
+            uri,
+            token,
+            Some("application/json"),
+            Body::from(json.to_string()),
+        )
+        .await
+    }
+
+    pub async fn delete(&self, uri: &str, token: Option<&str>) -> TestResponse {
+        self.request(Method::DELETE, uri, token, None, Body::empty())
+            .await
+    }
+
+    pub async fn upload(&self, token: &str, content_type: &str, bytes: &[u8]) -> TestResponse {
+        let mut body = format!(
+            "--{BOUNDARY}\r\n\
+             Content-Disposition: form-data; name=\"file\"; filename=\"upload\"\r\n\
+             Content-Type: {content_type}\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(format!("\r\n--{BOUNDARY}--\r\n").as_bytes());
+
+        self.request(
+            Method::POST,
+            "/media",
+            Some(token),
+            Some(&format!("multipart/form-data; boundary={BOUNDARY}")),
+            Body::from(body),
+        )
+        .await
+    }
+
+    pub async fn register(&self) -> TestUser {
+        let response = self
+            .request(Method::POST, "/register", None, None, Body::empty())
+            .await;
+        assert_eq!(response.status, StatusCode::CREATED);
+
+        let token = response.json()["accessKey"]
+            .as_str()
+            .expect("missing access key")
+            .to_string();
+        let claims = jsonwebtoken::decode::<Value>(
+            &token,
+            &jsonwebtoken::DecodingKey::from_secret(SIGNING_KEY.as_bytes()),
+            &jsonwebtoken::Validation::default(),
+        )
+        .expect("invalid access key");
+        let id = claims.claims["sub"]
+            .as_str()
+            .and_then(|sub| sub.parse().ok())
+            .expect("access key has no subject");
+
+        TestUser { id, token }
+    }
+
+    pub async fn unit_id(&self, abbreviation: &str) -> Value {
+        self.get("/units", None)
+            .await
+            .json()
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|unit| unit["abbreviation"] == abbreviation)
+            .map(|unit| unit["id"].clone())
+            .expect("unit is seeded")
+    }
+
+    pub async fn create_ingredient(&self, user: &TestUser, name: &str, attributes: Value) -> Value {
+        let mut body = json!({ "name": name, "description": "" });
+        body.as_object_mut()
+            .unwrap()
+            .extend(attributes.as_object().unwrap().clone());
+
+        let ingredient = self.post("/ingredients", Some(&user.token), body).await;
+        assert_eq!(ingredient.status, StatusCode::CREATED);
+        ingredient.json()
+    }
+
+    pub async fn create_bar(&self, user: &TestUser) -> Value {
+        let bar = self
+            .post("/bars", Some(&user.token), json!({ "name": "home bar" }))
+            .await;
+        assert_eq!(bar.status, StatusCode::CREATED);
+        bar.json()
+    }
+
+    pub async fn stock_ingredient(
+        &self,
+        user: &TestUser,
+        bar: &Value,
+        ingredient: &Value,
+        quantity: f64,
+        unit: &str,
+        levels: Value,
+    ) -> Value {
+        let mut body = json!({
+            "ingredientId": ingredient["id"],
+            "quantity": quantity,
+            "unitId": self.unit_id(unit).await,
+        });
+        body.as_object_mut()
+            .unwrap()
+            .extend(levels.as_object().unwrap().clone());
+
+        let stock = self
+            .post(
+                &format!("/bars/{}/ingredients", bar["id"].as_str().unwrap()),
+                Some(&user.token),
+                body,
+            )
+            .await;
+        assert_eq!(stock.status, StatusCode::CREATED);
+        stock.json()
+    }
+
+    pub async fn import_products(
+        &self,
+        dataset: &str,
+        format: DatasetFormat,
+    ) -> Result<usize, ImportError> {
+        self.db
+            .import_products(read_products(dataset, format)?)
+            .await
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let db = self.db.clone();
+        let Some(backend) = self.backend.take() else {
+            return;
+        };
+
+        // The test's runtime is shutting down by now, so the database is
+        // dropped from a thread with a runtime of its own.
+        let cleanup = std::thread::spawn(move || {
+            tokio::runtime::Runtime::new()
+                .expect("failed to start cleanup runtime")
+                .block_on(async move {
+                    db.close().await;
+                    backend.drop().await
+                })
+        });
+
+        if let Ok(Err(e)) = cleanup.join() {
+            eprintln!("failed to drop test database: {e}");
+        }
+    }
+}
+
+fn with_database(url: &str, database: &str) -> String {
+    let (url, query) = match url.split_once('?') {
+        Some((url, query)) => (url, Some(query)),
+        None => (url, None),
+    };
+    let authority = url
+        .strip_prefix("postgres://")
+        .or_else(|| url.strip_prefix("postgresql://"))
+        .expect("DATABASE_URL must be a postgres:// URL");
+    let host = authority.split('/').next().unwrap_or(authority);
+
+    match query {
+        Some(query) => format!("postgres://{host}/{database}?{query}"),
+        None => format!("postgres://{host}/{database}"),
+    }
+}
+
+pub fn png(width: u32, height: u32, color: [u8; 3]) -> Vec<u8> {
+    let image = image::RgbImage::from_pixel(width, height, image::Rgb(color));
+    let mut bytes = std::io::Cursor::new(Vec::new());
+    image
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .expect("failed to encode png");
+
+    bytes.into_inner()
+}
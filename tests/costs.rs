@@ -0,0 +1,166 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+async fn bar_with_gin(app: &TestApp, user: &TestUser) -> (Value, Value, String) {
+    let bar = app.create_bar(user).await;
+    let gin = app.create_ingredient(user, "gin", json!({})).await;
+    let stock = app
+        .stock_ingredient(user, &bar, &gin, 700.0, "ml", json!({}))
+        .await;
+    let uri = format!(
+        "/bars/{}/ingredients/{}",
+        bar["id"].as_str().unwrap(),
+        stock["id"].as_str().unwrap()
+    );
+
+    (bar, gin, uri)
+}
+
+async fn buy_gin(app: &TestApp, user: &TestUser, uri: &str) {
+    for (price, quantity, unit, date) in [
+        (25.0, 700, "ml", "2025-06-01"),
+        (30.0, 1, "l", "2025-06-15"),
+    ] {
+        let purchase = app
+            .post(
+                &format!("{uri}/purchases"),
+                Some(&user.token),
+                json!({
+                    "price": price,
+                    "quantity": quantity,
+                    "unitId": app.unit_id(unit).await,
+                    "store": "bottle shop",
+                    "purchasedAt": date,
+                }),
+            )
+            .await;
+        assert_eq!(purchase.status, StatusCode::CREATED);
+    }
+}
+
+#[tokio::test]
+async fn records_purchases() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (_, _, uri) = bar_with_gin(&app, &user).await;
+    buy_gin(&app, &user, &uri).await;
+
+    let purchases = app
+        .get(&format!("{uri}/purchases"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(purchases[0]["price"], 30.0);
+    assert_eq!(purchases[0]["unit"]["abbreviation"], "l");
+    assert_eq!(purchases[0]["purchased_at"], "2025-06-15");
+    assert_eq!(purchases[1]["price"], 25.0);
+    assert_eq!(purchases[1]["store"], "bottle shop");
+
+    // 55 for 1.7l is 0.0324 a milliliter.
+    let cost = app
+        .get(&format!("{uri}/cost"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(cost["purchases"], 2);
+    assert_eq!(cost["spent"], 55.0);
+    assert_eq!(cost["purchased"]["quantity"], 1700.0);
+    assert_eq!(cost["purchased"]["unit"]["abbreviation"], "ml");
+    assert_eq!(cost["unit_cost"], 0.0324);
+}
+
+#[tokio::test]
+async fn rejects_invalid_purchases() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+    let (_, _, uri) = bar_with_gin(&app, &user).await;
+    let uri = format!("{uri}/purchases");
+    let ml = app.unit_id("ml").await;
+
+    for purchase in [
+        json!({ "price": -1, "quantity": 700, "unitId": ml }),
+        json!({ "price": 25, "quantity": 0, "unitId": ml }),
+        json!({ "price": 25, "quantity": 700, "unitId": app.unit_id("g").await }),
+    ] {
+        let response = app.post(&uri, Some(&user.token), purchase.clone()).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{purchase}");
+    }
+
+    let response = app
+        .post(
+            &uri,
+            Some(&other.token),
+            json!({ "price": 25, "quantity": 700, "unitId": ml }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let cost = app
+        .get(&uri.replace("purchases", "cost"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(cost["purchases"], 0);
+    assert!(cost["unit_cost"].is_null());
+}
+
+#[tokio::test]
+async fn costs_a_recipe_and_suggests_a_price() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (bar, gin, uri) = bar_with_gin(&app, &user).await;
+    buy_gin(&app, &user, &uri).await;
+
+    let campari = app.create_ingredient(&user, "campari", json!({})).await;
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Gin and Campari", "description": "" }),
+        )
+        .await
+        .json();
+    for (ingredient, quantity, unit) in [(&gin, 2, "oz"), (&campari, 30, "ml")] {
+        app.post(
+            &format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({
+                "ingredientId": ingredient["id"],
+                "quantity": quantity,
+                "unitId": app.unit_id(unit).await,
+            }),
+        )
+        .await;
+    }
+    let uri = format!(
+        "/bars/{}/recipes/{}/cost",
+        bar["id"].as_str().unwrap(),
+        recipe["id"].as_str().unwrap()
+    );
+
+    // Two ounces of gin is 59.15ml, at 55 for 1.7l.
+    let cost = app.get(&uri, Some(&user.token)).await;
+    assert_eq!(cost.status, StatusCode::OK);
+
+    let cost = cost.json();
+    assert_eq!(cost["ingredients"][0]["name"], "gin");
+    assert_eq!(cost["ingredients"][0]["cost"], 1.9136);
+    assert!(cost["ingredients"][1]["cost"].is_null());
+    assert_eq!(cost["cost"], 1.91);
+    assert_eq!(cost["complete"], false);
+    assert_eq!(cost["pour_cost"], 20.0);
+    assert_eq!(cost["suggested_price"], 9.57);
+
+    let cost = app
+        .get(&format!("{uri}?pourCost=25"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(cost["suggested_price"], 7.65);
+
+    for query in ["?pourCost=0", "?pourCost=120", "?pourCost=NaN"] {
+        let response = app.get(&format!("{uri}{query}"), Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "query {query}");
+    }
+}
@@ -0,0 +1,28 @@
+use axum::http::StatusCode;
+
+use common::TestApp;
+
+mod common;
+
+#[tokio::test]
+async fn reports_liveness_and_readiness() {
+    let app = TestApp::spawn().await;
+
+    let live = app.get("/livez", None).await;
+    assert_eq!(live.status, StatusCode::OK);
+    assert_eq!(live.json()["status"], "OK");
+
+    let ready = app.get("/readyz", None).await;
+    assert_eq!(ready.status, StatusCode::OK);
+    let ready = ready.json();
+    assert_eq!(ready["status"], "OK");
+    assert_eq!(ready["dependencies"]["database"]["status"], "OK");
+    assert_eq!(ready["dependencies"]["storage"]["status"], "OK");
+
+    // Once shutdown starts the server stays alive but stops being ready.
+    app.readiness.drain();
+    let ready = app.get("/readyz", None).await;
+    assert_eq!(ready.status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(ready.json()["status"], "DRAINING");
+    assert_eq!(app.get("/livez", None).await.status, StatusCode::OK);
+}
@@ -0,0 +1,223 @@
+use axum::http::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+use common::TestApp;
+
+mod common;
+
+#[tokio::test]
+async fn creates_and_retrieves_an_ingredient() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let created = app
+        .post(
+            "/ingredients",
+            Some(&user.token),
+            json!({ "name": "London Dry Gin", "description": "juniper forward" }),
+        )
+        .await;
+    assert_eq!(created.status, StatusCode::CREATED);
+
+    let ingredient = created.json();
+    assert_eq!(ingredient["name"], "london dry gin");
+    assert_eq!(ingredient["description"], "juniper forward");
+    assert_eq!(ingredient["owner"], user.id.to_string());
+
+    let uri = format!("/ingredients/{}", ingredient["id"].as_str().unwrap());
+    let retrieved = app.get(&uri, Some(&user.token)).await;
+    assert_eq!(retrieved.status, StatusCode::OK);
+    assert_eq!(retrieved.json(), ingredient);
+
+    let sub_ingredients = app
+        .get(&format!("{uri}/ingredients"), Some(&user.token))
+        .await;
+    assert_eq!(sub_ingredients.status, StatusCode::OK);
+    assert_eq!(sub_ingredients.json(), json!([]));
+}
+
+#[tokio::test]
+async fn lists_ingredients() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    for name in ["gin", "vermouth", "campari"] {
+        app.post(
+            "/ingredients",
+            Some(&user.token),
+            json!({ "name": name, "description": "" }),
+        )
+        .await;
+    }
+
+    let listed = app
+        .get("/ingredients?catalogue=false", Some(&user.token))
+        .await;
+    assert_eq!(listed.status, StatusCode::OK);
+
+    let mut names: Vec<String> = listed
+        .json()
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|i| i["name"].as_str().unwrap().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["campari", "gin", "vermouth"]);
+}
+
+#[tokio::test]
+async fn unknown_ingredient_is_not_found() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let uri = format!("/ingredients/{}", Uuid::new_v4());
+
+    assert_eq!(
+        app.get(&uri, Some(&user.token)).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.get(&format!("{uri}/ingredients"), Some(&user.token))
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn ingredients_of_another_user_are_hidden() {
+    let app = TestApp::spawn().await;
+    let owner = app.register().await;
+    let other = app.register().await;
+
+    let ingredient = app
+        .post(
+            "/ingredients",
+            Some(&owner.token),
+            json!({ "name": "gin", "description": "" }),
+        )
+        .await
+        .json();
+    let uri = format!("/ingredients/{}", ingredient["id"].as_str().unwrap());
+
+    assert_eq!(
+        app.get(&uri, Some(&other.token)).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.get(&format!("{uri}/ingredients"), Some(&other.token))
+            .await
+            .status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.get("/ingredients?catalogue=false", Some(&other.token))
+            .await
+            .json(),
+        json!([])
+    );
+}
+
+#[tokio::test]
+async fn builds_compound_ingredients() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+
+    let create = |token: String, body: serde_json::Value| {
+        let app = &app;
+        async move { app.post("/ingredients", Some(&token), body).await }
+    };
+    let rum = create(
+        user.token.clone(),
+        json!({ "name": "rum", "description": "", "abv": 40 }),
+    )
+    .await
+    .json();
+    assert_eq!(rum["abv"], 40.0);
+    assert!(rum["sugar"].is_null());
+
+    let falernum = create(
+        user.token.clone(),
+        json!({ "name": "falernum", "description": "", "abv": 11, "sugar": 20 }),
+    )
+    .await
+    .json();
+    let spiced = create(
+        user.token.clone(),
+        json!({ "name": "spiced rum", "description": "" }),
+    )
+    .await
+    .json();
+    let uri = format!(
+        "/ingredients/{}/ingredients",
+        spiced["id"].as_str().unwrap()
+    );
+
+    for (part, parts) in [(&rum, 3), (&falernum, 1)] {
+        let added = app
+            .post(
+                &uri,
+                Some(&user.token),
+                json!({ "ingredientId": part["id"], "parts": parts }),
+            )
+            .await;
+        assert_eq!(added.status, StatusCode::CREATED);
+        assert_eq!(added.json()["ingredient"], *part);
+    }
+
+    let listed = app.get(&uri, Some(&user.token)).await.json();
+    let mut parts: Vec<i64> = listed
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|part| part["parts"].as_i64().unwrap())
+        .collect();
+    parts.sort();
+    assert_eq!(parts, [1, 3]);
+
+    let itself = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "ingredientId": spiced["id"], "parts": 1 }),
+        )
+        .await;
+    assert_eq!(itself.status, StatusCode::BAD_REQUEST);
+
+    // Falernum is already a part of the spiced rum.
+    let cycle = app
+        .post(
+            &format!(
+                "/ingredients/{}/ingredients",
+                falernum["id"].as_str().unwrap()
+            ),
+            Some(&user.token),
+            json!({ "ingredientId": spiced["id"], "parts": 1 }),
+        )
+        .await;
+    assert_eq!(cycle.status, StatusCode::BAD_REQUEST);
+
+    let others = create(
+        other.token.clone(),
+        json!({ "name": "gin", "description": "" }),
+    )
+    .await
+    .json();
+    let response = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "ingredientId": others["id"], "parts": 1 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let too_strong = create(
+        user.token.clone(),
+        json!({ "name": "spirit", "description": "", "abv": 101 }),
+    )
+    .await;
+    assert_eq!(too_strong.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,308 @@
+use axum::http::{header, StatusCode};
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use common::{png, TestApp};
+
+mod common;
+
+#[tokio::test]
+async fn uploads_and_downloads_an_image() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let bytes = png(16, 8, [20, 120, 200]);
+
+    let uploaded = app.upload(&user.token, "image/png", &bytes).await;
+    assert_eq!(uploaded.status, StatusCode::CREATED);
+
+    let media = uploaded.json();
+    assert_eq!(media["size"], bytes.len());
+    assert_eq!(media["content_type"], "image/png");
+    assert_eq!(media["owner"], user.id.to_string());
+    assert_eq!(media["width"], 16);
+    assert_eq!(media["height"], 8);
+    assert!(media["blurhash"].is_string());
+    assert!(media["dominant_color"].is_string());
+
+    let downloaded = app
+        .get(
+            &format!("/media/{}", media["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(downloaded.status, StatusCode::OK);
+    assert_eq!(downloaded.headers[header::CONTENT_TYPE], "image/png");
+    assert_eq!(downloaded.body, bytes);
+}
+
+#[tokio::test]
+async fn identical_uploads_share_their_bytes() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let bytes = png(4, 4, [0, 0, 0]);
+
+    let first = app.upload(&user.token, "image/png", &bytes).await.json();
+    let second = app.upload(&user.token, "image/png", &bytes).await.json();
+    assert_ne!(first["id"], second["id"]);
+
+    // Deleting one copy leaves the bytes in place for the other.
+    let deleted = app
+        .delete(
+            &format!("/media/{}", first["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(deleted.status, StatusCode::NO_CONTENT);
+
+    let downloaded = app
+        .get(
+            &format!("/media/{}", second["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(downloaded.status, StatusCode::OK);
+    assert_eq!(downloaded.body, bytes);
+
+    let usage = app.get("/me/usage", Some(&user.token)).await.json();
+    assert_eq!(usage["media_count"], 1);
+    assert_eq!(usage["used"], bytes.len());
+
+    // Once every copy is gone the bytes are removed, and uploading them again
+    // stores them afresh.
+    app.delete(
+        &format!("/media/{}", second["id"].as_str().unwrap()),
+        Some(&user.token),
+    )
+    .await;
+    let third = app.upload(&user.token, "image/png", &bytes).await.json();
+    let downloaded = app
+        .get(
+            &format!("/media/{}", third["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(downloaded.status, StatusCode::OK);
+    assert_eq!(downloaded.body, bytes);
+}
+
+#[tokio::test]
+async fn lists_media_with_filters_and_references() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let image = app
+        .upload(&user.token, "image/png", &png(4, 4, [255, 0, 0]))
+        .await
+        .json();
+    app.upload(&user.token, "text/plain", b"tasting notes")
+        .await;
+    let bar = app
+        .post(
+            "/bars",
+            Some(&user.token),
+            json!({ "name": "home bar", "thumbnailId": image["id"] }),
+        )
+        .await
+        .json();
+
+    let all = app.get("/media", Some(&user.token)).await;
+    assert_eq!(all.status, StatusCode::OK);
+    assert_eq!(all.headers["x-total-count"], "2");
+
+    let images = app
+        .get("/media?contentType=image/*", Some(&user.token))
+        .await;
+    assert_eq!(images.headers["x-total-count"], "1");
+
+    let images = images.json();
+    assert_eq!(images[0]["id"], image["id"]);
+    assert_eq!(images[0]["bars"], json!([bar["id"]]));
+
+    // Only a trailing `*` is a wildcard.
+    for filter in ["image/p_g", "%25", "image/%25"] {
+        let filtered = app
+            .get(&format!("/media?contentType={filter}"), Some(&user.token))
+            .await;
+        assert_eq!(filtered.headers["x-total-count"], "0", "filter {filter}");
+    }
+
+    let page = app.get("/media?limit=1&offset=1", Some(&user.token)).await;
+    assert_eq!(page.headers["x-total-count"], "2");
+    assert_eq!(page.json().as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn deletes_only_unused_media() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let used = app
+        .upload(&user.token, "image/png", &png(4, 4, [1, 2, 3]))
+        .await
+        .json();
+    let unused = app
+        .upload(&user.token, "image/png", &png(4, 4, [4, 5, 6]))
+        .await
+        .json();
+    app.post(
+        "/bars",
+        Some(&user.token),
+        json!({ "name": "home bar", "thumbnailId": used["id"] }),
+    )
+    .await;
+
+    let deleted = app.delete("/media/unused", Some(&user.token)).await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    assert_eq!(deleted.json(), json!({ "deleted": [unused["id"]] }));
+
+    let remaining = app.get("/media", Some(&user.token)).await.json();
+    assert_eq!(remaining.as_array().unwrap().len(), 1);
+    assert_eq!(remaining[0]["id"], used["id"]);
+}
+
+#[tokio::test]
+async fn deletes_media_in_bulk() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+
+    let mut ids = Vec::new();
+    for color in [[1, 1, 1], [2, 2, 2], [3, 3, 3]] {
+        let media = app
+            .upload(&user.token, "image/png", &png(4, 4, color))
+            .await
+            .json();
+        ids.push(media["id"].clone());
+    }
+    let others = app
+        .upload(&other.token, "image/png", &png(4, 4, [4, 4, 4]))
+        .await
+        .json();
+
+    // Nothing is deleted while any of the media is someone else's.
+    let response = app
+        .post(
+            "/media/delete",
+            Some(&user.token),
+            json!({ "ids": [ids[0], others["id"]] }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let too_many: Vec<Uuid> = (0..201).map(|_| Uuid::new_v4()).collect();
+    let response = app
+        .post(
+            "/media/delete",
+            Some(&user.token),
+            json!({ "ids": too_many }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let deleted = app
+        .post(
+            "/media/delete",
+            Some(&user.token),
+            json!({ "ids": [ids[0], ids[1], ids[0]] }),
+        )
+        .await;
+    assert_eq!(deleted.status, StatusCode::OK);
+    let mut deleted: Vec<Value> = deleted.json()["deleted"].as_array().unwrap().clone();
+    deleted.sort_by_key(|id| id.to_string());
+    let mut expected = vec![ids[0].clone(), ids[1].clone()];
+    expected.sort_by_key(|id| id.to_string());
+    assert_eq!(deleted, expected);
+
+    let remaining = app.get("/media", Some(&user.token)).await.json();
+    assert_eq!(remaining.as_array().unwrap().len(), 1);
+    assert_eq!(remaining[0]["id"], ids[2]);
+    assert_eq!(
+        app.get("/media", Some(&other.token)).await.json()[0]["id"],
+        others["id"]
+    );
+}
+
+#[tokio::test]
+async fn enforces_upload_limits() {
+    let app = TestApp::spawn_with(|flags| {
+        flags.limits.max_upload_size = Some(64);
+        flags.limits.uploads_per_hour = Some(1);
+    })
+    .await;
+    let user = app.register().await;
+
+    let too_large = app.upload(&user.token, "text/plain", &[b'a'; 65]).await;
+    assert_eq!(too_large.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+    let uploaded = app.upload(&user.token, "text/plain", b"ok").await;
+    assert_eq!(uploaded.status, StatusCode::CREATED);
+
+    let too_many = app.upload(&user.token, "text/plain", b"again").await;
+    assert_eq!(too_many.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(too_many.json()["details"]["limit"], 1);
+
+    // Deleting the upload does not make room for another within the hour.
+    let uri = format!("/media/{}", uploaded.json()["id"].as_str().unwrap());
+    app.delete(&uri, Some(&user.token)).await;
+    let still_too_many = app.upload(&user.token, "text/plain", b"again").await;
+    assert_eq!(still_too_many.status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn unknown_media_is_not_found() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let uri = format!("/media/{}", Uuid::new_v4());
+
+    let response = app.get(&uri, Some(&user.token)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(
+        response.json(),
+        json!({
+            "message": "no rows",
+            "requestId": response.headers["x-request-id"].to_str().unwrap(),
+        })
+    );
+    assert_eq!(
+        app.delete(&uri, Some(&user.token)).await.status,
+        StatusCode::NOT_FOUND
+    );
+}
+
+#[tokio::test]
+async fn media_of_another_user_is_hidden() {
+    let app = TestApp::spawn().await;
+    let owner = app.register().await;
+    let other = app.register().await;
+
+    let media = app
+        .upload(&owner.token, "image/png", &png(4, 4, [9, 9, 9]))
+        .await
+        .json();
+    let uri = format!("/media/{}", media["id"].as_str().unwrap());
+
+    assert_eq!(
+        app.get(&uri, Some(&other.token)).await.status,
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        app.delete(&uri, Some(&other.token)).await.status,
+        StatusCode::NOT_FOUND
+    );
+
+    let listed = app.get("/media", Some(&other.token)).await;
+    assert_eq!(listed.headers["x-total-count"], "0");
+    assert_eq!(listed.json(), json!([]));
+
+    let deleted = app.delete("/media/unused", Some(&other.token)).await.json();
+    assert_eq!(deleted, json!({ "deleted": [] }));
+
+    let usage = app.get("/me/usage", Some(&other.token)).await.json();
+    assert_eq!(usage["media_count"], 0);
+
+    // The owner still has it after all of that.
+    assert_eq!(
+        app.get(&uri, Some(&owner.token)).await.status,
+        StatusCode::OK
+    );
+}
@@ -0,0 +1,47 @@
+use axum::http::StatusCode;
+
+use common::TestApp;
+
+mod common;
+
+const METRICS_TOKEN: &str = "scrape-me";
+
+fn gauge(scrape: &str, name: &str) -> f64 {
+    scrape
+        .lines()
+        .find_map(|line| line.strip_prefix(&format!("{name} ")))
+        .unwrap_or_else(|| panic!("{name} missing from the scrape"))
+        .parse()
+        .unwrap()
+}
+
+// The gauges are global to the process, so everything that reads them is in
+// one test.
+#[tokio::test]
+async fn serves_metrics_to_holders_of_the_token() {
+    let app = TestApp::spawn().await;
+    let response = app.get("/metrics", None).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let app = TestApp::spawn_with(|flags| {
+        flags.metrics.token = Some(METRICS_TOKEN.to_string());
+    })
+    .await;
+    for token in [None, Some("scrape-you")] {
+        let response = app.get("/metrics", token).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED, "{token:?}");
+    }
+
+    app.register().await;
+    let response = app.get("/metrics", Some(METRICS_TOKEN)).await;
+    assert_eq!(response.status, StatusCode::OK);
+    let scrape = String::from_utf8(response.body).unwrap();
+    assert_eq!(gauge(&scrape, "tapster_users"), 1.0);
+    assert!(scrape.contains("tapster_db_pool_max_connections 4"));
+
+    // The totals are not counted again until the interval has passed.
+    app.register().await;
+    let response = app.get("/metrics", Some(METRICS_TOKEN)).await;
+    let scrape = String::from_utf8(response.body).unwrap();
+    assert_eq!(gauge(&scrape, "tapster_users"), 1.0);
+}
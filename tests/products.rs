@@ -0,0 +1,321 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use tapster_api::{DatasetFormat, ImportError};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+const PRODUCTS: &str = "\
+barcode,name,ingredient,size,unit,abv\r
+080812345674,\"Tapster's Gin, London Dry\",London Dry Gin,700,ml,40\r
+5000299000014,Tapster's Dry Vermouth,dry vermouth,1,l,18\r
+";
+
+async fn scan(app: &TestApp, user: &TestUser, uri: &str, scan: Value) -> (StatusCode, Value) {
+    let response = app
+        .post(&format!("{uri}/scan"), Some(&user.token), scan)
+        .await;
+    (response.status, response.json())
+}
+
+#[tokio::test]
+async fn scans_bottles_into_stock() {
+    let app = TestApp::spawn().await;
+    assert_eq!(
+        app.import_products(PRODUCTS, DatasetFormat::Csv)
+            .await
+            .unwrap(),
+        2
+    );
+    let user = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    let product = app.get("/products/080812345674", Some(&user.token)).await;
+    assert_eq!(product.status, StatusCode::OK);
+
+    let product = product.json();
+    assert_eq!(product["barcode"], "00080812345674");
+    assert_eq!(product["name"], "Tapster's Gin, London Dry");
+    assert_eq!(product["ingredient"], "london dry gin");
+    assert_eq!(product["unit"]["abbreviation"], "ml");
+
+    // The first scan creates the ingredient and stocks it at the bar.
+    let (status, scanned) = scan(&app, &user, &uri, json!({ "barcode": "080812345674" })).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(scanned["ingredient_created"], true);
+    let stock = &scanned["bar_ingredient"];
+    assert_eq!(stock["ingredient"]["name"], "london dry gin");
+    assert_eq!(
+        stock["ingredient"]["description"],
+        "Tapster's Gin, London Dry"
+    );
+    assert_eq!(stock["ingredient"]["abv"], 40.0);
+    assert_eq!(stock["quantity"], 700.0);
+    assert_eq!(stock["bottle_size"], 700.0);
+    assert_eq!(stock["unit"]["abbreviation"], "ml");
+    assert_eq!(scanned["bottles"].as_array().unwrap().len(), 1);
+    assert_eq!(scanned["bottles"][0]["size"], 700.0);
+    assert_eq!(scanned["bottles"][0]["fill"], 1.0);
+    assert!(scanned["bottles"][0]["opened_at"].is_null());
+
+    // The same product under its EAN-13 restocks it.
+    let (status, scanned) = scan(
+        &app,
+        &user,
+        &uri,
+        json!({ "barcode": "0080812345674", "bottles": 2 }),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(scanned["ingredient_created"], false);
+    assert_eq!(scanned["bar_ingredient"]["id"], stock["id"]);
+    assert_eq!(scanned["bar_ingredient"]["quantity"], 2100.0);
+    assert_eq!(scanned["bottles"].as_array().unwrap().len(), 2);
+
+    let ingredients = app
+        .get("/ingredients?catalogue=false", Some(&user.token))
+        .await
+        .json();
+    assert_eq!(ingredients.as_array().unwrap().len(), 1);
+    let bottles = app
+        .get(&format!("{uri}/bottles"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(bottles.as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn concurrent_first_scans_create_the_ingredient_once() {
+    let app = TestApp::spawn().await;
+    app.import_products(PRODUCTS, DatasetFormat::Csv)
+        .await
+        .unwrap();
+    let user = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    let body = json!({ "barcode": "080812345674" });
+    let ((first, _), (second, _)) = tokio::join!(
+        scan(&app, &user, &uri, body.clone()),
+        scan(&app, &user, &uri, body.clone()),
+    );
+    let mut statuses = [first, second];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CREATED]);
+
+    let ingredients = app
+        .get("/ingredients?catalogue=false", Some(&user.token))
+        .await
+        .json();
+    assert_eq!(ingredients.as_array().unwrap().len(), 1);
+    let bottles = app
+        .get(&format!("{uri}/bottles"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(bottles.as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn converts_scanned_bottles_to_the_stocked_unit() {
+    let app = TestApp::spawn().await;
+    app.import_products(PRODUCTS, DatasetFormat::Csv)
+        .await
+        .unwrap();
+    let user = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    let vermouth = app
+        .create_ingredient(&user, "Dry Vermouth", json!({}))
+        .await;
+    let stock = app
+        .stock_ingredient(&user, &bar, &vermouth, 250.0, "ml", json!({}))
+        .await;
+
+    let (status, scanned) = scan(&app, &user, &uri, json!({ "barcode": "5000299000014" })).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(scanned["ingredient_created"], false);
+    assert_eq!(scanned["bar_ingredient"]["id"], stock["id"]);
+    assert_eq!(scanned["bar_ingredient"]["quantity"], 1250.0);
+    assert_eq!(scanned["bottles"][0]["size"], 1000.0);
+    assert_eq!(scanned["bottles"][0]["unit"]["abbreviation"], "ml");
+}
+
+#[tokio::test]
+async fn rejects_invalid_scans() {
+    let app = TestApp::spawn().await;
+    app.import_products(PRODUCTS, DatasetFormat::Csv)
+        .await
+        .unwrap();
+    let user = app.register().await;
+    let other = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    for body in [
+        json!({ "barcode": "080812345675" }),
+        json!({ "barcode": "80812345674" }),
+        json!({ "barcode": "08081234567a" }),
+        json!({ "barcode": "080812345674", "bottles": 0 }),
+        json!({ "barcode": "080812345674", "bottles": 101 }),
+    ] {
+        let response = app
+            .post(&format!("{uri}/scan"), Some(&user.token), body.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{body}");
+    }
+
+    let response = app
+        .post(
+            &format!("{uri}/scan"),
+            Some(&user.token),
+            json!({ "barcode": "4001234567891" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    let response = app.get("/products/4001234567891", Some(&user.token)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .post(
+            &format!("{uri}/scan"),
+            Some(&other.token),
+            json!({ "barcode": "080812345674" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    // Gin stocked by weight cannot take bottles measured by volume.
+    let gin = app
+        .create_ingredient(&user, "london dry gin", json!({}))
+        .await;
+    app.stock_ingredient(&user, &bar, &gin, 0.0, "g", json!({}))
+        .await;
+    let response = app
+        .post(
+            &format!("{uri}/scan"),
+            Some(&user.token),
+            json!({ "barcode": "080812345674" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn imports_products_from_json() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let products = json!([
+        {
+            "barcode": "12345670",
+            "name": "Tapster's Orange Bitters",
+            "ingredient": "orange bitters",
+            "size": 200,
+            "unit": "ml",
+            "abv": 28,
+        },
+        {
+            "barcode": "01234567890128",
+            "name": "Tapster's Demerara Syrup",
+            "ingredient": "demerara syrup",
+            "size": 500,
+            "unit": "ml",
+        },
+    ]);
+    assert_eq!(
+        app.import_products(&products.to_string(), DatasetFormat::Json)
+            .await
+            .unwrap(),
+        2
+    );
+
+    let bitters = app
+        .get("/products/00000012345670", Some(&user.token))
+        .await
+        .json();
+    assert_eq!(bitters["size"], 200.0);
+    let syrup = app
+        .get("/products/1234567890128", Some(&user.token))
+        .await
+        .json();
+    assert!(syrup["abv"].is_null());
+
+    // Importing a product again replaces it.
+    let products = json!([{
+        "barcode": "12345670",
+        "name": "Tapster's Orange Bitters",
+        "ingredient": "orange bitters",
+        "size": 100,
+        "unit": "ml",
+        "abv": 28,
+    }]);
+    app.import_products(&products.to_string(), DatasetFormat::Json)
+        .await
+        .unwrap();
+    let bitters = app
+        .get("/products/12345670", Some(&user.token))
+        .await
+        .json();
+    assert_eq!(bitters["size"], 100.0);
+}
+
+#[tokio::test]
+async fn rejects_invalid_datasets() {
+    let app = TestApp::spawn().await;
+
+    for (dataset, record) in [
+        // A wrong check digit.
+        (
+            "barcode,name,ingredient,size,unit\n080812345675,Gin,gin,700,ml\n",
+            1,
+        ),
+        (
+            "barcode,name,ingredient,size,unit\n080812345674,Gin,gin,0,ml\n",
+            1,
+        ),
+        (
+            "barcode,name,ingredient,size,unit\n080812345674,Gin,gin,700,pint\n",
+            1,
+        ),
+        (
+            "barcode,name,ingredient,size,unit,abv\n\
+             080812345674,Gin,gin,700,ml,40\n\
+             5000299000014,Vermouth,vermouth,1,l,180\n",
+            2,
+        ),
+        (
+            "barcode,name,ingredient,size,unit\n080812345674,Gin,gin,700\n",
+            1,
+        ),
+    ] {
+        match app.import_products(dataset, DatasetFormat::Csv).await {
+            Err(ImportError::Record { record: r, .. }) => assert_eq!(r, record, "{dataset}"),
+            other => panic!("{dataset}: expected a record error, got {other:?}"),
+        }
+    }
+
+    for dataset in [
+        "",
+        "barcode,name,size,unit\n080812345674,Gin,700,ml\n",
+        "barcode,name,ingredient,size,unit\n\"080812345674,Gin,gin,700,ml\n",
+    ] {
+        assert!(
+            matches!(
+                app.import_products(dataset, DatasetFormat::Csv).await,
+                Err(ImportError::Dataset(_))
+            ),
+            "{dataset}"
+        );
+    }
+    assert!(matches!(
+        app.import_products("{}", DatasetFormat::Json).await,
+        Err(ImportError::Dataset(_))
+    ));
+
+    // Nothing of a dataset with an invalid record is imported.
+    let user = app.register().await;
+    let response = app.get("/products/080812345674", Some(&user.token)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
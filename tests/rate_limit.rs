@@ -0,0 +1,63 @@
+use axum::{
+    body::Body,
+    http::{Method, Request, StatusCode},
+};
+
+use common::{TestApp, TestResponse};
+
+mod common;
+
+async fn register(app: &TestApp, forwarded_for: &str) -> TestResponse {
+    app.send(
+        Request::builder()
+            .method(Method::POST)
+            .uri("/register")
+            .header("x-forwarded-for", forwarded_for)
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await
+}
+
+#[tokio::test]
+async fn limits_clients_by_the_address_the_proxy_saw() {
+    let app = TestApp::spawn_with(|flags| {
+        flags.rate_limit.enabled = Some(true);
+        flags.rate_limit.auth_per_minute = Some(1);
+        flags.rate_limit.auth_burst = Some(2);
+        flags.rate_limit.trust_forwarded_for = Some(true);
+    })
+    .await;
+
+    // The client makes up the entries on the left, which do not get it a
+    // bucket of its own.
+    for spoofed in ["10.0.0.1", "10.0.0.2"] {
+        let response = register(&app, &format!("{spoofed}, 203.0.113.7")).await;
+        assert_eq!(response.status, StatusCode::CREATED);
+    }
+    let response = register(&app, "10.0.0.3, 203.0.113.7").await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers.contains_key("retry-after"));
+
+    let response = register(&app, "203.0.113.8").await;
+    assert_eq!(response.status, StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn skips_the_entries_of_trusted_proxies() {
+    let app = TestApp::spawn_with(|flags| {
+        flags.rate_limit.enabled = Some(true);
+        flags.rate_limit.auth_per_minute = Some(1);
+        flags.rate_limit.auth_burst = Some(1);
+        flags.rate_limit.trust_forwarded_for = Some(true);
+        flags.rate_limit.trusted_proxies = Some(2);
+    })
+    .await;
+
+    let response = register(&app, "10.0.0.1, 203.0.113.7, 192.0.2.1").await;
+    assert_eq!(response.status, StatusCode::CREATED);
+    let response = register(&app, "10.0.0.2, 203.0.113.7, 192.0.2.2").await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    let response = register(&app, "203.0.113.8, 192.0.2.1").await;
+    assert_eq!(response.status, StatusCode::CREATED);
+}
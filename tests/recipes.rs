@@ -0,0 +1,386 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use common::{png, TestApp, TestUser};
+
+mod common;
+
+async fn recipe(
+    app: &TestApp,
+    user: &TestUser,
+    method: Option<&str>,
+    lines: &[(&Value, f64)],
+) -> Value {
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Cocktail", "description": "", "method": method }),
+        )
+        .await;
+    assert_eq!(recipe.status, StatusCode::CREATED);
+
+    let recipe = recipe.json();
+    let ml = app.unit_id("ml").await;
+    for (ingredient, quantity) in lines {
+        app.post(
+            &format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({ "ingredientId": ingredient["id"], "quantity": quantity, "unitId": ml }),
+        )
+        .await;
+    }
+
+    recipe
+}
+
+async fn negroni(app: &TestApp, user: &TestUser, amount: f64, unit: &str) -> Value {
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Negroni", "description": "stirred, on the rocks" }),
+        )
+        .await;
+    assert_eq!(recipe.status, StatusCode::CREATED);
+
+    let recipe = recipe.json();
+    let unit = app.unit_id(unit).await;
+    for name in ["gin", "campari", "sweet vermouth"] {
+        let ingredient = app.create_ingredient(user, name, json!({})).await;
+        let line = app
+            .post(
+                &format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap()),
+                Some(&user.token),
+                json!({ "ingredientId": ingredient["id"], "quantity": amount, "unitId": unit }),
+            )
+            .await;
+        assert_eq!(line.status, StatusCode::CREATED);
+    }
+
+    recipe
+}
+
+#[tokio::test]
+async fn creates_a_recipe_with_ingredients() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let recipe = negroni(&app, &user, 30.0, "ml").await;
+    let uri = format!("/recipes/{}", recipe["id"].as_str().unwrap());
+
+    assert_eq!(recipe["name"], "negroni");
+    assert_eq!(app.get(&uri, Some(&user.token)).await.json(), recipe);
+    assert_eq!(
+        app.get("/recipes", Some(&user.token)).await.json(),
+        json!([recipe])
+    );
+
+    let lines = app
+        .get(&format!("{uri}/ingredients"), Some(&user.token))
+        .await
+        .json();
+    let names: Vec<&str> = lines
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|line| line["ingredient"]["name"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, ["gin", "campari", "sweet vermouth"]);
+    assert_eq!(lines[0]["quantity"], 30.0);
+    assert_eq!(lines[0]["unit"]["abbreviation"], "ml");
+}
+
+#[tokio::test]
+async fn creates_a_recipe_with_a_thumbnail() {
+    let app = TestApp::spawn().await;
+    let owner = app.register().await;
+    let other = app.register().await;
+    let media = app
+        .upload(&owner.token, "image/png", &png(8, 8, [120, 20, 40]))
+        .await
+        .json();
+    let body = json!({ "name": "Negroni", "description": "", "thumbnailId": media["id"] });
+
+    let stolen = app.post("/recipes", Some(&other.token), body.clone()).await;
+    assert_eq!(stolen.status, StatusCode::NOT_FOUND);
+
+    let recipe = app.post("/recipes", Some(&owner.token), body).await;
+    assert_eq!(recipe.status, StatusCode::CREATED);
+    assert_eq!(recipe.json()["thumbnail"], media);
+}
+
+#[tokio::test]
+async fn rejects_lines_that_are_not_the_users() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+    let recipe = negroni(&app, &user, 1.0, "oz").await;
+    let uri = format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap());
+    let unit = app.unit_id("oz").await;
+
+    let others_ingredient = app.create_ingredient(&other, "rye", json!({})).await;
+    let response = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "ingredientId": others_ingredient["id"], "quantity": 1, "unitId": unit }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let own_ingredient = app.create_ingredient(&user, "rye", json!({})).await;
+    let response = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "ingredientId": own_ingredient["id"], "quantity": 0, "unitId": unit }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "ingredientId": own_ingredient["id"], "quantity": 1, "unitId": Uuid::new_v4() }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn scales_to_servings_in_practical_units() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let recipe = negroni(&app, &user, 1.0, "oz").await;
+    let uri = format!("/recipes/{}/scale", recipe["id"].as_str().unwrap());
+
+    let scaled = app
+        .get(&format!("{uri}?servings=16"), Some(&user.token))
+        .await;
+    assert_eq!(scaled.status, StatusCode::OK);
+
+    // 16 oz of each is two cups.
+    let scaled = scaled.json();
+    assert_eq!(scaled["servings"], 16.0);
+    assert_eq!(scaled["ingredients"][0]["quantity"], 2.0);
+    assert_eq!(scaled["ingredients"][0]["unit"]["abbreviation"], "cup");
+    assert!(scaled["water"].is_null());
+    assert_eq!(scaled["total_volume"]["quantity"], 6.0);
+
+    let metric = app
+        .get(
+            &format!("{uri}?servings=2&system=metric"),
+            Some(&user.token),
+        )
+        .await
+        .json();
+    assert_eq!(metric["ingredients"][0]["quantity"], 59.15);
+    assert_eq!(metric["ingredients"][0]["unit"]["abbreviation"], "ml");
+}
+
+#[tokio::test]
+async fn scales_a_diluted_batch_to_a_volume() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let recipe = negroni(&app, &user, 30.0, "ml").await;
+    let uri = format!("/recipes/{}/scale", recipe["id"].as_str().unwrap());
+
+    let scaled = app
+        .get(
+            &format!("{uri}?volume=1.2&unit=l&dilution=20"),
+            Some(&user.token),
+        )
+        .await
+        .json();
+
+    // 90ml a serving plus 20% water is 108ml, so 1.2l makes 11.11 servings
+    // of 333.33ml of each ingredient and 200ml of water.
+    assert_eq!(scaled["servings"], 11.11);
+    assert_eq!(scaled["ingredients"][0]["quantity"], 333.33);
+    assert_eq!(scaled["ingredients"][0]["unit"]["abbreviation"], "ml");
+    assert_eq!(scaled["water"]["quantity"], 200.0);
+    assert_eq!(scaled["water"]["unit"]["abbreviation"], "ml");
+    assert_eq!(scaled["total_volume"]["quantity"], 1.2);
+    assert_eq!(scaled["total_volume"]["unit"]["abbreviation"], "l");
+}
+
+#[tokio::test]
+async fn rejects_invalid_scaling() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let recipe = negroni(&app, &user, 30.0, "ml").await;
+    let uri = format!("/recipes/{}/scale", recipe["id"].as_str().unwrap());
+
+    for query in [
+        "",
+        "?servings=2&volume=1",
+        "?servings=0",
+        "?servings=inf",
+        "?volume=NaN",
+        "?servings=2&dilution=NaN",
+        "?volume=1&unit=g",
+        "?servings=2&dilution=150",
+        "?servings=2&system=cubits",
+    ] {
+        let response = app.get(&format!("{uri}{query}"), Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "query {query}");
+    }
+}
+
+#[tokio::test]
+async fn recipes_of_another_user_are_hidden() {
+    let app = TestApp::spawn().await;
+    let owner = app.register().await;
+    let other = app.register().await;
+    let recipe = negroni(&app, &owner, 30.0, "ml").await;
+    let uri = format!("/recipes/{}", recipe["id"].as_str().unwrap());
+
+    for path in ["", "/ingredients", "/scale?servings=2"] {
+        let response = app.get(&format!("{uri}{path}"), Some(&other.token)).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND, "path {path}");
+    }
+
+    let ingredient = app.create_ingredient(&other, "gin", json!({})).await;
+    let response = app
+        .post(
+            &format!("{uri}/ingredients"),
+            Some(&other.token),
+            json!({
+                "ingredientId": ingredient["id"],
+                "quantity": 1,
+                "unitId": app.unit_id("oz").await,
+            }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    assert_eq!(
+        app.get("/recipes", Some(&other.token)).await.json(),
+        json!([])
+    );
+}
+
+#[tokio::test]
+async fn analyses_a_stirred_drink() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let mut lines = Vec::new();
+    for (name, abv, sugar) in [("gin", 40, 0), ("campari", 24, 24), ("vermouth", 16, 16)] {
+        let ingredient = app
+            .create_ingredient(&user, name, json!({ "abv": abv, "sugar": sugar }))
+            .await;
+        lines.push(ingredient);
+    }
+    let lines: Vec<(&Value, f64)> = lines.iter().map(|i| (i, 30.0)).collect();
+    let recipe = recipe(&app, &user, Some("stirred"), &lines).await;
+    assert_eq!(recipe["method"], "stirred");
+
+    let analysis = app
+        .get(
+            &format!("/recipes/{}/analysis", recipe["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(analysis.status, StatusCode::OK);
+
+    // 90ml at 26.67% stirred takes on 39.12% water, giving 125.21ml holding
+    // 24ml, or 18.94g, of ethanol.
+    let analysis = analysis.json();
+    assert_eq!(analysis["initial_volume"]["quantity"], 90.0);
+    assert_eq!(analysis["initial_volume"]["unit"]["abbreviation"], "ml");
+    assert_eq!(analysis["dilution"], 39.12);
+    assert_eq!(analysis["final_volume"]["quantity"], 125.21);
+    assert_eq!(analysis["abv"], 19.17);
+    assert_eq!(analysis["sugar"], 9.58);
+    assert_eq!(analysis["acid"], 0.0);
+    assert_eq!(analysis["standard_drinks"], 1.35);
+
+    let uk = app
+        .get(
+            &format!(
+                "/recipes/{}/analysis?standardDrink=8",
+                recipe["id"].as_str().unwrap()
+            ),
+            Some(&user.token),
+        )
+        .await
+        .json();
+    assert_eq!(uk["standard_drinks"], 2.37);
+}
+
+#[tokio::test]
+async fn analyses_through_compound_ingredients() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let create = |body: Value| {
+        let app = &app;
+        let token = user.token.clone();
+        async move { app.post("/ingredients", Some(&token), body).await.json() }
+    };
+    let rum = create(json!({ "name": "rum", "description": "", "abv": 40 })).await;
+    let falernum =
+        create(json!({ "name": "falernum", "description": "", "abv": 11, "sugar": 20 })).await;
+    let spiced = create(json!({ "name": "spiced rum", "description": "" })).await;
+    let lime = create(json!({ "name": "lime", "description": "", "acid": 6 })).await;
+    for (part, parts) in [(&rum, 3), (&falernum, 1)] {
+        app.post(
+            &format!(
+                "/ingredients/{}/ingredients",
+                spiced["id"].as_str().unwrap()
+            ),
+            Some(&user.token),
+            json!({ "ingredientId": part["id"], "parts": parts }),
+        )
+        .await;
+    }
+
+    // Three parts rum to one of falernum is 32.75% with 5g of sugar per 100ml.
+    let neat = recipe(&app, &user, None, &[(&spiced, 60.0)]).await;
+    let analysis = app
+        .get(
+            &format!("/recipes/{}/analysis", neat["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await
+        .json();
+    assert_eq!(analysis["dilution"], 0.0);
+    assert_eq!(analysis["final_volume"]["quantity"], 60.0);
+    assert_eq!(analysis["abv"], 32.75);
+    assert_eq!(analysis["sugar"], 5.0);
+
+    let shaken = recipe(
+        &app,
+        &user,
+        Some("shaken"),
+        &[(&spiced, 60.0), (&lime, 20.0)],
+    )
+    .await;
+    let analysis = app
+        .get(
+            &format!("/recipes/{}/analysis", shaken["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await
+        .json();
+    assert_eq!(analysis["method"], "shaken");
+    assert_eq!(analysis["dilution"], 72.54);
+    assert_eq!(analysis["abv"], 14.24);
+    assert_eq!(analysis["acid"], 0.87);
+}
+
+#[tokio::test]
+async fn rejects_unknown_methods() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+
+    let response = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Mojito", "description": "", "method": "muddled" }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,242 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+fn assert_close(actual: &Value, expected: f64) {
+    let actual = actual.as_f64().unwrap();
+    assert!(
+        (actual - expected).abs() < 0.01,
+        "expected {expected}, got {actual}"
+    );
+}
+
+async fn bar_and_recipe(app: &TestApp, user: &TestUser) -> (Value, Value) {
+    let bar = app.create_bar(user).await;
+    let gin = app.create_ingredient(user, "gin", json!({})).await;
+    let campari = app.create_ingredient(user, "campari", json!({})).await;
+    let dry = app.create_ingredient(user, "dry vermouth", json!({})).await;
+    let sweet = app
+        .create_ingredient(user, "sweet vermouth", json!({}))
+        .await;
+    let house = app
+        .create_ingredient(user, "house vermouth", json!({}))
+        .await;
+    let syrup = app.create_ingredient(user, "syrup", json!({})).await;
+
+    for (part, parts) in [(&dry, 2), (&sweet, 1)] {
+        app.post(
+            &format!("/ingredients/{}/ingredients", house["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({ "ingredientId": part["id"], "parts": parts }),
+        )
+        .await;
+    }
+    for (stocked, quantity, unit) in [
+        (&gin, 700.0, "ml"),
+        (&campari, 1.0, "l"),
+        (&dry, 500.0, "ml"),
+        (&sweet, 500.0, "ml"),
+    ] {
+        app.stock_ingredient(user, &bar, stocked, quantity, unit, json!({}))
+            .await;
+    }
+
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Negroni", "description": "" }),
+        )
+        .await
+        .json();
+    for (line, quantity, unit) in [
+        (&gin, 1, "oz"),
+        (&campari, 30, "ml"),
+        (&house, 30, "ml"),
+        (&syrup, 10, "ml"),
+    ] {
+        app.post(
+            &format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({
+                "ingredientId": line["id"],
+                "quantity": quantity,
+                "unitId": app.unit_id(unit).await,
+            }),
+        )
+        .await;
+    }
+
+    (bar, recipe)
+}
+
+async fn stock(app: &TestApp, user: &TestUser, bar: &Value) -> Value {
+    let stock = app
+        .get(
+            &format!("/bars/{}/ingredients", bar["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await
+        .json();
+
+    stock
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| {
+            (
+                s["ingredient"]["name"].as_str().unwrap().to_string(),
+                s["quantity"].clone(),
+            )
+        })
+        .collect::<serde_json::Map<_, _>>()
+        .into()
+}
+
+#[tokio::test]
+async fn serves_deduct_stock() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (bar, recipe) = bar_and_recipe(&app, &user).await;
+    let uri = format!("/bars/{}/serves", bar["id"].as_str().unwrap());
+
+    let serve = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "recipeId": recipe["id"], "servings": 2 }),
+        )
+        .await;
+    assert_eq!(serve.status, StatusCode::CREATED);
+
+    let serve = serve.json();
+    assert_eq!(serve["recipe_id"], recipe["id"]);
+    assert_eq!(serve["servings"], 2.0);
+    assert!(serve["undone_at"].is_null());
+
+    // Nothing is taken for the syrup, which the bar does not stock.
+    let deductions = serve["deductions"].as_array().unwrap();
+    assert_eq!(deductions.len(), 4);
+
+    // Two ounces of gin is 59.15ml, and 60ml of campari is 0.06l. The house
+    // vermouth comes out of the dry and sweet vermouth, two parts to one.
+    let stock = stock(&app, &user, &bar).await;
+    assert_close(&stock["gin"], 640.85);
+    assert_close(&stock["campari"], 0.94);
+    assert_close(&stock["dry vermouth"], 460.0);
+    assert_close(&stock["sweet vermouth"], 480.0);
+
+    let campari = deductions.iter().find(|d| d["name"] == "campari").unwrap();
+    assert_close(&campari["quantity"], 0.06);
+    assert_eq!(campari["unit"]["abbreviation"], "l");
+
+    let listed = app.get(&uri, Some(&user.token)).await.json();
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["id"], serve["id"]);
+    assert_eq!(listed[0]["deductions"].as_array().unwrap().len(), 4);
+
+    let retrieved = app
+        .get(
+            &format!("{uri}/{}", serve["id"].as_str().unwrap()),
+            Some(&user.token),
+        )
+        .await;
+    assert_eq!(retrieved.status, StatusCode::OK);
+    assert_eq!(retrieved.json()["servings"], 2.0);
+}
+
+#[tokio::test]
+async fn undoing_a_serve_restores_stock() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (bar, recipe) = bar_and_recipe(&app, &user).await;
+    let uri = format!("/bars/{}/serves", bar["id"].as_str().unwrap());
+
+    let serve = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "recipeId": recipe["id"], "servings": 1 }),
+        )
+        .await
+        .json();
+    let undo = format!("{uri}/{}/undo", serve["id"].as_str().unwrap());
+
+    let undone = app.post(&undo, Some(&user.token), json!({})).await;
+    assert_eq!(undone.status, StatusCode::OK);
+    assert!(undone.json()["undone_at"].is_string());
+
+    let stock = stock(&app, &user, &bar).await;
+    assert_close(&stock["gin"], 700.0);
+    assert_close(&stock["campari"], 1.0);
+    assert_close(&stock["dry vermouth"], 500.0);
+    assert_close(&stock["sweet vermouth"], 500.0);
+
+    let again = app.post(&undo, Some(&user.token), json!({})).await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+
+    // The undone serve stays in the history.
+    let listed = app.get(&uri, Some(&user.token)).await.json();
+    assert!(listed[0]["undone_at"].is_string());
+}
+
+#[tokio::test]
+async fn rejects_invalid_serves() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+    let (bar, recipe) = bar_and_recipe(&app, &user).await;
+    let uri = format!("/bars/{}/serves", bar["id"].as_str().unwrap());
+
+    let response = app
+        .post(
+            &uri,
+            Some(&user.token),
+            json!({ "recipeId": recipe["id"], "servings": 0 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = app
+        .post(
+            &uri,
+            Some(&other.token),
+            json!({ "recipeId": recipe["id"], "servings": 1 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app.get(&uri, Some(&other.token)).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let listed = app.get(&uri, Some(&user.token)).await.json();
+    assert!(listed.as_array().unwrap().is_empty());
+    assert_close(&stock(&app, &user, &bar).await["gin"], 700.0);
+}
+
+#[tokio::test]
+async fn concurrent_serves_all_deduct() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (bar, recipe) = bar_and_recipe(&app, &user).await;
+    let uri = format!("/bars/{}/serves", bar["id"].as_str().unwrap());
+    let serve = || {
+        app.post(
+            &uri,
+            Some(&user.token),
+            json!({ "recipeId": recipe["id"], "servings": 1 }),
+        )
+    };
+
+    let served = tokio::join!(serve(), serve(), serve(), serve());
+    for serve in [served.0, served.1, served.2, served.3] {
+        assert_eq!(serve.status, StatusCode::CREATED);
+    }
+
+    let stock = stock(&app, &user, &bar).await;
+    assert_close(&stock["campari"], 0.88);
+    assert_close(&stock["dry vermouth"], 420.0);
+}
@@ -0,0 +1,223 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+async fn bar(app: &TestApp, user: &TestUser) -> (Value, Value, Value) {
+    let bar = app.create_bar(user).await;
+    let gin = app.create_ingredient(user, "gin", json!({})).await;
+    let campari = app.create_ingredient(user, "campari", json!({})).await;
+    let vermouth = app.create_ingredient(user, "vermouth", json!({})).await;
+
+    app.stock_ingredient(
+        user,
+        &bar,
+        &gin,
+        300.0,
+        "ml",
+        json!({ "par": 1400, "minimum": 700, "bottleSize": 700 }),
+    )
+    .await;
+    app.stock_ingredient(
+        user,
+        &bar,
+        &campari,
+        1000.0,
+        "ml",
+        json!({ "par": 1000, "bottleSize": 1000 }),
+    )
+    .await;
+    app.stock_ingredient(user, &bar, &vermouth, 750.0, "ml", json!({}))
+        .await;
+
+    (bar, campari, vermouth)
+}
+
+#[tokio::test]
+async fn restocks_below_par() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (bar, _, _) = bar(&app, &user).await;
+    let uri = format!("/bars/{}/shopping-list", bar["id"].as_str().unwrap());
+
+    // 1100ml short of par is two 700ml bottles.
+    let list = app.get(&uri, Some(&user.token)).await;
+    assert_eq!(list.status, StatusCode::OK);
+
+    let list = list.json();
+    assert_eq!(list["items"].as_array().unwrap().len(), 1);
+    let gin = &list["items"][0];
+    assert_eq!(gin["name"], "gin");
+    assert_eq!(gin["in_stock"], 300.0);
+    assert_eq!(gin["needed"], 1100.0);
+    assert_eq!(gin["bottles"], 2);
+    assert_eq!(gin["quantity"], 1400.0);
+    assert_eq!(gin["unit"]["abbreviation"], "ml");
+
+    let text = app
+        .get(&format!("{uri}?format=text"), Some(&user.token))
+        .await;
+    assert_eq!(text.headers["content-type"], "text/plain; charset=utf-8");
+    assert_eq!(
+        String::from_utf8(text.body).unwrap(),
+        "Shopping list for home bar\n\n- gin: 2 x 700ml (1400ml)\n"
+    );
+
+    let csv = app
+        .get(&format!("{uri}?format=csv"), Some(&user.token))
+        .await;
+    assert_eq!(csv.headers["content-type"], "text/csv; charset=utf-8");
+    assert_eq!(
+        String::from_utf8(csv.body).unwrap(),
+        "ingredient,quantity,unit,bottles,bottle_size\r\ngin,1400,ml,2,700\r\n"
+    );
+}
+
+#[tokio::test]
+async fn adds_what_recipes_need() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (bar, campari, vermouth) = bar(&app, &user).await;
+    let syrup = app.create_ingredient(&user, "syrup, rich", json!({})).await;
+
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Americano", "description": "" }),
+        )
+        .await
+        .json();
+    for (line, quantity) in [(&campari, 30), (&vermouth, 30), (&syrup, 5)] {
+        app.post(
+            &format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap()),
+            Some(&user.token),
+            json!({
+                "ingredientId": line["id"],
+                "quantity": quantity,
+                "unitId": app.unit_id("ml").await,
+            }),
+        )
+        .await;
+    }
+    let uri = format!(
+        "/bars/{}/shopping-list?recipes={}&servings=30",
+        bar["id"].as_str().unwrap(),
+        recipe["id"].as_str().unwrap()
+    );
+
+    // Thirty Americanos take 900ml of campari, leaving it 900ml short of par,
+    // and 900ml of vermouth, 150ml more than is on hand.
+    let list = app.get(&uri, Some(&user.token)).await.json();
+    let items = list["items"].as_array().unwrap();
+    let names: Vec<&str> = items.iter().map(|i| i["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["campari", "gin", "syrup, rich", "vermouth"]);
+
+    assert_eq!(items[0]["for_recipes"], 900.0);
+    assert_eq!(items[0]["needed"], 900.0);
+    assert_eq!(items[0]["bottles"], 1);
+    assert_eq!(items[0]["quantity"], 1000.0);
+
+    assert!(items[2]["bar_ingredient_id"].is_null());
+    assert_eq!(items[2]["quantity"], 150.0);
+    assert!(items[2]["bottles"].is_null());
+
+    assert_eq!(items[3]["needed"], 150.0);
+    assert_eq!(items[3]["quantity"], 150.0);
+
+    let csv = app
+        .get(&format!("{uri}&format=csv"), Some(&user.token))
+        .await;
+    assert!(String::from_utf8(csv.body)
+        .unwrap()
+        .contains("\r\n\"syrup, rich\",150,ml,,\r\n"));
+}
+
+#[tokio::test]
+async fn sets_levels() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+    let bar = app.create_bar(&user).await;
+    let gin = app.create_ingredient(&user, "gin", json!({})).await;
+    let stock = app
+        .stock_ingredient(&user, &bar, &gin, 300.0, "ml", json!({}))
+        .await;
+    assert!(stock["par"].is_null());
+
+    let uri = format!(
+        "/bars/{}/ingredients/{}/levels",
+        bar["id"].as_str().unwrap(),
+        stock["id"].as_str().unwrap()
+    );
+    let levels = json!({ "par": 700, "minimum": 350, "bottleSize": 700 });
+
+    let updated = app.put(&uri, Some(&user.token), levels.clone()).await;
+    assert_eq!(updated.status, StatusCode::OK);
+
+    let updated = updated.json();
+    assert_eq!(updated["par"], 700.0);
+    assert_eq!(updated["minimum"], 350.0);
+    assert_eq!(updated["bottle_size"], 700.0);
+
+    for levels in [
+        json!({ "par": -1 }),
+        json!({ "par": 700, "minimum": 800 }),
+        json!({ "bottleSize": 0 }),
+    ] {
+        let response = app.put(&uri, Some(&user.token), levels.clone()).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{levels}");
+    }
+
+    let response = app.put(&uri, Some(&other.token), levels).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let list = format!("/bars/{}/shopping-list", bar["id"].as_str().unwrap());
+    let too_many = (0..51)
+        .map(|_| uuid::Uuid::new_v4().to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    for query in [
+        "?format=pdf".to_string(),
+        "?recipes=gin".to_string(),
+        "?servings=0".to_string(),
+        "?servings=inf".to_string(),
+        "?servings=NaN".to_string(),
+        format!("?recipes={too_many}"),
+    ] {
+        let response = app.get(&format!("{list}{query}"), Some(&user.token)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "query {query}");
+    }
+}
+
+#[tokio::test]
+async fn neutralises_formulas_in_csv() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let bar = app.create_bar(&user).await;
+    for name in ["=HYPERLINK(\"x\")", "\tcmd", "\rcmd"] {
+        let formula = app.create_ingredient(&user, name, json!({})).await;
+        app.stock_ingredient(&user, &bar, &formula, 0.0, "ml", json!({ "par": 700 }))
+            .await;
+    }
+
+    let csv = app
+        .get(
+            &format!(
+                "/bars/{}/shopping-list?format=csv",
+                bar["id"].as_str().unwrap()
+            ),
+            Some(&user.token),
+        )
+        .await;
+    let csv = String::from_utf8(csv.body).unwrap();
+    for line in [
+        "\"'=hyperlink(\"\"x\"\")\",700,ml,,\r\n",
+        "'\tcmd,700,ml,,\r\n",
+        "\"'\rcmd\",700,ml,,\r\n",
+    ] {
+        assert!(csv.contains(line), "{line:?} missing from {csv:?}");
+    }
+}
@@ -0,0 +1,37 @@
+// Every module brings its own copy of the test harness along.
+#![allow(clippy::duplicate_mod)]
+
+#[path = "auth.rs"]
+mod auth;
+#[path = "bars.rs"]
+mod bars;
+#[path = "bottles.rs"]
+mod bottles;
+#[path = "catalogue.rs"]
+mod catalogue;
+#[path = "categories.rs"]
+mod categories;
+#[path = "costs.rs"]
+mod costs;
+#[path = "health.rs"]
+mod health;
+#[path = "ingredients.rs"]
+mod ingredients;
+#[path = "media.rs"]
+mod media;
+#[path = "metrics.rs"]
+mod metrics;
+#[path = "products.rs"]
+mod products;
+#[path = "rate_limit.rs"]
+mod rate_limit;
+#[path = "recipes.rs"]
+mod recipes;
+#[path = "serves.rs"]
+mod serves;
+#[path = "shopping.rs"]
+mod shopping;
+#[path = "stock_takes.rs"]
+mod stock_takes;
+#[path = "units.rs"]
+mod units;
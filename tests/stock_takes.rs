@@ -0,0 +1,406 @@
+use axum::http::StatusCode;
+use serde_json::{json, Value};
+
+use common::{TestApp, TestUser};
+
+mod common;
+
+async fn bar(app: &TestApp, user: &TestUser) -> (String, Value, Value) {
+    let bar = app.create_bar(user).await;
+    let uri = format!("/bars/{}", bar["id"].as_str().unwrap());
+
+    let mut stock = serde_json::Map::new();
+    for (name, abv, quantity, bottle_size) in [
+        ("gin", Some(40), 1400.0, Some(700)),
+        ("vermouth", Some(16), 1000.0, None),
+        ("campari", Some(25), 1000.0, None),
+    ] {
+        let ingredient = app
+            .create_ingredient(user, name, json!({ "abv": abv }))
+            .await;
+        let bar_ingredient = app
+            .stock_ingredient(
+                user,
+                &bar,
+                &ingredient,
+                quantity,
+                "ml",
+                json!({ "bottleSize": bottle_size }),
+            )
+            .await;
+        stock.insert(name.to_string(), bar_ingredient["id"].clone());
+        stock.insert(format!("{name} ingredient"), ingredient["id"].clone());
+    }
+
+    let recipe = app
+        .post(
+            "/recipes",
+            Some(&user.token),
+            json!({ "name": "Gin neat", "description": "" }),
+        )
+        .await
+        .json();
+    app.post(
+        &format!("/recipes/{}/ingredients", recipe["id"].as_str().unwrap()),
+        Some(&user.token),
+        json!({
+            "ingredientId": stock["gin ingredient"],
+            "quantity": 50,
+            "unitId": app.unit_id("ml").await,
+        }),
+    )
+    .await;
+
+    (uri, stock.into(), recipe)
+}
+
+async fn serve(app: &TestApp, user: &TestUser, uri: &str, recipe: &Value, servings: u32) {
+    let serve = app
+        .post(
+            &format!("{uri}/serves"),
+            Some(&user.token),
+            json!({ "recipeId": recipe["id"], "servings": servings }),
+        )
+        .await;
+    assert_eq!(serve.status, StatusCode::CREATED);
+}
+
+async fn quantity(app: &TestApp, user: &TestUser, uri: &str, id: &Value) -> Value {
+    let stock = app
+        .get(&format!("{uri}/ingredients"), Some(&user.token))
+        .await
+        .json();
+
+    stock
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["id"] == *id)
+        .unwrap()["quantity"]
+        .clone()
+}
+
+#[tokio::test]
+async fn counts_reports_and_applies_variance() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (uri, stock, recipe) = bar(&app, &user).await;
+    serve(&app, &user, &uri, &recipe, 2).await;
+
+    let stock_take = app
+        .post(&format!("{uri}/stock-takes"), Some(&user.token), json!({}))
+        .await;
+    assert_eq!(stock_take.status, StatusCode::CREATED);
+
+    let stock_take = stock_take.json();
+    assert!(stock_take["closed_at"].is_null());
+    let stock_take_uri = format!("{uri}/stock-takes/{}", stock_take["id"].as_str().unwrap());
+
+    let again = app
+        .post(&format!("{uri}/stock-takes"), Some(&user.token), json!({}))
+        .await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+
+    // A full bottle and a partial one weighing 285g more than empty, at
+    // 0.95g/ml, is 1000ml.
+    let counted = app
+        .post(
+            &format!("{stock_take_uri}/counts"),
+            Some(&user.token),
+            json!({
+                "barIngredientId": stock["gin"],
+                "bottles": 1,
+                "weight": 785,
+                "tareWeight": 500,
+                "density": 0.95,
+            }),
+        )
+        .await;
+    assert_eq!(counted.status, StatusCode::OK);
+    assert_eq!(counted.json()["counts"][0]["quantity"], 1000.0);
+
+    // Counting again replaces the count. Gin at 40% is estimated at 0.948g/ml.
+    let counted = app
+        .post(
+            &format!("{stock_take_uri}/counts"),
+            Some(&user.token),
+            json!({
+                "barIngredientId": stock["gin"],
+                "bottles": 1,
+                "weight": 594.8,
+                "tareWeight": 500,
+            }),
+        )
+        .await
+        .json();
+    assert_eq!(counted["counts"].as_array().unwrap().len(), 1);
+    assert_eq!(counted["counts"][0]["quantity"], 800.0);
+
+    let counted = app
+        .post(
+            &format!("{stock_take_uri}/counts"),
+            Some(&user.token),
+            json!({
+                "barIngredientId": stock["vermouth"],
+                "quantity": 0.5,
+                "unitId": app.unit_id("l").await,
+            }),
+        )
+        .await
+        .json();
+    assert_eq!(counted["counts"][1]["name"], "vermouth");
+    assert_eq!(counted["counts"][1]["quantity"], 500.0);
+
+    let report = app
+        .post(
+            &format!("{stock_take_uri}/close"),
+            Some(&user.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(report.status, StatusCode::OK);
+
+    // Two serves took 100ml of gin, and another 500ml has gone missing.
+    let report = report.json();
+    assert!(report["since"].is_null());
+    assert!(report["until"].is_string());
+    let gin = &report["ingredients"][0];
+    assert_eq!(gin["name"], "gin");
+    assert_eq!(gin["expected"], 1300.0);
+    assert_eq!(gin["counted"], 800.0);
+    assert_eq!(gin["variance"], -500.0);
+    assert_eq!(gin["usage"], 100.0);
+    assert_eq!(gin["variance_of_usage"], -500.0);
+    let vermouth = &report["ingredients"][1];
+    assert_eq!(vermouth["variance"], -500.0);
+    assert_eq!(vermouth["usage"], 0.0);
+    assert!(vermouth["variance_of_usage"].is_null());
+    assert_eq!(report["uncounted"], json!([stock["campari"]]));
+
+    let response = app
+        .post(
+            &format!("{stock_take_uri}/counts"),
+            Some(&user.token),
+            json!({ "barIngredientId": stock["gin"], "quantity": 1 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    // A serve after closing stays deducted once the counts are applied.
+    serve(&app, &user, &uri, &recipe, 1).await;
+    let applied = app
+        .post(
+            &format!("{stock_take_uri}/apply"),
+            Some(&user.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(applied.status, StatusCode::OK);
+    let applied = applied.json();
+    assert!(applied["applied_at"].is_string());
+    assert_eq!(applied["clamped"], json!([]));
+
+    assert_eq!(quantity(&app, &user, &uri, &stock["gin"]).await, 750.0);
+    assert_eq!(quantity(&app, &user, &uri, &stock["vermouth"]).await, 500.0);
+    assert_eq!(quantity(&app, &user, &uri, &stock["campari"]).await, 1000.0);
+
+    let again = app
+        .post(
+            &format!("{stock_take_uri}/apply"),
+            Some(&user.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(again.status, StatusCode::CONFLICT);
+
+    // The next stock take counts usage from when this one closed.
+    let next = app
+        .post(&format!("{uri}/stock-takes"), Some(&user.token), json!({}))
+        .await
+        .json();
+    let next_uri = format!("{uri}/stock-takes/{}", next["id"].as_str().unwrap());
+    app.post(
+        &format!("{next_uri}/counts"),
+        Some(&user.token),
+        json!({ "barIngredientId": stock["gin"], "quantity": 750 }),
+    )
+    .await;
+
+    let report = app
+        .get(&format!("{next_uri}/report"), Some(&user.token))
+        .await
+        .json();
+    assert!(report["until"].is_null());
+    assert_eq!(report["ingredients"][0]["expected"], 750.0);
+    assert_eq!(report["ingredients"][0]["variance"], 0.0);
+    assert_eq!(report["ingredients"][0]["usage"], 50.0);
+
+    let listed = app
+        .get(&format!("{uri}/stock-takes"), Some(&user.token))
+        .await
+        .json();
+    assert_eq!(listed.as_array().unwrap().len(), 2);
+    assert_eq!(listed[0]["id"], next["id"]);
+    assert_eq!(report["since"], listed[1]["closed_at"]);
+}
+
+#[tokio::test]
+async fn rejects_invalid_counts() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let other = app.register().await;
+    let (uri, stock, _) = bar(&app, &user).await;
+
+    let stock_take = app
+        .post(&format!("{uri}/stock-takes"), Some(&user.token), json!({}))
+        .await
+        .json();
+    let stock_take_uri = format!("{uri}/stock-takes/{}", stock_take["id"].as_str().unwrap());
+
+    let response = app
+        .post(
+            &format!("{stock_take_uri}/apply"),
+            Some(&user.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+
+    let grams = app.unit_id("g").await;
+    for count in [
+        json!({ "barIngredientId": stock["gin"] }),
+        json!({ "barIngredientId": stock["gin"], "quantity": -1 }),
+        json!({ "barIngredientId": stock["gin"], "weight": 800 }),
+        json!({ "barIngredientId": stock["gin"], "weight": 400, "tareWeight": 500 }),
+        json!({ "barIngredientId": stock["gin"], "weight": 800, "tareWeight": 500, "density": 0 }),
+        json!({ "barIngredientId": stock["vermouth"], "bottles": 1 }),
+        json!({ "barIngredientId": stock["gin"], "quantity": 100, "unitId": grams }),
+    ] {
+        let response = app
+            .post(
+                &format!("{stock_take_uri}/counts"),
+                Some(&user.token),
+                count.clone(),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST, "{count}");
+    }
+
+    let response = app
+        .post(
+            &format!("{stock_take_uri}/counts"),
+            Some(&other.token),
+            json!({ "barIngredientId": stock["gin"], "quantity": 100 }),
+        )
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let response = app
+        .get(&format!("{stock_take_uri}/report"), Some(&other.token))
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+    let report = app
+        .get(&format!("{stock_take_uri}/report"), Some(&user.token))
+        .await
+        .json();
+    assert!(report["ingredients"].as_array().unwrap().is_empty());
+    assert_eq!(report["uncounted"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn rejects_applying_a_superseded_stock_take() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (uri, stock, _) = bar(&app, &user).await;
+
+    let mut stock_take_uris = Vec::new();
+    for quantity in [1300, 1200] {
+        let stock_take = app
+            .post(&format!("{uri}/stock-takes"), Some(&user.token), json!({}))
+            .await
+            .json();
+        let stock_take_uri = format!("{uri}/stock-takes/{}", stock_take["id"].as_str().unwrap());
+        app.post(
+            &format!("{stock_take_uri}/counts"),
+            Some(&user.token),
+            json!({ "barIngredientId": stock["gin"], "quantity": quantity }),
+        )
+        .await;
+        let closed = app
+            .post(
+                &format!("{stock_take_uri}/close"),
+                Some(&user.token),
+                json!({}),
+            )
+            .await;
+        assert_eq!(closed.status, StatusCode::OK);
+        stock_take_uris.push(stock_take_uri);
+    }
+
+    let applied = app
+        .post(
+            &format!("{}/apply", stock_take_uris[1]),
+            Some(&user.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(applied.status, StatusCode::OK);
+
+    let superseded = app
+        .post(
+            &format!("{}/apply", stock_take_uris[0]),
+            Some(&user.token),
+            json!({}),
+        )
+        .await;
+    assert_eq!(superseded.status, StatusCode::CONFLICT);
+    assert_eq!(quantity(&app, &user, &uri, &stock["gin"]).await, 1200.0);
+}
+
+#[tokio::test]
+async fn reports_stock_that_would_go_below_zero() {
+    let app = TestApp::spawn().await;
+    let user = app.register().await;
+    let (uri, stock, recipe) = bar(&app, &user).await;
+
+    let stock_take = app
+        .post(&format!("{uri}/stock-takes"), Some(&user.token), json!({}))
+        .await
+        .json();
+    let stock_take_uri = format!("{uri}/stock-takes/{}", stock_take["id"].as_str().unwrap());
+    app.post(
+        &format!("{stock_take_uri}/counts"),
+        Some(&user.token),
+        json!({ "barIngredientId": stock["gin"], "quantity": 100 }),
+    )
+    .await;
+    app.post(
+        &format!("{stock_take_uri}/close"),
+        Some(&user.token),
+        json!({}),
+    )
+    .await;
+
+    // 200ml served since closing leaves 1200ml, 100ml short of the 1300ml
+    // that went missing. Applying it twice at once applies it only once.
+    serve(&app, &user, &uri, &recipe, 4).await;
+    let apply_uri = format!("{stock_take_uri}/apply");
+    let (first, second) = tokio::join!(
+        app.post(&apply_uri, Some(&user.token), json!({})),
+        app.post(&apply_uri, Some(&user.token), json!({})),
+    );
+    let mut statuses = [first.status, second.status];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+    let applied = if first.status == StatusCode::OK {
+        first
+    } else {
+        second
+    };
+    assert_eq!(
+        applied.json()["clamped"],
+        json!([{ "bar_ingredient_id": stock["gin"], "shortfall": 100.0 }])
+    );
+    assert_eq!(quantity(&app, &user, &uri, &stock["gin"]).await, 0.0);
+}
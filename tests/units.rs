@@ -0,0 +1,37 @@
+use axum::http::StatusCode;
+
+use common::TestApp;
+
+mod common;
+
+#[tokio::test]
+async fn lists_the_seeded_units() {
+    let app = TestApp::spawn().await;
+
+    let response = app.get("/units", None).await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let units = response.json();
+    let units = units.as_array().unwrap();
+    let ml = units
+        .iter()
+        .find(|unit| unit["abbreviation"] == "ml")
+        .expect("milliliters are seeded");
+    assert_eq!(ml["name"], "milliliter");
+    assert_eq!(ml["system"], "metric");
+    assert_eq!(ml["dimension"], "volume");
+    assert_eq!(ml["base_quantity"], 1.0);
+
+    let oz = units
+        .iter()
+        .find(|unit| unit["abbreviation"] == "oz")
+        .expect("fluid ounces are seeded");
+    assert_eq!(oz["system"], "imperial");
+    assert_eq!(oz["base_quantity"], 29.5735);
+
+    let g = units
+        .iter()
+        .find(|unit| unit["abbreviation"] == "g")
+        .expect("grams are seeded");
+    assert_eq!(g["dimension"], "mass");
+}